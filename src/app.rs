@@ -1,9 +1,11 @@
 use crate::app_modes;
-use crate::config::TermvizConfig;
+use crate::config::{TermvizConfig, ZRange};
 use crate::footprint::get_footprint;
 use crate::listeners::Listeners;
+use crate::source;
+use crate::theme::Theme;
 use crossterm::{
-    event::EnableMouseCapture,
+    event::{EnableMouseCapture, KeyCode, MouseEvent},
     execute,
     terminal::{enable_raw_mode, size, EnterAlternateScreen},
 };
@@ -13,27 +15,36 @@ use std::convert::TryFrom;
 use std::io;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Instant;
 use tui::backend::Backend;
 use tui::backend::CrosstermBackend;
-use tui::layout::{Alignment, Constraint, Direction, Layout};
-use tui::style::{Color, Modifier, Style};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
-use tui::widgets::{Block, Borders, Paragraph, Row, Table, Wrap};
+use tui::widgets::{Block, Borders, Clear, Paragraph, Row, Table, Wrap};
 use tui::{Frame, Terminal};
 
 pub struct App<B: Backend> {
     mode: usize,
     show_help: bool,
+    show_layers: bool,
+    /// Index of the selected layer in the layers overlay.
+    selected_layer: usize,
     keymap: HashMap<String, String>,
     app_modes: Vec<Box<dyn app_modes::BaseMode<B>>>,
+    viewport: Rc<RefCell<app_modes::viewport::Viewport>>,
+    theme: Theme,
+    start_time: Instant,
 }
 
 impl<B: Backend> App<B> {
-    pub fn new(tf_listener: Arc<rustros_tf::TfListener>, config: TermvizConfig) -> App<B> {
+    pub fn new(tf_listener: Arc<source::TfListener>, config: TermvizConfig) -> App<B> {
         let config_copy = config.clone();
+        let theme = Theme::from_config(&config.theme);
         let listeners = Listeners::new(
             tf_listener.clone(),
             config.fixed_frame.clone(),
+            config.background_color.clone(),
             config.laser_topics,
             config.marker_topics,
             config.marker_array_topics,
@@ -43,10 +54,42 @@ impl<B: Backend> App<B> {
             config.pointcloud2_topics,
             config.polygon_stamped_topics,
             config.path_topics,
+            config.odometry_topics,
+            config.range_topics,
+            config.grid_cells_topics,
+            config.point_stamped_topics,
+            config.imu_topics,
+            config.pose_with_covariance_topics,
+            config.twist_stamped_topics,
+            config.wrench_stamped_topics,
+            config.navsat_fix_topics,
+            config.detection3d_topics,
+            config.camera_info_topics,
+            config.battery_topics,
+            config.move_base_status_topics,
+            config.field_topics,
         );
         let viewport = Rc::new(RefCell::new(app_modes::viewport::Viewport::new(
             &config.fixed_frame,
             &config.robot_frame,
+            &config.follow_frames,
+            &config.bookmarks,
+            &config.grid,
+            &config.trail,
+            &config.minimap,
+            &config.localization_quality,
+            &config.export,
+            &config.record,
+            &config.background_color,
+            config.fill_background,
+            &config.footprint_color,
+            (&config.x_axis_color, &config.y_axis_color),
+            &theme,
+            &config.canvas_marker,
+            &config.mode_canvas_markers,
+            config.robot_up,
+            config.view_rotation,
+            &config.draw_order,
             tf_listener,
             &config.visible_area,
             &get_footprint(),
@@ -55,21 +98,132 @@ impl<B: Backend> App<B> {
             listeners,
             size().unwrap(),
         )));
-        let send_pose = Box::new(app_modes::send_pose::SendPose::new(
-            &config.send_pose_topics,
+        let marker_namespaces = Box::new(app_modes::marker_namespaces::MarkerNamespaces::new(
             viewport.clone(),
         ));
-        let teleop = Box::new(app_modes::teleoperate::Teleoperate::new(
-            viewport,
-            config.teleop,
+        let image_view = Box::new(app_modes::image_view::ImageView::new(
+            config.image_topics.clone(),
+            theme.clone(),
+        ));
+        let diagnostics = Box::new(app_modes::diagnostics::Diagnostics::new(
+            config.diagnostics,
+            theme.clone(),
+        ));
+        let logs = Box::new(app_modes::logs::Logs::new(
+            config.logs.clone(),
+            theme.clone(),
+        ));
+        let measure = Box::new(app_modes::measure::Measure::new(viewport.clone()));
+        let multi_view = Box::new(app_modes::multi_view::MultiView::new(
+            viewport.clone(),
+            config.multi_view,
+        ));
+        let dashboard = Box::new(app_modes::dashboard::Dashboard::new(
+            viewport.clone(),
+            config.dashboard,
+            config.logs,
+            theme.clone(),
         ));
-        let topic_manager = Box::new(app_modes::topic_managment::TopicManager::new(config_copy));
-        let image_view = Box::new(app_modes::image_view::ImageView::new(config.image_topics));
+        // Without a ROS master, only the modes showing the received data are available. The other
+        // ones publish or query the master when they are built, so they must not be built then.
+        let app_modes: Vec<Box<dyn app_modes::BaseMode<B>>> = if source::reading_bags() {
+            let viewer = Box::new(app_modes::viewer::Viewer::new("Viewer", viewport.clone()));
+            let topic_monitor = Box::new(app_modes::topic_monitor::TopicMonitor::new(
+                config.topic_monitor,
+                theme.clone(),
+            ));
+            vec![
+                viewer,
+                image_view,
+                diagnostics,
+                marker_namespaces,
+                logs,
+                topic_monitor,
+                measure,
+                multi_view,
+                dashboard,
+            ]
+        } else {
+            let send_pose = Box::new(app_modes::send_pose::SendPose::new(
+                &config.send_pose_topics,
+                &config.send_pose_frames,
+                &config.pose_presets,
+                config.goal_queue,
+                viewport.clone(),
+            ));
+            let teleop = Box::new(app_modes::teleoperate::Teleoperate::new(
+                viewport.clone(),
+                config.teleop.clone(),
+            ));
+            let topic_manager = Box::new(app_modes::topic_managment::TopicManager::new(
+                config_copy,
+                theme.clone(),
+            ));
+            let joint_teleop = Box::new(app_modes::joint_teleop::JointTeleop::new(
+                config.joint_teleop,
+                theme.clone(),
+            ));
+            let node_manager = Box::new(app_modes::node_manager::NodeManager::new(theme.clone()));
+            let bag_playback = Box::new(app_modes::bag_playback::BagPlayback::new(
+                config.bag_playback,
+                theme.clone(),
+            ));
+            let param_browser =
+                Box::new(app_modes::param_browser::ParamBrowser::new(theme.clone()));
+            let waypoint_editor = Box::new(app_modes::waypoint_editor::WaypointEditor::new(
+                viewport.clone(),
+                config.waypoint_editor,
+            ));
+            let polygon_editor = Box::new(app_modes::polygon_editor::PolygonEditor::new(
+                viewport.clone(),
+                config.polygon_editor,
+            ));
+            let split_view = Box::new(app_modes::split_view::SplitView::new(
+                viewport.clone(),
+                app_modes::teleoperate::Teleoperate::new(viewport.clone(), config.teleop),
+                app_modes::image_view::ImageView::new(config.image_topics, theme.clone()),
+                config.split_view,
+            ));
+            let map_editor = Box::new(app_modes::map_editor::MapEditor::new(
+                viewport.clone(),
+                config.map_editor,
+            ));
+            let topic_monitor = Box::new(app_modes::topic_monitor::TopicMonitor::new(
+                config.topic_monitor,
+                theme.clone(),
+            ));
+            vec![
+                send_pose,
+                teleop,
+                image_view,
+                topic_manager,
+                diagnostics,
+                joint_teleop,
+                marker_namespaces,
+                logs,
+                topic_monitor,
+                node_manager,
+                param_browser,
+                bag_playback,
+                waypoint_editor,
+                polygon_editor,
+                measure,
+                split_view,
+                multi_view,
+                dashboard,
+                map_editor,
+            ]
+        };
         App {
             mode: 1,
             show_help: false,
+            show_layers: false,
+            selected_layer: 0,
             keymap: config.key_mapping,
-            app_modes: vec![send_pose, teleop, image_view, topic_manager],
+            app_modes,
+            viewport,
+            theme,
+            start_time: Instant::now(),
         }
     }
 
@@ -83,6 +237,7 @@ impl<B: Backend> App<B> {
     }
 
     pub fn run(&mut self) {
+        self.viewport.borrow_mut().run();
         self.app_modes[self.mode - 1].run();
     }
 
@@ -91,6 +246,148 @@ impl<B: Backend> App<B> {
             self.show_help(f);
         } else {
             self.app_modes[self.mode - 1].draw(f);
+            if self.show_layers {
+                self.draw_layers(f);
+            }
+        }
+        self.draw_alerts(f);
+    }
+
+    /// Draws the overlay listing the layers of the viewport with their visibility.
+    fn draw_layers(&self, f: &mut Frame<B>) {
+        let layers = self.viewport.borrow().layers();
+        let size = f.size();
+        let width = (size.width / 2).max(30).min(size.width);
+        let height = (layers.len() as u16 + 2).max(3).min(size.height);
+        let area = Rect::new(
+            (size.width - width) / 2,
+            (size.height - height) / 2,
+            width,
+            height,
+        );
+        let mut lines: Vec<Spans> = layers
+            .into_iter()
+            .enumerate()
+            .map(|(i, (layer, visible))| {
+                let checkbox = if visible { "[x] " } else { "[ ] " };
+                let z_range = match self.viewport.borrow().z_range(&layer) {
+                    Some(z_range) if z_range != ZRange::default() => {
+                        format!(" ({})", z_range.describe())
+                    }
+                    _ => String::new(),
+                };
+                let style = if i == self.selected_layer {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Spans::from(Span::styled(
+                    checkbox.to_string() + &layer + &z_range,
+                    style,
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Spans::from("No topic"));
+        }
+        let paragraph = Paragraph::new(lines).block(
+            Block::default()
+                .title(" Layers, from bottom to top ")
+                .borders(Borders::ALL),
+        );
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    /// Moves the selection or toggles the selected layer of the layers overlay.
+    fn handle_layers_input(&mut self, input: &String) {
+        let layers = self.viewport.borrow().layers();
+        match input.as_str() {
+            app_modes::input::UP if !layers.is_empty() => {
+                self.selected_layer = (self.selected_layer + layers.len() - 1) % layers.len()
+            }
+            app_modes::input::DOWN if !layers.is_empty() => {
+                self.selected_layer = (self.selected_layer + 1) % layers.len()
+            }
+            app_modes::input::CONFIRM => {
+                if let Some((layer, _)) = layers.get(self.selected_layer) {
+                    self.viewport.borrow_mut().toggle_layer(layer);
+                }
+            }
+            app_modes::input::MOVE_FORWARD | app_modes::input::MOVE_BACKWARD => {
+                let forward = input == app_modes::input::MOVE_FORWARD;
+                self.selected_layer = self
+                    .viewport
+                    .borrow_mut()
+                    .move_layer(self.selected_layer, forward);
+            }
+            app_modes::input::LEFT
+            | app_modes::input::RIGHT
+            | app_modes::input::DECREMENT_STEP
+            | app_modes::input::INCREMENT_STEP => {
+                if let Some((layer, _)) = layers.get(self.selected_layer) {
+                    let (min_steps, max_steps) = match input.as_str() {
+                        app_modes::input::LEFT => (-1.0, 0.0),
+                        app_modes::input::RIGHT => (1.0, 0.0),
+                        app_modes::input::DECREMENT_STEP => (0.0, -1.0),
+                        _ => (0.0, 1.0),
+                    };
+                    self.viewport
+                        .borrow_mut()
+                        .adjust_z_range(layer, min_steps, max_steps);
+                }
+            }
+            app_modes::input::DELETE => {
+                if let Some((layer, _)) = layers.get(self.selected_layer) {
+                    self.viewport
+                        .borrow_mut()
+                        .set_z_range(layer, ZRange::default());
+                }
+            }
+            app_modes::input::CANCEL => self.show_layers = false,
+            _ => (),
+        }
+    }
+
+    /// Draws a flashing banner at the bottom of the screen if monitored values are out of range.
+    fn draw_alerts(&self, f: &mut Frame<B>) {
+        let alerts = self.viewport.borrow().alerts();
+        if alerts.is_empty() {
+            return;
+        }
+        let size = f.size();
+        let height = (alerts.len() as u16).min(size.height);
+        let area = Rect::new(0, size.height - height, size.width, height);
+        let flash_on = self.start_time.elapsed().as_millis() / 500 % 2 == 0;
+        let style = if flash_on {
+            Style::default()
+                .fg(self.theme.text)
+                .bg(self.theme.error)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(self.theme.error)
+                .add_modifier(Modifier::BOLD)
+        };
+        let lines: Vec<Spans> = alerts
+            .into_iter()
+            .map(|alert| Spans::from(format!("WARNING: {}", alert)))
+            .collect();
+        f.render_widget(Clear, area);
+        f.render_widget(Paragraph::new(lines).style(style), area);
+    }
+
+    pub fn is_capturing_text(&self) -> bool {
+        !self.show_help && !self.show_layers && self.app_modes[self.mode - 1].is_capturing_text()
+    }
+
+    pub fn handle_text_input(&mut self, key: KeyCode) {
+        self.app_modes[self.mode - 1].handle_text_input(key);
+    }
+
+    pub fn handle_mouse(&mut self, event: MouseEvent) {
+        if !self.show_help && !self.show_layers {
+            self.app_modes[self.mode - 1].handle_mouse(event);
         }
     }
 
@@ -118,6 +415,14 @@ impl<B: Backend> App<B> {
                 app_modes::input::MODE_7 => maybe_new_mode = Some(7),
                 app_modes::input::MODE_8 => maybe_new_mode = Some(8),
                 app_modes::input::MODE_9 => maybe_new_mode = Some(9),
+                app_modes::input::MODE_10 => maybe_new_mode = Some(10),
+                app_modes::input::NEXT_MODE => {
+                    maybe_new_mode = Some(self.mode % self.app_modes.len() + 1)
+                }
+                app_modes::input::PREVIOUS_MODE => {
+                    maybe_new_mode =
+                        Some((self.mode + self.app_modes.len() - 2) % self.app_modes.len() + 1)
+                }
                 _ => {}
             },
         }
@@ -135,6 +440,19 @@ impl<B: Backend> App<B> {
         if self.show_help {
             return;
         }
+        if input == app_modes::input::SHOW_LAYERS {
+            self.show_layers = !self.show_layers;
+            return;
+        }
+        if self.show_layers {
+            self.handle_layers_input(input);
+            return;
+        }
+        if input == app_modes::input::NEXT_CANVAS_MARKER {
+            let mode = self.app_modes[self.mode - 1].get_name();
+            self.viewport.borrow_mut().next_canvas_marker(&mode);
+            return;
+        }
         self.app_modes[self.mode - 1].handle_input(input);
     }
 
@@ -158,10 +476,28 @@ impl<B: Backend> App<B> {
         key_bindings_raw.extend(self.app_modes[self.mode - 1].get_keymap());
         key_bindings_raw.extend([
             ["".to_string(), "".to_string()],
+            [
+                app_modes::input::NEXT_MODE.to_string(),
+                "Switches to the next mode.".to_string(),
+            ],
+            [
+                app_modes::input::PREVIOUS_MODE.to_string(),
+                "Switches to the previous mode.".to_string(),
+            ],
             [
                 app_modes::input::SHOW_HELP.to_string(),
                 "Opens/closes this page.".to_string(),
             ],
+            [
+                app_modes::input::SHOW_LAYERS.to_string(),
+                "Opens/closes the list of layers, where the selected one is hidden or shown with the confirm key, and moved over or under the others with the move keys. The heights drawn of point clouds and markers are adjusted with the left/right keys for the lower bound, the step keys for the upper bound, and reset with the delete key."
+                    .to_string(),
+            ],
+            [
+                app_modes::input::NEXT_CANVAS_MARKER.to_string(),
+                "Draws the viewport of the current mode with the next marker among braille, dots and blocks."
+                    .to_string(),
+            ],
             ["Ctrl+c".to_string(), "Quits the application.".to_string()],
         ]);
         for e in &mut key_bindings_raw {
@@ -170,16 +506,35 @@ impl<B: Backend> App<B> {
                 None => (),
             }
         }
+        let mode_keys = |input: &str| {
+            self.keymap
+                .get(input)
+                .cloned()
+                .unwrap_or_else(|| input.to_string())
+        };
         for i in 0..self.app_modes.len() {
+            // Only the first 10 modes have a number key
+            if i >= 10 {
+                key_bindings_raw[i][0] = format!(
+                    "{}/{}",
+                    mode_keys(app_modes::input::NEXT_MODE),
+                    mode_keys(app_modes::input::PREVIOUS_MODE)
+                );
+                key_bindings_raw[i][1] += " Reachable with the next and previous mode keys only.";
+                continue;
+            }
             if key_bindings_raw[i][0].contains("Switch") {
-                key_bindings_raw[i][0] = (i + 1).to_string();
+                key_bindings_raw[i][0] = ((i + 1) % 10).to_string();
             } else {
-                key_bindings_raw[i][0] = (i + 1).to_string() + ", " + &key_bindings_raw[i][0];
+                key_bindings_raw[i][0] =
+                    ((i + 1) % 10).to_string() + ", " + &key_bindings_raw[i][0];
             }
         }
         let title_text = vec![Spans::from(Span::styled(
             "TermViz - ".to_string() + &self.app_modes[self.mode - 1].get_name(),
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Style::default()
+                .fg(self.theme.title)
+                .add_modifier(Modifier::BOLD),
         ))];
 
         // Define areas from text
@@ -211,13 +566,13 @@ impl<B: Backend> App<B> {
         // Widget creation
         let title = Paragraph::new(title_text)
             .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
 
         let explanation = Paragraph::new(explanation_spans)
             .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
 
@@ -227,9 +582,11 @@ impl<B: Backend> App<B> {
                     .title(" Key binding ")
                     .borders(Borders::ALL),
             )
-            .header(Row::new(vec!["Key", "Function"]).style(Style::default().fg(Color::Yellow)))
+            .header(
+                Row::new(vec!["Key", "Function"]).style(Style::default().fg(self.theme.highlight)),
+            )
             .widths(&[Constraint::Min(9), Constraint::Percentage(100)])
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .column_spacing(10);
         f.render_widget(title, areas[0]);
         f.render_widget(explanation, areas[1]);
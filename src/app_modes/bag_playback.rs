@@ -0,0 +1,271 @@
+//! Bag playback mode controls a `rosbag play` process started by termviz.
+//!
+//! The process is started paused and driven through its keyboard interface,
+//! the bag time is read from the clock it publishes.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::BagPlaybackConfig;
+use crate::source;
+use crate::theme::Theme;
+use rosrust_msg::rosgraph_msgs::Clock;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, RwLock};
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Wrap};
+use tui::Frame;
+
+/// Playback rates selectable with the step keys.
+const RATES: [f64; 7] = [0.1, 0.25, 0.5, 1.0, 2.0, 5.0, 10.0];
+
+/// First and last bag time received on the clock topic.
+#[derive(Default)]
+struct ClockData {
+    start: Option<f64>,
+    current: Option<f64>,
+}
+
+pub struct BagPlayback {
+    config: BagPlaybackConfig,
+    process: Option<Child>,
+    paused: bool,
+    rate_index: usize,
+    clock: Arc<RwLock<ClockData>>,
+    error: Option<String>,
+    theme: Theme,
+    _subscriber: source::Subscriber,
+}
+
+impl BagPlayback {
+    pub fn new(config: BagPlaybackConfig, theme: Theme) -> BagPlayback {
+        let clock = Arc::new(RwLock::new(ClockData::default()));
+        let cb_clock = clock.clone();
+        let sub = source::subscribe("clock", 1, move |msg: Clock| {
+            let time = msg.clock.seconds();
+            let mut clock = cb_clock.write().unwrap();
+            clock.start.get_or_insert(time);
+            clock.current = Some(time);
+        })
+        .unwrap();
+        BagPlayback {
+            rate_index: RATES.iter().position(|&r| r == 1.0).unwrap(),
+            config,
+            process: None,
+            paused: true,
+            clock,
+            error: None,
+            theme,
+            _subscriber: sub,
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        RATES[self.rate_index]
+    }
+
+    /// Returns the time elapsed since the beginning of the bag, if known.
+    fn offset(&self) -> Option<f64> {
+        let clock = self.clock.read().unwrap();
+        Some(clock.current? - clock.start?)
+    }
+
+    /// Starts a paused `rosbag play` process from the given offset in the bag.
+    fn start(&mut self, offset: f64) {
+        self.stop();
+        let process = Command::new("rosbag")
+            .arg("play")
+            .arg("--pause")
+            .arg("--clock")
+            .arg(format!("--rate={}", self.rate()))
+            .arg(format!("--start={}", offset))
+            .args(&self.config.bags)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        match process {
+            Ok(process) => {
+                self.process = Some(process);
+                self.paused = true;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(format!("Could not run rosbag: {}", e)),
+        }
+    }
+
+    fn stop(&mut self) {
+        if let Some(mut process) = self.process.take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+    }
+
+    /// Sends a key to the keyboard interface of rosbag play.
+    fn send_key(&mut self, key: &[u8]) {
+        if let Some(stdin) = self.process.as_mut().and_then(|p| p.stdin.as_mut()) {
+            if stdin.write_all(key).and_then(|_| stdin.flush()).is_err() {
+                self.error = Some("Playback is not running".to_string());
+            }
+        }
+    }
+
+    fn is_running(&mut self) -> bool {
+        match self.process.as_mut().map(|p| p.try_wait()) {
+            Some(Ok(None)) => true,
+            _ => false,
+        }
+    }
+
+    /// Changes the rate by restarting the playback from the current position.
+    fn change_rate(&mut self, step: i64) {
+        let index = (self.rate_index as i64 + step).clamp(0, RATES.len() as i64 - 1) as usize;
+        if index == self.rate_index {
+            return;
+        }
+        self.rate_index = index;
+        if self.is_running() {
+            let paused = self.paused;
+            let offset = self.offset().unwrap_or(0.0);
+            self.start(offset);
+            if !paused {
+                self.send_key(b" ");
+                self.paused = false;
+            }
+        }
+    }
+}
+
+impl Drop for BagPlayback {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl AppMode for BagPlayback {
+    fn run(&mut self) {
+        if self.process.is_some() && !self.is_running() {
+            self.process = None;
+            self.paused = true;
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        match input.as_str() {
+            input::CONFIRM => {
+                if self.is_running() {
+                    self.send_key(b" ");
+                    self.paused = !self.paused;
+                } else {
+                    *self.clock.write().unwrap() = ClockData::default();
+                    self.start(0.0);
+                }
+            }
+            input::NEXT => {
+                if self.paused {
+                    self.send_key(b"s");
+                }
+            }
+            input::INCREMENT_STEP => self.change_rate(1),
+            input::DECREMENT_STEP => self.change_rate(-1),
+            input::CANCEL => self.stop(),
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode plays the configured bags with rosbag play, publishing the clock."
+                .to_string(),
+            "The playback starts paused, the current bag time is shown in the header.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::CONFIRM.to_string(),
+                "Starts the playback, or pauses and resumes it.".to_string(),
+            ],
+            [
+                input::NEXT.to_string(),
+                "Publishes the next message while paused.".to_string(),
+            ],
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the playback rate.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the playback rate.".to_string(),
+            ],
+            [input::CANCEL.to_string(), "Stops the playback.".to_string()],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Bag Playback".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for BagPlayback {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let state = if self.process.is_none() {
+            "STOPPED"
+        } else if self.paused {
+            "PAUSED"
+        } else {
+            "PLAYING"
+        };
+        let clock = self.clock.read().unwrap();
+        let time = match (clock.current, clock.start) {
+            (Some(current), Some(start)) => format!("{:.3} (+{:.3}s)", current, current - start),
+            _ => "-".to_string(),
+        };
+        let mut header_spans = vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " - {} - Time: {} - Rate: {}x",
+                state,
+                time,
+                self.rate()
+            )),
+        ];
+        if let Some(error) = &self.error {
+            header_spans.push(Span::styled(
+                format!(" - {}", error),
+                Style::default().fg(self.theme.error),
+            ));
+        }
+        let header = Paragraph::new(Spans::from(header_spans))
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::default().fg(self.theme.text))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let bags: Vec<Spans> = self
+            .config
+            .bags
+            .iter()
+            .map(|bag| Spans::from(format!(" * {}", bag)))
+            .collect();
+        let body =
+            Paragraph::new(bags).block(Block::default().title(" Bags ").borders(Borders::ALL));
+        f.render_widget(body, chunks[1]);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for BagPlayback {}
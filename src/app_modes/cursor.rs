@@ -0,0 +1,167 @@
+//! A cursor that can be moved in the viewport to pick points or poses in the fixed frame.
+
+use crate::app_modes::input;
+use crate::app_modes::viewport::{ViewPainter, Viewport};
+use crate::footprint::get_current_footprint;
+use crate::transformation;
+use nalgebra::{Isometry2, Vector2};
+use tui::style::Color;
+use tui::widgets::canvas::Line;
+
+/// Size of the crosshair of point cursors, in meters at zoom 1.
+const CROSSHAIR_SIZE: f64 = 0.2;
+
+pub struct Cursor {
+    pub pose: Isometry2<f64>,
+    pub step: f64,
+    /// Oriented cursors move along their own axes and can be rotated, like the send pose ghost.
+    /// Other cursors move along the axes of the fixed frame.
+    oriented: bool,
+}
+
+impl Cursor {
+    pub fn new(pose: Isometry2<f64>, oriented: bool) -> Cursor {
+        Cursor {
+            pose,
+            step: 0.1,
+            oriented,
+        }
+    }
+
+    pub fn position(&self) -> (f64, f64) {
+        (self.pose.translation.x, self.pose.translation.y)
+    }
+
+    fn move_by(&mut self, x: f64, y: f64, yaw: f64) {
+        let new_yaw = self.pose.rotation.angle() + yaw;
+        let (dx, dy) = if self.oriented {
+            (
+                x * new_yaw.cos() - y * new_yaw.sin(),
+                x * new_yaw.sin() + y * new_yaw.cos(),
+            )
+        } else {
+            (x, y)
+        };
+        self.pose = Isometry2::new(
+            Vector2::new(self.pose.translation.x + dx, self.pose.translation.y + dy),
+            new_yaw,
+        );
+    }
+
+    /// Moves the cursor or changes its step. Returns true if the input was used.
+    pub fn handle_input(&mut self, input: &String) -> bool {
+        let step = self.step;
+        match (input.as_str(), self.oriented) {
+            (input::UP, true) => self.move_by(step, 0.0, 0.0),
+            (input::DOWN, true) => self.move_by(-step, 0.0, 0.0),
+            (input::LEFT, true) => self.move_by(0.0, step, 0.0),
+            (input::RIGHT, true) => self.move_by(0.0, -step, 0.0),
+            (input::ROTATE_LEFT, true) => self.move_by(0.0, 0.0, step),
+            (input::ROTATE_RIGHT, true) => self.move_by(0.0, 0.0, -step),
+            (input::UP, false) => self.move_by(0.0, step, 0.0),
+            (input::DOWN, false) => self.move_by(0.0, -step, 0.0),
+            (input::LEFT, false) => self.move_by(-step, 0.0, 0.0),
+            (input::RIGHT, false) => self.move_by(step, 0.0, 0.0),
+            (input::INCREMENT_STEP, _) => self.step += 0.1,
+            (input::DECREMENT_STEP, _) => self.step = (self.step - 0.1).max(0.01),
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = if self.oriented {
+            vec![
+                [
+                    input::UP.to_string(),
+                    "Moves the cursor forward.".to_string(),
+                ],
+                [
+                    input::DOWN.to_string(),
+                    "Moves the cursor backward.".to_string(),
+                ],
+                [
+                    input::LEFT.to_string(),
+                    "Moves the cursor to the left.".to_string(),
+                ],
+                [
+                    input::RIGHT.to_string(),
+                    "Moves the cursor to the right.".to_string(),
+                ],
+                [
+                    input::ROTATE_LEFT.to_string(),
+                    "Rotates the cursor counter-clockwise.".to_string(),
+                ],
+                [
+                    input::ROTATE_RIGHT.to_string(),
+                    "Rotates the cursor clockwise.".to_string(),
+                ],
+            ]
+        } else {
+            vec![
+                [input::UP.to_string(), "Moves the cursor up.".to_string()],
+                [
+                    input::DOWN.to_string(),
+                    "Moves the cursor down.".to_string(),
+                ],
+                [
+                    input::LEFT.to_string(),
+                    "Moves the cursor to the left.".to_string(),
+                ],
+                [
+                    input::RIGHT.to_string(),
+                    "Moves the cursor to the right.".to_string(),
+                ],
+            ]
+        };
+        keymap.extend([
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the step of the cursor.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the step of the cursor.".to_string(),
+            ],
+        ]);
+        keymap
+    }
+
+    /// Draws the cursor: the robot footprint and axes if oriented, a crosshair otherwise.
+    pub fn draw(&self, ctx: &mut dyn ViewPainter, viewport: &Viewport, color: Color) {
+        let rotation = viewport.rotation();
+        if self.oriented {
+            let pose = transformation::iso2d_to_ros(&self.pose);
+            for elem in &get_current_footprint(&pose, &viewport.footprint) {
+                ctx.draw_line(&rotation.line(&Line {
+                    x1: elem.0,
+                    y1: elem.1,
+                    x2: elem.2,
+                    y2: elem.3,
+                    color,
+                }));
+            }
+            for line in Viewport::get_frame_lines(&pose, viewport.axis_length, (color, color)) {
+                ctx.draw_line(&rotation.line(&line));
+            }
+        } else {
+            // The crosshair stays aligned with the view
+            let (x, y) = rotation.apply(self.position());
+            let size = CROSSHAIR_SIZE / viewport.zoom;
+            ctx.draw_line(&Line {
+                x1: x - size,
+                y1: y,
+                x2: x + size,
+                y2: y,
+                color,
+            });
+            ctx.draw_line(&Line {
+                x1: x,
+                y1: y - size,
+                x2: x,
+                y2: y + size,
+                color,
+            });
+        }
+    }
+}
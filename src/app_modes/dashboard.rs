@@ -0,0 +1,215 @@
+//! Dashboard mode shows the widgets declared in the config in a grid, e.g. as a home screen
+//! with the state of the robot.
+
+use crate::app_modes::logs::level_color;
+use crate::app_modes::viewer::Viewer;
+use crate::app_modes::viewport::{draw_viewport, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::{DashboardConfig, DashboardWidgetConfig, LogsConfig};
+use crate::rosout::{level_name, RosoutListener};
+use crate::theme::Theme;
+use crate::transformation;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph};
+use tui::Frame;
+
+pub struct Dashboard {
+    viewport: Rc<RefCell<Viewport>>,
+    viewer: Viewer,
+    config: DashboardConfig,
+    /// Subscriber to the logs, only if a widget shows them.
+    rosout: Option<RosoutListener>,
+    theme: Theme,
+}
+
+impl Dashboard {
+    pub fn new(
+        viewport: Rc<RefCell<Viewport>>,
+        config: DashboardConfig,
+        logs: LogsConfig,
+        theme: Theme,
+    ) -> Dashboard {
+        let rosout = if config.widgets.iter().any(|widget| widget.kind == "logs") {
+            Some(RosoutListener::new(logs))
+        } else {
+            None
+        };
+        Dashboard {
+            viewer: Viewer::new("Dashboard", viewport.clone()),
+            viewport,
+            config,
+            rosout,
+            theme,
+        }
+    }
+
+    /// Splits the area into the cells of the grid, row by row.
+    fn cells(&self, area: Rect) -> Vec<Rect> {
+        let columns = self.config.columns.max(1) as usize;
+        let rows = (self.config.widgets.len() + columns - 1) / columns;
+        Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Ratio(1, rows as u32); rows])
+            .split(area)
+            .into_iter()
+            .flat_map(|row| {
+                Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![Constraint::Ratio(1, columns as u32); columns])
+                    .split(row)
+            })
+            .collect()
+    }
+
+    /// Returns the lines of a widget showing text, or nothing if it is not a text widget.
+    fn text(&self, widget: &DashboardWidgetConfig, height: usize) -> Vec<Spans<'static>> {
+        let viewport = self.viewport.borrow();
+        let listeners = &viewport.listeners;
+        let lines: Vec<Spans> = match widget.kind.as_str() {
+            "battery" => listeners
+                .batteries
+                .iter()
+                .filter_map(|battery| battery.status())
+                .map(Spans::from)
+                .collect(),
+            "fields" => listeners
+                .fields
+                .iter()
+                .map(|field| Spans::from(field.status()))
+                .collect(),
+            "move_base" => listeners
+                .move_base_statuses
+                .iter()
+                .filter_map(|move_base| move_base.status())
+                .map(Spans::from)
+                .collect(),
+            "transform" => {
+                let source_frame = widget
+                    .source_frame
+                    .clone()
+                    .unwrap_or_else(|| viewport.robot_frame.clone());
+                let target_frame = widget
+                    .target_frame
+                    .clone()
+                    .unwrap_or_else(|| viewport.static_frame.clone());
+                match viewport.tf_listener.lookup_transform(
+                    &target_frame,
+                    &source_frame,
+                    rosrust::Time::new(),
+                ) {
+                    Ok(transform) => {
+                        let translation = &transform.transform.translation;
+                        let yaw = transformation::ros_to_iso2d(&transform.transform)
+                            .rotation
+                            .angle();
+                        vec![
+                            Spans::from(format!("{} in {}", source_frame, target_frame)),
+                            Spans::from(format!(
+                                "x: {:.2}, y: {:.2}, z: {:.2}",
+                                translation.x, translation.y, translation.z
+                            )),
+                            Spans::from(format!("yaw: {:.1}°", yaw.to_degrees())),
+                        ]
+                    }
+                    Err(_) => vec![Spans::from(Span::styled(
+                        format!("No transform from {} to {}", source_frame, target_frame),
+                        Style::default().fg(self.theme.error),
+                    ))],
+                }
+            }
+            "logs" => {
+                let buffer = self.rosout.as_ref().unwrap().buffer.read().unwrap();
+                let count = buffer.len();
+                let shown = widget.lines.min(height);
+                buffer
+                    .iter()
+                    .skip(count.saturating_sub(shown))
+                    .map(|entry| {
+                        Spans::from(vec![
+                            Span::styled(
+                                format!("[{:5}] ", level_name(entry.level)),
+                                Style::default().fg(level_color(entry.level, &self.theme)),
+                            ),
+                            Span::raw(format!("[{}]: {}", entry.node, entry.msg)),
+                        ])
+                    })
+                    .collect()
+            }
+            kind => vec![Spans::from(Span::styled(
+                format!("Unknown widget kind: {}", kind),
+                Style::default().fg(self.theme.error),
+            ))],
+        };
+        if lines.is_empty() {
+            return vec![Spans::from(Span::styled(
+                "No data",
+                Style::default().fg(self.theme.inactive),
+            ))];
+        }
+        lines
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Dashboard {}
+
+impl AppMode for Dashboard {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        // The keys of the viewport act on the viewport widgets
+        self.viewport.borrow_mut().handle_input(input);
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the widgets configured under dashboard in a grid.".to_string(),
+            "The keys of the viewport act on the viewport widgets.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        self.viewport.borrow().get_keymap()
+    }
+
+    fn get_name(&self) -> String {
+        "Dashboard".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for Dashboard {
+    fn draw(&self, f: &mut Frame<B>) {
+        for (widget, cell) in self.config.widgets.iter().zip(self.cells(f.size())) {
+            let title = widget.title.clone().unwrap_or_else(|| widget.kind.clone());
+            let block = Block::default()
+                .title(Span::styled(
+                    title,
+                    Style::default()
+                        .fg(self.theme.title)
+                        .add_modifier(Modifier::BOLD),
+                ))
+                .borders(Borders::ALL);
+            let area = block.inner(cell);
+            f.render_widget(block, cell);
+            if widget.kind == "viewport" {
+                // The viewport keeps its proportions in the widget
+                let terminal_size = self.viewport.borrow().terminal_size;
+                self.viewport.borrow_mut().terminal_size = (area.width.max(1), area.height.max(1));
+                draw_viewport(&self.viewer, f, area);
+                self.viewport.borrow_mut().terminal_size = terminal_size;
+            } else {
+                let text = self.text(widget, area.height as usize);
+                f.render_widget(
+                    Paragraph::new(text).style(Style::default().fg(self.theme.text)),
+                    area,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Diagnostics mode shows the statuses received as diagnostic_msgs::DiagnosticArray,
+//! grouped by hardware ID.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::DiagnosticsConfig;
+use crate::source;
+use crate::theme::Theme;
+use rosrust_msg::diagnostic_msgs::{DiagnosticArray, DiagnosticStatus};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use tui::Frame;
+
+/// Last received status of each diagnostic, by hardware ID and by name.
+type DiagnosticsTree = BTreeMap<String, BTreeMap<String, DiagnosticStatus>>;
+
+fn level_color(level: i8, theme: &Theme) -> Color {
+    if level == DiagnosticStatus::OK {
+        theme.ok
+    } else if level == DiagnosticStatus::WARN {
+        theme.warning
+    } else if level == DiagnosticStatus::ERROR {
+        theme.error
+    } else {
+        theme.inactive
+    }
+}
+
+/// A line of the tree, as shown on screen.
+struct Row {
+    depth: usize,
+    text: String,
+    color: Color,
+    /// Hardware ID and, for statuses, the name of the status.
+    key: Option<(String, Option<String>)>,
+}
+
+pub struct Diagnostics {
+    config: DiagnosticsConfig,
+    tree: Arc<RwLock<DiagnosticsTree>>,
+    expanded: HashSet<(String, Option<String>)>,
+    state: ListState,
+    theme: Theme,
+    _subscriber: source::Subscriber,
+}
+
+impl Diagnostics {
+    pub fn new(config: DiagnosticsConfig, theme: Theme) -> Diagnostics {
+        let tree = Arc::new(RwLock::new(DiagnosticsTree::new()));
+        let cb_tree = tree.clone();
+        let sub = source::subscribe(&config.topic, 10, move |msg: DiagnosticArray| {
+            let mut tree = cb_tree.write().unwrap();
+            for status in msg.status {
+                // Statuses without hardware ID are grouped together under an empty ID
+                tree.entry(status.hardware_id.clone())
+                    .or_insert_with(BTreeMap::new)
+                    .insert(status.name.clone(), status);
+            }
+        })
+        .unwrap();
+        let mut state = ListState::default();
+        state.select(Some(0));
+        Diagnostics {
+            config,
+            tree,
+            expanded: HashSet::new(),
+            state,
+            theme,
+            _subscriber: sub,
+        }
+    }
+
+    /// Flattens the tree into the rows that are currently visible.
+    fn rows(&self) -> Vec<Row> {
+        let tree = self.tree.read().unwrap();
+        let mut rows = Vec::new();
+        for (hardware_id, statuses) in tree.iter() {
+            let worst_level = statuses.values().map(|s| s.level).max().unwrap_or(0);
+            let hardware_key = (hardware_id.clone(), None);
+            let hardware_expanded = self.expanded.contains(&hardware_key);
+            rows.push(Row {
+                depth: 0,
+                text: format!(
+                    "{} {} ({})",
+                    if hardware_expanded { "v" } else { ">" },
+                    if hardware_id.is_empty() {
+                        "<no hardware ID>"
+                    } else {
+                        hardware_id
+                    },
+                    statuses.len()
+                ),
+                color: level_color(worst_level, &self.theme),
+                key: Some(hardware_key),
+            });
+            if !hardware_expanded {
+                continue;
+            }
+            for (name, status) in statuses.iter() {
+                let status_key = (hardware_id.clone(), Some(name.clone()));
+                let status_expanded = self.expanded.contains(&status_key);
+                rows.push(Row {
+                    depth: 1,
+                    text: format!(
+                        "{} {}: {}",
+                        if status_expanded { "v" } else { ">" },
+                        name,
+                        status.message
+                    ),
+                    color: level_color(status.level, &self.theme),
+                    key: Some(status_key),
+                });
+                if !status_expanded {
+                    continue;
+                }
+                for value in status.values.iter() {
+                    rows.push(Row {
+                        depth: 2,
+                        text: format!("{}: {}", value.key, value.value),
+                        color: self.theme.text,
+                        key: None,
+                    });
+                }
+            }
+        }
+        rows
+    }
+
+    fn select(&mut self, offset: i64) {
+        let len = self.rows().len() as i64;
+        if len == 0 {
+            return;
+        }
+        let i = self.state.selected().unwrap_or(0) as i64;
+        self.state
+            .select(Some((i + offset).rem_euclid(len) as usize));
+    }
+
+    fn toggle_selected(&mut self) {
+        let rows = self.rows();
+        let key = match self.state.selected().and_then(|i| rows.get(i)) {
+            Some(Row { key: Some(key), .. }) => key.clone(),
+            _ => return,
+        };
+        if !self.expanded.remove(&key) {
+            self.expanded.insert(key);
+        }
+    }
+}
+
+impl AppMode for Diagnostics {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        match input.as_str() {
+            input::UP => self.select(-1),
+            input::DOWN => self.select(1),
+            input::CONFIRM => self.toggle_selected(),
+            input::CANCEL => {
+                self.expanded.clear();
+                self.state.select(Some(0));
+            }
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the diagnostics received on the configured topic.".to_string(),
+            "The statuses are grouped by hardware ID and colored by level.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous entry.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next entry.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Expands or collapses the selected entry.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Collapses all entries.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Diagnostics".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for Diagnostics {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let header = Paragraph::new(Spans::from(vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" - Topic: ".to_string() + &self.config.topic),
+        ]))
+        .block(Block::default().borders(Borders::NONE))
+        .style(Style::default().fg(self.theme.text))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .rows()
+            .into_iter()
+            .map(|row| {
+                ListItem::new(Span::styled(
+                    "  ".repeat(row.depth) + &row.text,
+                    Style::default().fg(row.color),
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[1], &mut self.state.clone());
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Diagnostics {}
@@ -0,0 +1,451 @@
+//! Export of the viewport to image files, at a higher resolution than the terminal, and
+//! recording of the viewport to animations.
+
+use crate::app_modes::input;
+use crate::app_modes::viewport::{clip_line, legend_width, UseViewport, ViewPainter, Viewport};
+use crate::config::Color as ConfigColor;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, DynamicImage, Frame, Rgb, RgbImage};
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tui::style::Color;
+use tui::text::Span;
+use tui::widgets::canvas::{Line, Points};
+
+/// Every terminal cell holds 2x4 braille dots.
+const DOTS_PER_CELL: (u32, u32) = (2, 4);
+
+/// Speed of the color quantization of the GIF frames, from 1 (best) to 30 (fastest).
+const GIF_SPEED: i32 = 10;
+
+/// Frames of the view captured since the recording started.
+pub struct Recording {
+    started: Instant,
+    frames: Vec<(Instant, RgbImage)>,
+}
+
+impl Recording {
+    pub fn new() -> Recording {
+        Recording {
+            started: Instant::now(),
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Exports or records the view of the mode if the input asks for it, and keeps the result to
+/// show it.
+pub fn handle_input<T: UseViewport>(mode: &T, viewport: &RefCell<Viewport>, input: &str) {
+    match input {
+        input::EXPORT => {
+            let status = export_view(mode, &viewport.borrow());
+            viewport.borrow_mut().export_status = Some(status);
+        }
+        input::RECORD => {
+            let mut viewport = viewport.borrow_mut();
+            match viewport.recording.take() {
+                Some(recording) => {
+                    viewport.export_status = Some(write_recording(recording, &viewport));
+                }
+                None => viewport.recording = Some(Recording::new()),
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Captures the view of the mode while recording, and writes the recording once it lasted
+/// the configured duration. Called on every tick of the mode.
+pub fn record<T: UseViewport>(mode: &T, viewport: &RefCell<Viewport>) {
+    if viewport.borrow().recording.is_none() {
+        return;
+    }
+    let (size, scale, background) = {
+        let viewport = viewport.borrow();
+        (
+            viewport.terminal_size,
+            viewport.record.scale,
+            viewport.background_color.clone(),
+        )
+    };
+    let frame = render(mode, size, scale, &background);
+    let mut viewport = viewport.borrow_mut();
+    let duration = Duration::from_secs_f64(viewport.record.duration.max(0.0));
+    let recording = viewport.recording.as_mut().unwrap();
+    recording.frames.push((Instant::now(), frame));
+    if recording.elapsed() >= duration {
+        let recording = viewport.recording.take().unwrap();
+        viewport.export_status = Some(write_recording(recording, &viewport));
+    }
+}
+
+/// Writes the frames of the recording to a timestamped animated GIF, or to a timestamped
+/// directory of numbered PNG files. Returns the name of the file or directory.
+fn write_recording(recording: Recording, viewport: &Viewport) -> Result<String, String> {
+    if recording.frames.is_empty() {
+        return Err("no frame recorded".to_string());
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let result = match viewport.record.format.as_str() {
+        "gif" => {
+            let filename = format!("termviz_recording_{}.gif", now.as_secs());
+            write_gif(recording, &filename).map(|_| filename)
+        }
+        "png" => {
+            let dirname = format!("termviz_recording_{}", now.as_secs());
+            write_png_sequence(recording, &dirname).map(|_| dirname)
+        }
+        other => return Err(format!("unknown format {}", other)),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+fn write_gif(recording: Recording, filename: &str) -> io::Result<()> {
+    let file = BufWriter::new(File::create(filename)?);
+    let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+    let to_io_error = |e| io::Error::new(io::ErrorKind::Other, e);
+    encoder.set_repeat(Repeat::Infinite).map_err(to_io_error)?;
+    // Each frame is shown until the next one was captured
+    let mut frames = recording.frames.into_iter().peekable();
+    while let Some((captured, image)) = frames.next() {
+        let delay = match frames.peek() {
+            Some((next, _)) => next.duration_since(captured),
+            None => Duration::from_millis(100),
+        };
+        encoder
+            .encode_frame(Frame::from_parts(
+                DynamicImage::ImageRgb8(image).into_rgba8(),
+                0,
+                0,
+                Delay::from_saturating_duration(delay),
+            ))
+            .map_err(to_io_error)?;
+    }
+    Ok(())
+}
+
+fn write_png_sequence(recording: Recording, dirname: &str) -> io::Result<()> {
+    fs::create_dir(dirname)?;
+    for (i, (_, image)) in recording.frames.iter().enumerate() {
+        image
+            .save(Path::new(dirname).join(format!("frame_{:05}.png", i)))
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    }
+    Ok(())
+}
+
+/// Draws the view of the mode offscreen and writes it to a timestamped file in the current
+/// directory. Returns the name of the file.
+pub fn export_view<T: UseViewport>(mode: &T, viewport: &Viewport) -> Result<String, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let filename = format!("termviz_view_{}.{}", now.as_secs(), viewport.export.format);
+    let result = match viewport.export.format.as_str() {
+        "png" => render(
+            mode,
+            viewport.terminal_size,
+            viewport.export.scale,
+            &viewport.background_color,
+        )
+        .save(&filename)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e)),
+        "svg" => write_svg(mode, viewport, &filename),
+        other => return Err(format!("unknown format {}", other)),
+    };
+    result.map(|_| filename).map_err(|e| e.to_string())
+}
+
+/// Draws the view of the mode on an image `scale` times larger than the braille dots of the
+/// canvas shown in an area of the given size. Text does not fit in the pixels of a dot and is
+/// left out.
+pub fn render<T: UseViewport>(
+    mode: &T,
+    size: (u16, u16),
+    scale: u16,
+    background: &ConfigColor,
+) -> RgbImage {
+    let frame = PixelFrame::new(mode, size, scale);
+    let mut painter = ImagePainter {
+        image: RgbImage::from_pixel(
+            frame.width,
+            frame.height,
+            Rgb([background.r, background.g, background.b]),
+        ),
+        frame,
+    };
+    mode.draw_in_viewport(&mut painter);
+    painter.image
+}
+
+/// Projection of the bounds of the view onto an image, each braille dot of the canvas being
+/// `scale` pixels wide.
+struct PixelFrame {
+    x_bounds: [f64; 2],
+    y_bounds: [f64; 2],
+    width: u32,
+    height: u32,
+    scale: u32,
+}
+
+impl PixelFrame {
+    fn new<T: UseViewport>(mode: &T, size: (u16, u16), scale: u16) -> PixelFrame {
+        let (width, height) = canvas_size(mode, size);
+        let scale = scale.max(1) as u32;
+        PixelFrame {
+            x_bounds: mode.x_bounds(),
+            y_bounds: mode.y_bounds(),
+            width: width as u32 * DOTS_PER_CELL.0 * scale,
+            height: height as u32 * DOTS_PER_CELL.1 * scale,
+            scale,
+        }
+    }
+
+    fn contains(&self, (x, y): (f64, f64)) -> bool {
+        self.x_bounds[0] <= x
+            && x <= self.x_bounds[1]
+            && self.y_bounds[0] <= y
+            && y <= self.y_bounds[1]
+    }
+
+    /// Returns the position in pixels of a point of the view, from the top left corner.
+    fn to_pixel(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [x_min, x_max] = self.x_bounds;
+        let [y_min, y_max] = self.y_bounds;
+        (
+            (x - x_min) / (x_max - x_min) * self.width as f64,
+            (y_max - y) / (y_max - y_min) * self.height as f64,
+        )
+    }
+
+    /// Returns the part of the line inside the view, in pixels.
+    fn line_to_pixels(&self, line: &Line) -> Option<((f64, f64), (f64, f64))> {
+        let line = clip_line(line, self.x_bounds, self.y_bounds)?;
+        Some((
+            self.to_pixel((line.x1, line.y1)),
+            self.to_pixel((line.x2, line.y2)),
+        ))
+    }
+}
+
+/// Draws the primitives of the view straight on an image, at the resolution of the frame.
+struct ImagePainter {
+    image: RgbImage,
+    frame: PixelFrame,
+}
+
+impl ImagePainter {
+    /// Paints a square as wide as a braille dot centered on the given position in pixels.
+    fn paint_dot(&mut self, (x, y): (f64, f64), color: Rgb<u8>) {
+        let half = self.frame.scale as f64 / 2.0;
+        let (left, top) = ((x - half).round().max(0.0), (y - half).round().max(0.0));
+        let right = ((left as u32) + self.frame.scale).min(self.frame.width);
+        let bottom = ((top as u32) + self.frame.scale).min(self.frame.height);
+        for py in top as u32..bottom {
+            for px in left as u32..right {
+                self.image.put_pixel(px, py, color);
+            }
+        }
+    }
+}
+
+impl ViewPainter for ImagePainter {
+    fn draw_line(&mut self, line: &Line) {
+        let ((x1, y1), (x2, y2)) = match self.frame.line_to_pixels(line) {
+            Some(pixels) => pixels,
+            None => return,
+        };
+        let color = Rgb(to_rgb(line.color));
+        let steps = (x2 - x1).abs().max((y2 - y1).abs()).ceil().max(1.0) as u32;
+        for i in 0..=steps {
+            let t = i as f64 / steps as f64;
+            self.paint_dot((x1 + t * (x2 - x1), y1 + t * (y2 - y1)), color);
+        }
+    }
+
+    fn draw_points(&mut self, points: &Points) {
+        let color = Rgb(to_rgb(points.color));
+        for &point in points.coords {
+            if self.frame.contains(point) {
+                self.paint_dot(self.frame.to_pixel(point), color);
+            }
+        }
+    }
+
+    fn print(&mut self, _x: f64, _y: f64, _text: Span<'static>) {}
+
+    // The primitives are painted in the order of the layers
+    fn layer(&mut self) {}
+}
+
+/// Writes the primitives of the view as SVG elements, at the size of the frame.
+struct SvgPainter {
+    elements: Vec<String>,
+    frame: PixelFrame,
+}
+
+impl ViewPainter for SvgPainter {
+    fn draw_line(&mut self, line: &Line) {
+        if let Some(((x1, y1), (x2, y2))) = self.frame.line_to_pixels(line) {
+            self.elements.push(format!(
+                r#"<line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+                x1,
+                y1,
+                x2,
+                y2,
+                hex(to_rgb(line.color)),
+                self.frame.scale
+            ));
+        }
+    }
+
+    fn draw_points(&mut self, points: &Points) {
+        let color = hex(to_rgb(points.color));
+        let half = self.frame.scale as f64 / 2.0;
+        for &point in points.coords {
+            if self.frame.contains(point) {
+                let (x, y) = self.frame.to_pixel(point);
+                self.elements.push(format!(
+                    r#"<rect x="{:.1}" y="{:.1}" width="{}" height="{}" fill="{}"/>"#,
+                    x - half,
+                    y - half,
+                    self.frame.scale,
+                    self.frame.scale,
+                    color
+                ));
+            }
+        }
+    }
+
+    fn print(&mut self, x: f64, y: f64, text: Span<'static>) {
+        if !self.frame.contains((x, y)) {
+            return;
+        }
+        let (x, y) = self.frame.to_pixel((x, y));
+        // The text is one terminal cell high, as on the canvas
+        self.elements.push(format!(
+            r#"<text x="{:.1}" y="{:.1}" fill="{}" font-family="monospace" font-size="{}">{}</text>"#,
+            x,
+            y,
+            hex(to_rgb(text.style.fg.unwrap_or(Color::Reset))),
+            DOTS_PER_CELL.1 * self.frame.scale,
+            escape_xml(&text.content)
+        ));
+    }
+
+    fn layer(&mut self) {}
+}
+
+/// Returns the size of the canvas in an area of the given size, without the legend and the top
+/// bar, so that the exported view keeps its proportions.
+fn canvas_size<T: UseViewport>(mode: &T, (width, height): (u16, u16)) -> (u16, u16) {
+    (
+        (width - legend_width(&mode.legend(), width)).max(1),
+        height.saturating_sub(1).max(1),
+    )
+}
+
+/// Converts a terminal color to RGB, taking the xterm values for the named colors.
+pub fn to_rgb(color: Color) -> [u8; 3] {
+    match color {
+        Color::Black => [0, 0, 0],
+        Color::Red => [205, 0, 0],
+        Color::Green => [0, 205, 0],
+        Color::Yellow => [205, 205, 0],
+        Color::Blue => [0, 0, 238],
+        Color::Magenta => [205, 0, 205],
+        Color::Cyan => [0, 205, 205],
+        Color::Gray => [229, 229, 229],
+        Color::DarkGray => [127, 127, 127],
+        Color::LightRed => [255, 0, 0],
+        Color::LightGreen => [0, 255, 0],
+        Color::LightYellow => [255, 255, 0],
+        Color::LightBlue => [92, 92, 255],
+        Color::LightMagenta => [255, 0, 255],
+        Color::LightCyan => [0, 255, 255],
+        Color::White | Color::Reset => [255, 255, 255],
+        Color::Rgb(r, g, b) => [r, g, b],
+        Color::Indexed(i) => indexed_to_rgb(i),
+    }
+}
+
+/// Converts a color of the xterm 256-color palette to RGB.
+fn indexed_to_rgb(i: u8) -> [u8; 3] {
+    const NAMED: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Gray,
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ];
+    match i {
+        0..=15 => to_rgb(NAMED[i as usize]),
+        16..=231 => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + 40 * n };
+            let i = i - 16;
+            [level(i / 36), level(i / 6 % 6), level(i % 6)]
+        }
+        _ => {
+            let gray = 8 + 10 * (i - 232);
+            [gray, gray, gray]
+        }
+    }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the primitives of the view as vector elements, and its text as text elements.
+fn write_svg<T: UseViewport>(mode: &T, viewport: &Viewport, filename: &str) -> io::Result<()> {
+    let mut painter = SvgPainter {
+        elements: Vec::new(),
+        frame: PixelFrame::new(mode, viewport.terminal_size, viewport.export.scale),
+    };
+    mode.draw_in_viewport(&mut painter);
+    let (width, height) = (painter.frame.width, painter.frame.height);
+    let background = &viewport.background_color;
+    let mut file = BufWriter::new(File::create(filename)?);
+    writeln!(
+        file,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}">"#,
+        width, height, width, height
+    )?;
+    writeln!(
+        file,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex([background.r, background.g, background.b])
+    )?;
+    for element in painter.elements {
+        writeln!(file, "{}", element)?;
+    }
+    writeln!(file, "</svg>")?;
+    file.flush()
+}
@@ -4,9 +4,10 @@ use self::image::ImageListener;
 use crate::app_modes::{input, AppMode, BaseMode, Drawable};
 use crate::config::ImageListenerConfig;
 use crate::image;
+use crate::theme::Theme;
 use tui::backend::Backend;
-use tui::layout::{Alignment, Constraint, Layout};
-use tui::style::{Color, Modifier, Style};
+use tui::layout::{Alignment, Constraint, Layout, Rect};
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{Block, Borders, Paragraph, Wrap};
 use tui::Frame;
@@ -14,11 +15,12 @@ use tui_image::{ColorMode, Image};
 pub struct ImageView {
     images: Vec<ImageListener>,
     active_sub: usize,
+    theme: Theme,
 }
 
 /// Represents the image view mode.
 impl ImageView {
-    pub fn new(image_topics: Vec<ImageListenerConfig>) -> ImageView {
+    pub fn new(image_topics: Vec<ImageListenerConfig>, theme: Theme) -> ImageView {
         let mut images: Vec<image::ImageListener> = Vec::new();
         for image_config in image_topics {
             images.push(image::ImageListener::new(image_config));
@@ -26,6 +28,7 @@ impl ImageView {
         ImageView {
             images: images,
             active_sub: 0,
+            theme,
         }
     }
 }
@@ -103,32 +106,50 @@ impl AppMode for ImageView {
 
 impl<B: Backend> Drawable<B> for ImageView {
     fn draw(&self, f: &mut Frame<B>) {
+        self.draw_in(f, f.size());
+    }
+}
+
+impl ImageView {
+    /// Draws the active image with its header in the given area.
+    pub fn draw_in<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
         let chunks = Layout::default()
             .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
-            .split(f.size());
+            .split(area);
         if self.images.len() == 0 {
             let header = Paragraph::new(Spans::from(Span::raw(
                 self.get_name() + " view - No topic configured!",
             )))
             .block(Block::default().borders(Borders::NONE))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
             f.render_widget(header, chunks[0]);
         } else {
             for image_sub in &self.images {
                 if image_sub.is_active() {
-                    let header = Paragraph::new(Spans::from(vec![
+                    let mut header_spans = vec![
                         Span::styled(
                             self.get_name() + " view",
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                            Style::default()
+                                .fg(self.theme.title)
+                                .add_modifier(Modifier::BOLD),
                         ),
                         Span::raw(" - Topic: /".to_string() + &image_sub.config.topic),
-                    ]))
-                    .block(Block::default().borders(Borders::NONE))
-                    .style(Style::default().fg(Color::White))
-                    .alignment(Alignment::Left)
-                    .wrap(Wrap { trim: false });
+                    ];
+                    // Labels of the detections, in the color of their bounding box
+                    for detection in image_sub.detections.read().unwrap().iter() {
+                        header_spans.push(Span::raw(" "));
+                        header_spans.push(Span::styled(
+                            format!("[{}: {:.2}]", detection.class_id, detection.score),
+                            Style::default().fg(detection.color.to_tui()),
+                        ));
+                    }
+                    let header = Paragraph::new(Spans::from(header_spans))
+                        .block(Block::default().borders(Borders::NONE))
+                        .style(Style::default().fg(self.theme.text))
+                        .alignment(Alignment::Left)
+                        .wrap(Wrap { trim: false });
                     f.render_widget(header, chunks[0]);
                     let image = image_sub.img.read().unwrap();
                     let widget = Image::with_img(image.clone()).color_mode(ColorMode::Rgb);
@@ -0,0 +1,303 @@
+//! Joint teleoperation mode allows to jog single joints of the robot, e.g. of an arm.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::JointTeleopConfig;
+use crate::source;
+use crate::theme::Theme;
+use rosrust;
+use rosrust_msg::sensor_msgs::JointState;
+use rosrust_msg::std_msgs::Float64;
+use rosrust_msg::trajectory_msgs::{JointTrajectory, JointTrajectoryPoint};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap};
+use tui::Frame;
+
+/// Last received state of a joint.
+#[derive(Clone, Default)]
+struct JointData {
+    position: f64,
+    velocity: f64,
+    effort: f64,
+}
+
+enum CommandPublisher {
+    /// One std_msgs::Float64 publisher per joint.
+    Float64(HashMap<String, rosrust::Publisher<Float64>>),
+    /// A single trajectory_msgs::JointTrajectory publisher for all the joints.
+    JointTrajectory(rosrust::Publisher<JointTrajectory>),
+}
+
+pub struct JointTeleop {
+    config: JointTeleopConfig,
+    joints: Arc<RwLock<BTreeMap<String, JointData>>>,
+    publisher: CommandPublisher,
+    selected: usize,
+    increment: f64,
+    /// Error of the last command which could not be published.
+    error: Option<String>,
+    theme: Theme,
+    _subscriber: source::Subscriber,
+}
+
+impl JointTeleop {
+    pub fn new(config: JointTeleopConfig, theme: Theme) -> JointTeleop {
+        let joints = Arc::new(RwLock::new(BTreeMap::new()));
+        let cb_joints = joints.clone();
+        // Joint states may be published by several nodes, each with a subset of the joints
+        let sub = source::subscribe(&config.joint_states_topic, 10, move |msg: JointState| {
+            let mut joints = cb_joints.write().unwrap();
+            for (i, name) in msg.name.iter().enumerate() {
+                joints.insert(
+                    name.clone(),
+                    JointData {
+                        position: msg.position.get(i).cloned().unwrap_or(0.0),
+                        velocity: msg.velocity.get(i).cloned().unwrap_or(0.0),
+                        effort: msg.effort.get(i).cloned().unwrap_or(0.0),
+                    },
+                );
+            }
+        })
+        .unwrap();
+        let publisher = match config.command_type.as_str() {
+            "JointTrajectory" => CommandPublisher::JointTrajectory(
+                rosrust::publish(&config.command_topic, 1).unwrap(),
+            ),
+            _ => CommandPublisher::Float64(HashMap::new()),
+        };
+        JointTeleop {
+            increment: config.default_increment,
+            config,
+            joints,
+            publisher,
+            selected: 0,
+            error: None,
+            theme,
+            _subscriber: sub,
+        }
+    }
+
+    fn selected_joint(&self) -> Option<String> {
+        self.joints
+            .read()
+            .unwrap()
+            .keys()
+            .nth(self.selected)
+            .cloned()
+    }
+
+    /// Commands the selected joint to move by the given offset from its current position.
+    fn jog(&mut self, offset: f64) {
+        self.error = match self.send_command(offset) {
+            Ok(()) => None,
+            Err(e) => Some(e),
+        };
+    }
+
+    fn send_command(&self, offset: f64) -> Result<(), String> {
+        let joint = match self.selected_joint() {
+            Some(joint) => joint,
+            None => return Ok(()),
+        };
+        let joints = self.joints.read().unwrap();
+        let target = joints[&joint].position + offset;
+        match &self.publisher {
+            CommandPublisher::Float64(publishers) => match publishers.get(&joint) {
+                Some(publisher) => publisher
+                    .send(Float64 { data: target })
+                    .map_err(|e| format!("Failed to command {}: {}", joint, e)),
+                None => Err(format!("No publisher for {}", joint)),
+            },
+            CommandPublisher::JointTrajectory(publisher) => {
+                // The controller rejects trajectories naming joints it does not control
+                let names = if self.config.controlled_joints.is_empty() {
+                    vec![joint.clone()]
+                } else {
+                    self.config.controlled_joints.clone()
+                };
+                if !names.contains(&joint) {
+                    return Err(format!("{} is not a controlled joint", joint));
+                }
+                // The other joints are commanded to hold their current position
+                let mut msg = JointTrajectory::default();
+                let mut point = JointTrajectoryPoint::default();
+                for name in names {
+                    let position = if name == joint {
+                        target
+                    } else {
+                        match joints.get(&name) {
+                            Some(data) => data.position,
+                            None => return Err(format!("No state received for {}", name)),
+                        }
+                    };
+                    msg.joint_names.push(name);
+                    point.positions.push(position);
+                }
+                point.time_from_start =
+                    rosrust::Duration::from_nanos((self.config.trajectory_duration * 1e9) as i64);
+                msg.points.push(point);
+                publisher
+                    .send(msg)
+                    .map_err(|e| format!("Failed to send the trajectory: {}", e))
+            }
+        }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for JointTeleop {}
+
+impl AppMode for JointTeleop {
+    fn run(&mut self) {
+        // Create the publishers beforehand, so they are connected when jogging
+        if let CommandPublisher::Float64(publishers) = &mut self.publisher {
+            for joint in self.joints.read().unwrap().keys() {
+                if !publishers.contains_key(joint) {
+                    let topic = self.config.command_topic.replace("{joint}", joint);
+                    match rosrust::publish(&topic, 1) {
+                        Ok(publisher) => {
+                            publishers.insert(joint.clone(), publisher);
+                        }
+                        Err(e) => {
+                            self.error = Some(format!("Failed to advertise /{}: {}", topic, e))
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        let joint_count = self.joints.read().unwrap().len();
+        match input.as_str() {
+            input::UP => {
+                if joint_count > 0 {
+                    self.selected = (self.selected + joint_count - 1) % joint_count;
+                }
+            }
+            input::DOWN => {
+                if joint_count > 0 {
+                    self.selected = (self.selected + 1) % joint_count;
+                }
+            }
+            input::RIGHT => self.jog(self.increment),
+            input::LEFT => self.jog(-self.increment),
+            input::INCREMENT_STEP => self.increment += self.config.increment_step,
+            input::DECREMENT_STEP => {
+                self.increment = self
+                    .config
+                    .increment_step
+                    .max(self.increment - self.config.increment_step)
+            }
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to jog single joints by publishing position commands.".to_string(),
+            "The joints are read from the configured sensor_msgs::JointState topic.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous joint.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next joint.".to_string(),
+            ],
+            [
+                input::RIGHT.to_string(),
+                "Moves the selected joint positively by one step.".to_string(),
+            ],
+            [
+                input::LEFT.to_string(),
+                "Moves the selected joint negatively by one step.".to_string(),
+            ],
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the position step.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the position step.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Joint Teleoperate".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for JointTeleop {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let mut header_spans = vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(
+                " - Topic: /{} - Position step: {:.3}",
+                self.config.command_topic, self.increment
+            )),
+        ];
+        if let Some(error) = &self.error {
+            header_spans.push(Span::styled(
+                format!(" - {}", error),
+                Style::default().fg(self.theme.error),
+            ));
+        }
+        let header = Paragraph::new(Spans::from(header_spans))
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::default().fg(self.theme.text))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let rows: Vec<Row> = self
+            .joints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, data)| {
+                Row::new(vec![
+                    name.clone(),
+                    format!("{:.3}", data.position),
+                    format!("{:.3}", data.velocity),
+                    format!("{:.3}", data.effort),
+                ])
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Joint", "Position", "Velocity", "Effort"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&[
+                Constraint::Percentage(40),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+                Constraint::Percentage(20),
+            ])
+            .highlight_style(Style::default().fg(self.theme.highlight))
+            .highlight_symbol(">> ");
+        let mut state = TableState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(table, chunks[1], &mut state);
+    }
+}
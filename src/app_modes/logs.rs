@@ -0,0 +1,356 @@
+//! Logs mode shows the log messages of the nodes, as received on /rosout_agg.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::LogsConfig;
+use crate::rosout::{level_from_name, level_name, LogEntry, LogFilter, RosoutListener, LEVELS};
+use crate::theme::Theme;
+use crossterm::event::KeyCode;
+use regex::Regex;
+use rosrust_msg::rosgraph_msgs::Log;
+use std::ops::Range;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Color, Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Wrap};
+use tui::Frame;
+
+pub fn level_color(level: i8, theme: &Theme) -> Color {
+    if level == Log::DEBUG {
+        theme.inactive
+    } else if level == Log::INFO {
+        theme.text
+    } else if level == Log::WARN {
+        theme.warning
+    } else {
+        theme.error
+    }
+}
+
+fn entry_to_spans(entry: &LogEntry, matches: &[Range<usize>], theme: &Theme) -> Spans<'static> {
+    let style = Style::default().fg(level_color(entry.level, theme));
+    let match_style = Style::default().fg(Color::Black).bg(theme.highlight);
+    let mut spans = vec![
+        Span::styled(format!("[{:5}] ", level_name(entry.level)), style),
+        Span::raw(format!(
+            "[{}.{:09}] [{}]: ",
+            entry.stamp.sec, entry.stamp.nsec, entry.node
+        )),
+    ];
+    let mut last = 0;
+    for m in matches {
+        if m.start > last {
+            spans.push(Span::styled(entry.msg[last..m.start].to_string(), style));
+        }
+        spans.push(Span::styled(entry.msg[m.clone()].to_string(), match_style));
+        last = m.end;
+    }
+    if last < entry.msg.len() {
+        spans.push(Span::styled(entry.msg[last..].to_string(), style));
+    }
+    Spans::from(spans)
+}
+
+/// Text field currently being edited.
+#[derive(PartialEq)]
+enum Editing {
+    Nothing,
+    NodeFilter,
+    Search,
+}
+
+pub struct Logs {
+    rosout: RosoutListener,
+    filter: LogFilter,
+    editing: Editing,
+    search_input: String,
+    search: Option<Regex>,
+    /// Result of the last export, shown in the header.
+    export_status: Option<Result<String, String>>,
+    /// Number of lines scrolled up from the newest message. 0 means auto-scroll.
+    scroll: usize,
+    theme: Theme,
+}
+
+impl Logs {
+    pub fn new(config: LogsConfig, theme: Theme) -> Logs {
+        let filter = LogFilter {
+            min_level: level_from_name(&config.min_loglevel).unwrap_or(Log::DEBUG),
+            node: String::new(),
+        };
+        Logs {
+            rosout: RosoutListener::new(config),
+            filter,
+            editing: Editing::Nothing,
+            search_input: String::new(),
+            search: None,
+            export_status: None,
+            scroll: 0,
+            theme,
+        }
+    }
+
+    /// Returns the indices of the shown entries with at least one match, and the number of shown entries.
+    fn matching_lines(&self) -> (Vec<usize>, usize) {
+        let buffer = self.rosout.buffer.read().unwrap();
+        let search = match &self.search {
+            Some(search) => search,
+            None => return (Vec::new(), buffer.filtered(&self.filter).count()),
+        };
+        let mut len = 0;
+        let mut lines = Vec::new();
+        for (i, (_, matches)) in buffer.search(&self.filter, search).enumerate() {
+            if !matches.is_empty() {
+                lines.push(i);
+            }
+            len += 1;
+        }
+        (lines, len)
+    }
+
+    /// Scrolls so that the next (newer) or previous (older) match is the last shown line.
+    /// If `include_current` is true, the current last shown line is also considered.
+    fn jump_to_match(&mut self, forward: bool, include_current: bool) {
+        let (lines, len) = self.matching_lines();
+        if len == 0 {
+            return;
+        }
+        let current = len - 1 - self.scroll.min(len - 1);
+        let target = if forward {
+            lines
+                .into_iter()
+                .find(|&i| i > current || (include_current && i == current))
+        } else {
+            lines
+                .into_iter()
+                .rev()
+                .find(|&i| i < current || (include_current && i == current))
+        };
+        if let Some(i) = target {
+            self.scroll = len - 1 - i;
+        }
+    }
+
+    /// Changes the minimum severity to the next or previous level.
+    fn cycle_min_level(&mut self, forward: bool) {
+        let i = LEVELS
+            .iter()
+            .position(|&level| level == self.filter.min_level)
+            .unwrap_or(0);
+        let i = if forward {
+            (i + 1) % LEVELS.len()
+        } else {
+            (i + LEVELS.len() - 1) % LEVELS.len()
+        };
+        self.filter.min_level = LEVELS[i];
+        self.scroll = 0;
+    }
+}
+
+impl AppMode for Logs {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        let len = self
+            .rosout
+            .buffer
+            .read()
+            .unwrap()
+            .filtered(&self.filter)
+            .count();
+        match input.as_str() {
+            input::INCREMENT_STEP => self.cycle_min_level(true),
+            input::DECREMENT_STEP => self.cycle_min_level(false),
+            input::FILTER => self.editing = Editing::NodeFilter,
+            input::SEARCH => {
+                self.search_input.clear();
+                self.editing = Editing::Search;
+            }
+            input::EXPORT => {
+                let result = self.rosout.buffer.read().unwrap().export();
+                self.export_status = Some(result.map_err(|e| e.to_string()));
+            }
+            input::NEXT => self.jump_to_match(true, false),
+            input::PREVIOUS => self.jump_to_match(false, false),
+            input::UP => self.scroll = (self.scroll + 1).min(len.saturating_sub(1)),
+            input::DOWN => self.scroll = self.scroll.saturating_sub(1),
+            input::CANCEL => {
+                self.search = None;
+                self.scroll = 0;
+            }
+            input::CONFIRM => {
+                self.rosout.toggle_buffering();
+            }
+            _ => (),
+        }
+    }
+
+    fn is_capturing_text(&self) -> bool {
+        self.editing != Editing::Nothing
+    }
+
+    fn handle_text_input(&mut self, key: KeyCode) {
+        let text = match self.editing {
+            Editing::NodeFilter => &mut self.filter.node,
+            Editing::Search => &mut self.search_input,
+            Editing::Nothing => return,
+        };
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => {
+                if self.editing == Editing::Search {
+                    // An invalid expression clears the search
+                    self.search = Regex::new(&self.search_input).ok();
+                    self.editing = Editing::Nothing;
+                    self.jump_to_match(false, true);
+                    return;
+                }
+                self.editing = Editing::Nothing;
+            }
+            KeyCode::Esc => self.editing = Editing::Nothing,
+            _ => (),
+        }
+        if self.editing != Editing::Search {
+            self.scroll = 0;
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the log messages of the nodes.".to_string(),
+            "The view follows the newest messages, unless scrolled up.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [input::UP.to_string(), "Scrolls up.".to_string()],
+            [input::DOWN.to_string(), "Scrolls down.".to_string()],
+            [
+                input::CANCEL.to_string(),
+                "Clears the search and scrolls to the newest message.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Pauses or resumes the reception of messages.".to_string(),
+            ],
+            [
+                input::INCREMENT_STEP.to_string(),
+                "Increases the minimum severity.".to_string(),
+            ],
+            [
+                input::DECREMENT_STEP.to_string(),
+                "Decreases the minimum severity.".to_string(),
+            ],
+            [
+                input::FILTER.to_string(),
+                "Edits the node name filter. Enter or Esc to stop editing.".to_string(),
+            ],
+            [
+                input::SEARCH.to_string(),
+                "Searches the messages with a regular expression. Enter to confirm.".to_string(),
+            ],
+            [
+                input::NEXT.to_string(),
+                "Jumps to the next (newer) match.".to_string(),
+            ],
+            [
+                input::EXPORT.to_string(),
+                "Writes all the buffered messages to a file in the current directory.".to_string(),
+            ],
+            [
+                input::PREVIOUS.to_string(),
+                "Jumps to the previous (older) match.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Logs".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for Logs {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let mut header_spans = vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" - Topic: /".to_string() + &self.rosout.config.topic),
+            Span::raw(format!(
+                " - Min level: {} - Node: {}",
+                level_name(self.filter.min_level),
+                self.filter.node
+            )),
+        ];
+        let cursor = Span::styled("_", Style::default().add_modifier(Modifier::SLOW_BLINK));
+        if self.editing == Editing::NodeFilter {
+            header_spans.push(cursor.clone());
+        }
+        if self.editing == Editing::Search {
+            header_spans.push(Span::raw(format!(" - Search: {}", self.search_input)));
+            header_spans.push(cursor);
+        } else if let Some(search) = &self.search {
+            header_spans.push(Span::raw(format!(" - Search: {}", search.as_str())));
+        }
+        match &self.export_status {
+            Some(Ok(filename)) => header_spans.push(Span::styled(
+                format!(" [Exported to {}]", filename),
+                Style::default().fg(self.theme.ok),
+            )),
+            Some(Err(e)) => header_spans.push(Span::styled(
+                format!(" [Export failed: {}]", e),
+                Style::default().fg(self.theme.error),
+            )),
+            None => (),
+        }
+        if !self.rosout.is_buffering() {
+            header_spans.push(Span::styled(
+                " [PAUSED]",
+                Style::default().fg(self.theme.warning),
+            ));
+        }
+        let header = Paragraph::new(Spans::from(header_spans))
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::default().fg(self.theme.text))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        // Show the messages that fit on the screen, ending with the newest one
+        // unless scrolled up
+        let buffer = self.rosout.buffer.read().unwrap();
+        let len = buffer.filtered(&self.filter).count();
+        let end = len.saturating_sub(self.scroll);
+        let start = end.saturating_sub(chunks[1].height as usize);
+        let lines: Vec<Spans> = match &self.search {
+            Some(search) => buffer
+                .search(&self.filter, search)
+                .skip(start)
+                .take(end - start)
+                .map(|(entry, matches)| entry_to_spans(entry, &matches, &self.theme))
+                .collect(),
+            None => buffer
+                .filtered(&self.filter)
+                .skip(start)
+                .take(end - start)
+                .map(|entry| entry_to_spans(entry, &[], &self.theme))
+                .collect(),
+        };
+        let logs = Paragraph::new(lines).block(Block::default().borders(Borders::NONE));
+        f.render_widget(logs, chunks[1]);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Logs {}
@@ -0,0 +1,227 @@
+//! Map editor mode allows to paint the cells of a map with the cursor and to publish the
+//! edited grid, e.g. to erase the traces of people or to close a doorway in the field.
+
+use crate::app_modes::cursor::Cursor;
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use crate::config::MapEditorConfig;
+use crate::map::MapListener;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use rosrust_msg::nav_msgs::OccupancyGrid;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use tui::backend::Backend;
+
+/// Values with which the cells are painted, in turn.
+const BRUSH_VALUES: [(&str, i8); 3] = [("occupied", 100), ("free", 0), ("unknown", -1)];
+
+pub struct MapEditor {
+    viewport: Rc<RefCell<Viewport>>,
+    config: MapEditorConfig,
+    cursor: Cursor,
+    /// Index of the painted value in the brush values.
+    brush: usize,
+    /// If true, the cells are painted as the cursor moves.
+    pen_down: bool,
+    /// Number of strokes since the map was published.
+    strokes: usize,
+    publisher: rosrust::Publisher<OccupancyGrid>,
+}
+
+impl MapEditor {
+    pub fn new(viewport: Rc<RefCell<Viewport>>, config: MapEditorConfig) -> MapEditor {
+        let robot_pose = viewport.borrow().robot_pose();
+        MapEditor {
+            viewport,
+            publisher: rosrust::publish(&config.topic, 1).unwrap(),
+            config,
+            cursor: Cursor::new(robot_pose, false),
+            brush: 0,
+            pen_down: false,
+            strokes: 0,
+        }
+    }
+
+    /// Returns the edited map: the one of the configured topic, or the first one.
+    fn map<'a>(&self, viewport: &'a Viewport) -> Option<&'a MapListener> {
+        viewport.listeners.maps.iter().find(|map| {
+            self.config
+                .map_topic
+                .as_ref()
+                .map_or(true, |topic| &map.config.topic == topic)
+        })
+    }
+
+    /// Paints the cells around the given point with the value of the brush.
+    fn paint(&mut self, point: (f64, f64)) {
+        let viewport = self.viewport.borrow();
+        if let Some(map) = self.map(&viewport) {
+            if map.paint(point, self.config.brush_radius, BRUSH_VALUES[self.brush].1) {
+                self.strokes += 1;
+            }
+        }
+    }
+
+    /// Publishes the edited grid in its own frame.
+    fn publish(&mut self) {
+        let grid = {
+            let viewport = self.viewport.borrow();
+            self.map(&viewport).and_then(|map| map.grid())
+        };
+        if let Some(mut grid) = grid {
+            grid.header.stamp = rosrust::now();
+            self.publisher.send(grid).unwrap();
+            self.strokes = 0;
+        }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for MapEditor {}
+
+impl AppMode for MapEditor {
+    fn run(&mut self) {
+        export::record(self, &self.viewport);
+    }
+
+    fn reset(&mut self) {
+        self.pen_down = false;
+        if self.strokes == 0 {
+            self.cursor.pose = self.viewport.borrow().robot_pose();
+        }
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
+        if self.cursor.handle_input(input) {
+            if self.pen_down {
+                self.paint(self.cursor.position());
+            }
+            return;
+        }
+        match input.as_str() {
+            input::CONFIRM => {
+                self.pen_down = !self.pen_down;
+                if self.pen_down {
+                    self.paint(self.cursor.position());
+                }
+            }
+            input::CANCEL => self.pen_down = false,
+            input::NEXT => self.brush = (self.brush + 1) % BRUSH_VALUES.len(),
+            input::PREVIOUS => {
+                self.brush = (self.brush + BRUSH_VALUES.len() - 1) % BRUSH_VALUES.len()
+            }
+            input::PUBLISH => self.publish(),
+            _ => (),
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                let point = self.viewport.borrow().terminal_to_world(
+                    self.x_bounds(),
+                    self.y_bounds(),
+                    event.column,
+                    event.row,
+                );
+                // The cursor stays in place, so that the view does not move while dragging
+                if let Some(point) = point {
+                    self.paint(point);
+                }
+            }
+            _ => self.viewport.borrow_mut().handle_mouse(event),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Map Editor".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to paint the cells of a map and to publish the edited map."
+                .to_string(),
+            "The edits are lost when a new map is received on the topic of the map.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = self.cursor.get_keymap();
+        keymap.extend([
+            [
+                input::CONFIRM.to_string(),
+                "Puts the pen down to paint the cells under the cursor, or lifts it.".to_string(),
+            ],
+            [input::CANCEL.to_string(), "Lifts the pen.".to_string()],
+            [
+                input::NEXT.to_string(),
+                "Paints the next value: occupied, free or unknown.".to_string(),
+            ],
+            [
+                input::PREVIOUS.to_string(),
+                "Paints the previous value.".to_string(),
+            ],
+            [
+                input::PUBLISH.to_string(),
+                "Publishes the edited map.".to_string(),
+            ],
+        ]);
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for MapEditor {
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        let color = if self.pen_down {
+            viewport.theme.highlight
+        } else {
+            viewport.theme.accent
+        };
+        self.cursor.draw(ctx, &viewport, color);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .x_bounds_around(self.cursor.position())
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .y_bounds_around(self.cursor.position())
+    }
+
+    fn info(&self) -> String {
+        let (x, y) = self.cursor.position();
+        let topic = {
+            let viewport = self.viewport.borrow();
+            self.map(&viewport).map(|map| map.config.topic.clone())
+        };
+        let mut info = match topic {
+            Some(topic) => format!(
+                "Map: /{}, Brush: {}, Pen: {}, Edits: {}",
+                topic,
+                BRUSH_VALUES[self.brush].0,
+                if self.pen_down { "down" } else { "up" },
+                self.strokes
+            ),
+            None => "No map to edit".to_string(),
+        };
+        info += &format!(
+            ", Cursor: ({:.2}, {:.2}), Cursor step: {:.2}",
+            x, y, self.cursor.step
+        );
+        info
+    }
+}
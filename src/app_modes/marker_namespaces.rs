@@ -0,0 +1,149 @@
+//! Marker namespaces mode allows to hide and show the markers of single namespaces.
+
+use crate::app_modes::viewport::Viewport;
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap};
+use tui::Frame;
+
+pub struct MarkerNamespaces {
+    viewport: Rc<RefCell<Viewport>>,
+    selected: usize,
+}
+
+impl MarkerNamespaces {
+    pub fn new(viewport: Rc<RefCell<Viewport>>) -> MarkerNamespaces {
+        MarkerNamespaces {
+            viewport,
+            selected: 0,
+        }
+    }
+
+    fn get_namespaces(&self) -> Vec<(String, usize, bool)> {
+        self.viewport.borrow().listeners.markers.get_namespaces()
+    }
+}
+
+impl AppMode for MarkerNamespaces {
+    fn run(&mut self) {}
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        let namespaces = self.get_namespaces();
+        if namespaces.is_empty() {
+            return;
+        }
+        // Namespaces may have been removed since the last input
+        self.selected = self.selected.min(namespaces.len() - 1);
+        match input.as_str() {
+            input::UP => {
+                self.selected = (self.selected + namespaces.len() - 1) % namespaces.len();
+            }
+            input::DOWN => {
+                self.selected = (self.selected + 1) % namespaces.len();
+            }
+            input::CONFIRM => {
+                self.viewport
+                    .borrow()
+                    .listeners
+                    .markers
+                    .toggle_namespace(&namespaces[self.selected].0);
+            }
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode lists the namespaces of the received markers.".to_string(),
+            "Hidden namespaces are not drawn in the viewport, but their markers are still received."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous namespace.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next namespace.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Hides or shows the selected namespace.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Marker Namespaces".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for MarkerNamespaces {
+    fn draw(&self, f: &mut Frame<B>) {
+        let theme = self.viewport.borrow().theme.clone();
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let header = Paragraph::new(Spans::from(Span::styled(
+            self.get_name(),
+            Style::default()
+                .fg(theme.title)
+                .add_modifier(Modifier::BOLD),
+        )))
+        .block(Block::default().borders(Borders::NONE))
+        .style(Style::default().fg(theme.text))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let rows: Vec<Row> = self
+            .get_namespaces()
+            .into_iter()
+            .map(|(ns, count, visible)| {
+                let style = if visible {
+                    Style::default()
+                } else {
+                    Style::default().fg(theme.inactive)
+                };
+                Row::new(vec![
+                    if visible { "[x]" } else { "[ ]" }.to_string(),
+                    if ns.is_empty() {
+                        "<no namespace>".to_string()
+                    } else {
+                        ns
+                    },
+                    count.to_string(),
+                ])
+                .style(style)
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Visible", "Namespace", "Markers"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&[
+                Constraint::Length(8),
+                Constraint::Percentage(70),
+                Constraint::Length(8),
+            ])
+            .highlight_style(Style::default().fg(theme.highlight))
+            .highlight_symbol(">> ");
+        let mut state = TableState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(table, chunks[1], &mut state);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for MarkerNamespaces {}
@@ -0,0 +1,231 @@
+//! Measure mode allows to measure distances and angles in the fixed frame with a cursor.
+
+use crate::app_modes::cursor::Cursor;
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use std::cell::{Ref, RefCell};
+use std::f64::consts::PI;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::widgets::canvas::Line;
+
+#[derive(PartialEq)]
+enum Tool {
+    /// Measures the distance from a start point to the cursor.
+    Distance,
+    /// Measures the bearing of the segment from a vertex to the cursor, or the angle
+    /// between the rays from the vertex to a second point and to the cursor.
+    Angle,
+}
+
+/// Normalizes an angle to ]-pi, pi].
+fn normalize_angle(angle: f64) -> f64 {
+    let angle = angle.rem_euclid(2.0 * PI);
+    if angle > PI {
+        angle - 2.0 * PI
+    } else {
+        angle
+    }
+}
+
+fn format_angle(angle: f64) -> String {
+    format!("{:.1}° ({:.3} rad)", angle.to_degrees(), angle)
+}
+
+pub struct Measure {
+    viewport: Rc<RefCell<Viewport>>,
+    cursor: Cursor,
+    tool: Tool,
+    /// Points placed with the current tool: the start point, or the vertex and the end of the first ray.
+    points: Vec<(f64, f64)>,
+}
+
+impl Measure {
+    pub fn new(viewport: Rc<RefCell<Viewport>>) -> Measure {
+        let robot_pose = viewport.borrow().robot_pose();
+        Measure {
+            viewport,
+            cursor: Cursor::new(robot_pose, false),
+            tool: Tool::Distance,
+            points: Vec::new(),
+        }
+    }
+
+    fn max_points(&self) -> usize {
+        match self.tool {
+            Tool::Distance => 1,
+            Tool::Angle => 2,
+        }
+    }
+
+    /// Places a point at the cursor, starting a new measurement once all the points are placed.
+    fn place_point(&mut self) {
+        if self.points.len() == self.max_points() {
+            self.points.clear();
+        }
+        self.points.push(self.cursor.position());
+    }
+
+    fn switch_tool(&mut self) {
+        self.tool = match self.tool {
+            Tool::Distance => Tool::Angle,
+            Tool::Angle => Tool::Distance,
+        };
+        self.points.clear();
+    }
+
+    fn measurement(&self) -> Option<String> {
+        let (x, y) = self.cursor.position();
+        match (&self.tool, self.points.as_slice()) {
+            (Tool::Distance, [start]) => {
+                let (dx, dy) = (x - start.0, y - start.1);
+                Some(format!(
+                    "Distance: {:.3}m (dx: {:.3}, dy: {:.3})",
+                    dx.hypot(dy),
+                    dx,
+                    dy
+                ))
+            }
+            (Tool::Angle, [vertex]) => Some(format!(
+                "Bearing: {}",
+                format_angle((y - vertex.1).atan2(x - vertex.0))
+            )),
+            (Tool::Angle, [vertex, first]) => {
+                let first_angle = (first.1 - vertex.1).atan2(first.0 - vertex.0);
+                let second_angle = (y - vertex.1).atan2(x - vertex.0);
+                Some(format!(
+                    "Angle: {}",
+                    format_angle(normalize_angle(second_angle - first_angle))
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Measure {}
+
+impl AppMode for Measure {
+    fn run(&mut self) {
+        export::record(self, &self.viewport);
+    }
+
+    fn reset(&mut self) {
+        if self.points.is_empty() {
+            self.cursor.pose = self.viewport.borrow().robot_pose();
+        }
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
+        if self.cursor.handle_input(input) {
+            return;
+        }
+        match input.as_str() {
+            input::CONFIRM => self.place_point(),
+            input::CANCEL => self.points.clear(),
+            input::NEXT | input::PREVIOUS => self.switch_tool(),
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Measure".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to measure distances and angles in the fixed frame.".to_string(),
+            "The measurement up to the cursor is shown in the top bar, with the values of the maps under the cursor."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = self.cursor.get_keymap();
+        keymap.extend([
+            [
+                input::CONFIRM.to_string(),
+                "Places the start point, or the vertex and the first ray of the angle, at the cursor."
+                    .to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Removes the placed points.".to_string(),
+            ],
+            [
+                input::NEXT.to_string(),
+                "Switches between distance and angle measurement.".to_string(),
+            ],
+            [
+                input::PREVIOUS.to_string(),
+                "Switches between distance and angle measurement.".to_string(),
+            ],
+        ]);
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for Measure {
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        let rotation = viewport.rotation();
+        let cursor = self.cursor.position();
+        let rays = match (&self.tool, self.points.as_slice()) {
+            (_, [start]) => vec![(*start, cursor)],
+            (Tool::Angle, [vertex, first]) => vec![(*vertex, *first), (*vertex, cursor)],
+            _ => vec![],
+        };
+        for (from, to) in rays {
+            ctx.draw_line(&rotation.line(&Line {
+                x1: from.0,
+                y1: from.1,
+                x2: to.0,
+                y2: to.1,
+                color: viewport.theme.highlight,
+            }));
+        }
+        self.cursor.draw(ctx, &viewport, viewport.theme.accent);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .x_bounds_around(self.cursor.position())
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .y_bounds_around(self.cursor.position())
+    }
+
+    fn info(&self) -> String {
+        let (x, y) = self.cursor.position();
+        let tool = match self.tool {
+            Tool::Distance => "Distance",
+            Tool::Angle => "Angle",
+        };
+        let mut info = format!("Tool: {}, Cursor: ({:.2}, {:.2})", tool, x, y);
+        for (topic, value) in self.viewport.borrow().map_values_at(x, y) {
+            match value {
+                -1 => info += &format!(", {}: unknown", topic),
+                _ => info += &format!(", {}: {}", topic, value),
+            }
+        }
+        if let Some(measurement) = self.measurement() {
+            info += &format!(", {}", measurement);
+        }
+        info += &format!(", Cursor step: {:.2}", self.cursor.step);
+        info
+    }
+}
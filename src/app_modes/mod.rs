@@ -1,11 +1,30 @@
 //! A module that contains all the builing blocks to create app modes, as well as the app modes themselves.
 
+pub mod bag_playback;
+pub mod cursor;
+pub mod dashboard;
+pub mod diagnostics;
+pub mod export;
 pub mod image_view;
+pub mod joint_teleop;
+pub mod logs;
+pub mod map_editor;
+pub mod marker_namespaces;
+pub mod measure;
+pub mod multi_view;
+pub mod node_manager;
+pub mod param_browser;
+pub mod polygon_editor;
 pub mod send_pose;
+pub mod split_view;
 pub mod teleoperate;
 pub mod topic_managment;
+pub mod topic_monitor;
+pub mod viewer;
 pub mod viewport;
+pub mod waypoint_editor;
 
+use crossterm::event::{KeyCode, MouseEvent};
 use tui::backend::Backend;
 use tui::Frame;
 
@@ -21,6 +40,9 @@ pub mod input {
     pub const MODE_7: &str = "Switch to mode 7";
     pub const MODE_8: &str = "Switch to mode 8";
     pub const MODE_9: &str = "Switch to mode 9";
+    pub const MODE_10: &str = "Switch to mode 10";
+    pub const NEXT_MODE: &str = "Switch to next mode";
+    pub const PREVIOUS_MODE: &str = "Switch to previous mode";
     pub const LEFT: &str = "Left";
     pub const RIGHT: &str = "Right";
     pub const UP: &str = "Up";
@@ -36,6 +58,43 @@ pub mod input {
     pub const NEXT: &str = "Next";
     pub const PREVIOUS: &str = "Previous";
     pub const SHOW_HELP: &str = "Show help";
+    pub const FILTER: &str = "Filter";
+    pub const SEARCH: &str = "Search";
+    pub const EXPORT: &str = "Export";
+    pub const KILL: &str = "Kill";
+    pub const DELETE: &str = "Delete";
+    pub const MOVE_FORWARD: &str = "Move forward";
+    pub const MOVE_BACKWARD: &str = "Move backward";
+    pub const PUBLISH: &str = "Publish";
+    pub const ENQUEUE: &str = "Enqueue";
+    pub const ENTER_COORDINATES: &str = "Enter coordinates";
+    pub const NEXT_TARGET_FRAME: &str = "Next target frame";
+    pub const SELECT_PRESET: &str = "Select preset";
+    pub const SAVE_PRESET: &str = "Save preset";
+    pub const PAN_UP: &str = "Pan up";
+    pub const PAN_DOWN: &str = "Pan down";
+    pub const PAN_LEFT: &str = "Pan left";
+    pub const PAN_RIGHT: &str = "Pan right";
+    pub const RECENTER: &str = "Recenter";
+    pub const FOLLOW_NEXT_FRAME: &str = "Follow next frame";
+    pub const FREE_CAMERA: &str = "Free camera";
+    pub const SAVE_BOOKMARK: &str = "Save bookmark";
+    pub const NEXT_BOOKMARK: &str = "Next bookmark";
+    pub const ROBOT_UP: &str = "Robot up";
+    pub const SHOW_LEGEND: &str = "Show legend";
+    pub const SHOW_LAYERS: &str = "Show layers";
+    pub const ROTATE_VIEW_LEFT: &str = "Rotate view counter-clockwise";
+    pub const ROTATE_VIEW_RIGHT: &str = "Rotate view clockwise";
+    pub const CLEAR_TRAIL: &str = "Clear trail";
+    pub const CLEAR_MEMORY: &str = "Clear memory";
+    pub const SAVE_MAP: &str = "Save map";
+    pub const NEXT_CANVAS_MARKER: &str = "Next canvas marker";
+    pub const RECORD: &str = "Record";
+    pub const RECORD_SEQUENCE: &str = "Record sequence";
+    pub const REPLAY_SEQUENCE: &str = "Replay sequence";
+    pub const SWITCH_FOCUS: &str = "Switch focus";
+    pub const SHOW_MINIMAP: &str = "Show minimap";
+    pub const NEXT_PROJECTION: &str = "Next projection";
     pub const UNMAPPED: &str = "Any other";
 }
 
@@ -62,6 +121,24 @@ pub trait AppMode {
 
     /// Returns the name of the mode.
     fn get_name(&self) -> String;
+
+    /// Returns true if the mode is currently capturing text, e.g. to edit a filter.
+    /// In that case, the keys are passed to handle_text_input instead of being mapped to inputs.
+    fn is_capturing_text(&self) -> bool {
+        false
+    }
+
+    /// Handles a key pressed while capturing text.
+    ///
+    /// # Arguments
+    /// - `key` : the pressed key
+    fn handle_text_input(&mut self, _key: KeyCode) {}
+
+    /// Handles a mouse event.
+    ///
+    /// # Arguments
+    /// - `event` : the mouse event, with the position in terminal cells
+    fn handle_mouse(&mut self, _event: MouseEvent) {}
 }
 
 /// Represents something that can be drawn on the screen
@@ -0,0 +1,197 @@
+//! Multi view mode shows several views of the viewport side by side, each with its own zoom,
+//! followed frame and visible layers.
+
+use crate::app_modes::viewer::Viewer;
+use crate::app_modes::viewport::{draw_viewport, ViewState, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::MultiViewConfig;
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::Span;
+use tui::widgets::{Block, Borders};
+use tui::Frame;
+
+pub struct MultiView {
+    viewport: Rc<RefCell<Viewport>>,
+    viewer: Viewer,
+    names: Vec<String>,
+    /// States of the views. The one of the focused view is only up to date while it is not
+    /// loaded in the viewport.
+    views: Vec<ViewState>,
+    focus: usize,
+    /// State of the viewport in the other modes, while this mode has loaded its focused view.
+    saved: Option<ViewState>,
+}
+
+impl MultiView {
+    pub fn new(viewport: Rc<RefCell<Viewport>>, config: MultiViewConfig) -> MultiView {
+        let views = {
+            let viewport = viewport.borrow();
+            if config.panes.is_empty() {
+                vec![viewport.view_state()]
+            } else {
+                config
+                    .panes
+                    .iter()
+                    .map(|pane| viewport.pane_view_state(pane))
+                    .collect()
+            }
+        };
+        let names = if config.panes.is_empty() {
+            vec!["View".to_string()]
+        } else {
+            config.panes.iter().map(|pane| pane.name.clone()).collect()
+        };
+        MultiView {
+            viewer: Viewer::new("Multi view", viewport.clone()),
+            viewport,
+            names,
+            views,
+            focus: 0,
+            saved: None,
+        }
+    }
+
+    /// Splits the terminal into the areas of the views.
+    fn panes(&self, area: Rect) -> Vec<Rect> {
+        let constraints: Vec<Constraint> = self
+            .views
+            .iter()
+            .map(|_| Constraint::Ratio(1, self.views.len() as u32))
+            .collect();
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(area)
+    }
+
+    /// Loads the focused view in the viewport on entering the mode.
+    fn load_focused_view(&mut self) {
+        if self.saved.is_none() {
+            let mut viewport = self.viewport.borrow_mut();
+            self.saved = Some(viewport.view_state());
+            viewport.set_view_state(self.views[self.focus].clone());
+        }
+    }
+
+    /// Keeps the state of the focused view and loads the given one in the viewport.
+    fn focus_view(&mut self, index: usize) {
+        self.load_focused_view();
+        let mut viewport = self.viewport.borrow_mut();
+        self.views[self.focus] = viewport.view_state();
+        self.focus = index;
+        viewport.set_view_state(self.views[index].clone());
+    }
+
+    fn block(&self, index: usize) -> Block {
+        let style = if index == self.focus {
+            Style::default()
+                .fg(self.viewport.borrow().theme.highlight)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(self.viewport.borrow().theme.inactive)
+        };
+        Block::default()
+            .title(Span::styled(self.names[index].clone(), style))
+            .borders(Borders::ALL)
+            .border_style(style)
+    }
+}
+
+impl<B: Backend> BaseMode<B> for MultiView {}
+
+impl AppMode for MultiView {
+    fn run(&mut self) {
+        self.load_focused_view();
+        if let Ok((width, height)) = crossterm::terminal::size() {
+            let pane = self
+                .block(self.focus)
+                .inner(self.panes(Rect::new(0, 0, width, height))[self.focus]);
+            self.viewport.borrow_mut().terminal_size = (pane.width.max(1), pane.height.max(1));
+        }
+    }
+
+    fn reset(&mut self) {
+        // The other modes get the viewport back as they left it
+        if let Some(saved) = self.saved.take() {
+            let mut viewport = self.viewport.borrow_mut();
+            self.views[self.focus] = viewport.view_state();
+            viewport.set_view_state(saved);
+        }
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        if input == input::SWITCH_FOCUS {
+            self.focus_view((self.focus + 1) % self.views.len());
+        } else {
+            self.load_focused_view();
+            self.viewport.borrow_mut().handle_input(input);
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        if let MouseEventKind::Down(MouseButton::Left) = event.kind {
+            let (width, height) = match crossterm::terminal::size() {
+                Ok(size) => size,
+                Err(_) => return,
+            };
+            let clicked = self
+                .panes(Rect::new(0, 0, width, height))
+                .iter()
+                .position(|pane| event.column >= pane.x && event.column < pane.x + pane.width);
+            if let Some(index) = clicked {
+                if index != self.focus {
+                    self.focus_view(index);
+                }
+            }
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows several views side by side, configured under multi_view.".to_string(),
+            "The keys of the viewport act on the focused view, which is highlighted.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = vec![[
+            input::SWITCH_FOCUS.to_string(),
+            "Focuses the next view. Clicking a view also focuses it.".to_string(),
+        ]];
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+
+    fn get_name(&self) -> String {
+        "Multi view".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for MultiView {
+    fn draw(&self, f: &mut Frame<B>) {
+        let focused = self.viewport.borrow().view_state();
+        let focused_size = self.viewport.borrow().terminal_size;
+        for (i, pane) in self.panes(f.size()).into_iter().enumerate() {
+            let block = self.block(i);
+            let area = block.inner(pane);
+            f.render_widget(block, pane);
+            {
+                let mut viewport = self.viewport.borrow_mut();
+                if i != self.focus {
+                    viewport.set_view_state(self.views[i].clone());
+                }
+                viewport.terminal_size = (area.width.max(1), area.height.max(1));
+            }
+            draw_viewport(&self.viewer, f, area);
+            if i != self.focus {
+                self.viewport.borrow_mut().set_view_state(focused.clone());
+            }
+        }
+        self.viewport.borrow_mut().terminal_size = focused_size;
+    }
+}
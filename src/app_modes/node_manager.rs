@@ -0,0 +1,261 @@
+//! Node manager mode lists the running nodes with their connections, and allows to shut them down.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::theme::Theme;
+use rosrust;
+use std::collections::BTreeMap;
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use tui::Frame;
+
+const REFRESH_PERIOD: Duration = Duration::from_secs(1);
+
+/// Topics and services of a node, as registered on the master.
+#[derive(Default)]
+struct NodeInfo {
+    publications: Vec<String>,
+    subscriptions: Vec<String>,
+    services: Vec<String>,
+}
+
+/// Builds the list of nodes from the state of the master.
+fn get_nodes() -> BTreeMap<String, NodeInfo> {
+    let mut nodes: BTreeMap<String, NodeInfo> = BTreeMap::new();
+    let state = match rosrust::state() {
+        Ok(state) => state,
+        Err(_e) => return nodes,
+    };
+    for topic in state.publishers {
+        for node in topic.connections {
+            nodes
+                .entry(node)
+                .or_default()
+                .publications
+                .push(topic.name.clone());
+        }
+    }
+    for topic in state.subscribers {
+        for node in topic.connections {
+            nodes
+                .entry(node)
+                .or_default()
+                .subscriptions
+                .push(topic.name.clone());
+        }
+    }
+    for service in state.services {
+        for node in service.connections {
+            nodes
+                .entry(node)
+                .or_default()
+                .services
+                .push(service.name.clone());
+        }
+    }
+    nodes
+}
+
+pub struct NodeManager {
+    nodes: BTreeMap<String, NodeInfo>,
+    selected: usize,
+    last_refresh: Option<Instant>,
+    /// Node to be shut down, waiting for confirmation.
+    kill_request: Option<String>,
+    /// Result of the last shutdown, shown in the header.
+    status: Option<Result<String, String>>,
+    theme: Theme,
+}
+
+impl NodeManager {
+    pub fn new(theme: Theme) -> NodeManager {
+        NodeManager {
+            nodes: BTreeMap::new(),
+            selected: 0,
+            last_refresh: None,
+            kill_request: None,
+            status: None,
+            theme,
+        }
+    }
+
+    fn selected_node(&self) -> Option<String> {
+        self.nodes.keys().nth(self.selected).cloned()
+    }
+
+    fn refresh(&mut self) {
+        self.nodes = get_nodes();
+        self.selected = self.selected.min(self.nodes.len().saturating_sub(1));
+        self.last_refresh = Some(Instant::now());
+    }
+
+    /// Shuts the node down with `rosnode kill`.
+    fn kill(&mut self, node: &str) {
+        self.status = Some(
+            match Command::new("rosnode").arg("kill").arg(node).output() {
+                Ok(output) if output.status.success() => Ok(format!("Killed {}", node)),
+                Ok(output) => Err(String::from_utf8_lossy(&output.stderr).trim().to_string()),
+                Err(e) => Err(format!("Could not run rosnode: {}", e)),
+            },
+        );
+        self.refresh();
+    }
+}
+
+impl AppMode for NodeManager {
+    fn run(&mut self) {
+        let outdated = match self.last_refresh {
+            Some(last) => last.elapsed() > REFRESH_PERIOD,
+            None => true,
+        };
+        if outdated {
+            self.refresh();
+        }
+    }
+
+    fn reset(&mut self) {
+        self.kill_request = None;
+        self.status = None;
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        // Any other key than confirm aborts a pending shutdown
+        if let Some(node) = self.kill_request.take() {
+            if input == input::CONFIRM {
+                self.kill(&node);
+            }
+            return;
+        }
+        match input.as_str() {
+            input::UP => {
+                if !self.nodes.is_empty() {
+                    self.selected = (self.selected + self.nodes.len() - 1) % self.nodes.len();
+                }
+            }
+            input::DOWN => {
+                if !self.nodes.is_empty() {
+                    self.selected = (self.selected + 1) % self.nodes.len();
+                }
+            }
+            input::KILL => self.kill_request = self.selected_node(),
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode lists the running nodes, with their publications, subscriptions and services."
+                .to_string(),
+            "Nodes can be shut down like with rosnode kill, after a confirmation.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous node.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next node.".to_string(),
+            ],
+            [
+                input::KILL.to_string(),
+                "Asks to shut the selected node down.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Confirms the shutdown. Any other key aborts it.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Node Manager".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for NodeManager {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let mut header_spans = vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" - {} nodes", self.nodes.len())),
+        ];
+        if let Some(node) = &self.kill_request {
+            header_spans.push(Span::styled(
+                format!(" - Shut {} down? Confirm to proceed", node),
+                Style::default().fg(self.theme.warning),
+            ));
+        } else {
+            match &self.status {
+                Some(Ok(msg)) => header_spans.push(Span::styled(
+                    format!(" - {}", msg),
+                    Style::default().fg(self.theme.ok),
+                )),
+                Some(Err(msg)) => header_spans.push(Span::styled(
+                    format!(" - {}", msg),
+                    Style::default().fg(self.theme.error),
+                )),
+                None => (),
+            }
+        }
+        let header = Paragraph::new(Spans::from(header_spans))
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::default().fg(self.theme.text))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let body = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(35), Constraint::Percentage(65)].as_ref())
+            .split(chunks[1]);
+        let items: Vec<ListItem> = self
+            .nodes
+            .keys()
+            .map(|node| ListItem::new(node.clone()))
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().title(" Nodes ").borders(Borders::ALL))
+            .highlight_style(Style::default().fg(self.theme.highlight))
+            .highlight_symbol(">> ");
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(list, body[0], &mut state);
+
+        let mut lines: Vec<Spans> = Vec::new();
+        if let Some(info) = self.selected_node().and_then(|node| self.nodes.get(&node)) {
+            for (title, names) in [
+                ("Publications", &info.publications),
+                ("Subscriptions", &info.subscriptions),
+                ("Services", &info.services),
+            ] {
+                lines.push(Spans::from(Span::styled(
+                    format!("{}:", title),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+                lines.extend(names.iter().map(|name| Spans::from(format!(" * {}", name))));
+                lines.push(Spans::from(""));
+            }
+        }
+        let info = Paragraph::new(lines)
+            .block(Block::default().title(" Info ").borders(Borders::ALL))
+            .wrap(Wrap { trim: false });
+        f.render_widget(info, body[1]);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for NodeManager {}
@@ -0,0 +1,378 @@
+//! Parameter browser mode shows the parameters of the parameter server as a tree,
+//! and allows to edit the primitive ones.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::theme::Theme;
+use crossterm::event::KeyCode;
+use rosrust;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use tui::Frame;
+
+#[derive(Clone)]
+enum ParamValue {
+    Bool(bool),
+    Int(i32),
+    Double(f64),
+    Str(String),
+    /// Lists and dictionaries, which cannot be edited.
+    Other,
+}
+
+impl fmt::Display for ParamValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamValue::Bool(v) => write!(f, "{}", v),
+            ParamValue::Int(v) => write!(f, "{}", v),
+            ParamValue::Double(v) => write!(f, "{}", v),
+            ParamValue::Str(v) => write!(f, "{}", v),
+            ParamValue::Other => write!(f, "<not editable>"),
+        }
+    }
+}
+
+impl ParamValue {
+    fn fetch(name: &str) -> ParamValue {
+        let param = match rosrust::param(name) {
+            Some(param) => param,
+            None => return ParamValue::Other,
+        };
+        if let Ok(v) = param.get::<bool>() {
+            ParamValue::Bool(v)
+        } else if let Ok(v) = param.get::<i32>() {
+            ParamValue::Int(v)
+        } else if let Ok(v) = param.get::<f64>() {
+            ParamValue::Double(v)
+        } else if let Ok(v) = param.get::<String>() {
+            ParamValue::Str(v)
+        } else {
+            ParamValue::Other
+        }
+    }
+
+    /// Parses a new value of the same type from the given text.
+    fn parse_same_type(&self, text: &str) -> Option<ParamValue> {
+        let text = text.trim();
+        match self {
+            ParamValue::Bool(_) => text.parse().ok().map(ParamValue::Bool),
+            ParamValue::Int(_) => text.parse().ok().map(ParamValue::Int),
+            ParamValue::Double(_) => text.parse().ok().map(ParamValue::Double),
+            ParamValue::Str(_) => Some(ParamValue::Str(text.to_string())),
+            ParamValue::Other => None,
+        }
+    }
+
+    fn store(&self, name: &str) -> Result<(), String> {
+        let param = rosrust::param(name).ok_or(format!("Invalid name {}", name))?;
+        let res = match self {
+            ParamValue::Bool(v) => param.set(v),
+            ParamValue::Int(v) => param.set(v),
+            ParamValue::Double(v) => param.set(v),
+            ParamValue::Str(v) => param.set(v),
+            ParamValue::Other => return Err("Value not editable".to_string()),
+        };
+        res.map_err(|e| e.to_string())
+    }
+}
+
+/// A line of the tree, as shown on screen.
+struct Row {
+    depth: usize,
+    /// Full name of the namespace or of the parameter.
+    name: String,
+    value: Option<ParamValue>,
+}
+
+enum State {
+    Browsing,
+    /// Typing the new value of the parameter.
+    Editing(String, String),
+    /// Waiting for the confirmation to set the parameter.
+    Confirming(String, ParamValue),
+}
+
+pub struct ParamBrowser {
+    params: BTreeMap<String, ParamValue>,
+    loaded: bool,
+    expanded: HashSet<String>,
+    selected: usize,
+    state: State,
+    /// Result of the last change, shown in the header.
+    status: Option<Result<String, String>>,
+    theme: Theme,
+}
+
+impl ParamBrowser {
+    pub fn new(theme: Theme) -> ParamBrowser {
+        ParamBrowser {
+            params: BTreeMap::new(),
+            loaded: false,
+            expanded: HashSet::new(),
+            selected: 0,
+            state: State::Browsing,
+            status: None,
+            theme,
+        }
+    }
+
+    fn load(&mut self) {
+        self.params = rosrust::parameters()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| {
+                let value = ParamValue::fetch(&name);
+                (name, value)
+            })
+            .collect();
+        self.loaded = true;
+    }
+
+    /// Flattens the parameters into the rows that are currently visible.
+    /// A namespace is shown only if all its parents are expanded.
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut shown_namespaces: HashSet<String> = HashSet::new();
+        for (name, value) in self.params.iter() {
+            let parts: Vec<&str> = name.trim_start_matches('/').split('/').collect();
+            let mut namespace = String::new();
+            let mut visible = true;
+            for (depth, part) in parts[..parts.len() - 1].iter().enumerate() {
+                namespace = namespace + "/" + part;
+                if shown_namespaces.insert(namespace.clone()) {
+                    rows.push(Row {
+                        depth,
+                        name: namespace.clone(),
+                        value: None,
+                    });
+                }
+                if !self.expanded.contains(&namespace) {
+                    visible = false;
+                    break;
+                }
+            }
+            if visible {
+                rows.push(Row {
+                    depth: parts.len() - 1,
+                    name: name.clone(),
+                    value: Some(value.clone()),
+                });
+            }
+        }
+        rows
+    }
+
+    fn select(&mut self, offset: i64) {
+        let len = self.rows().len() as i64;
+        if len == 0 {
+            return;
+        }
+        self.selected = (self.selected as i64 + offset).rem_euclid(len) as usize;
+    }
+
+    /// Expands or collapses the selected namespace, or starts editing the selected parameter.
+    fn activate_selected(&mut self) {
+        let rows = self.rows();
+        let row = match rows.get(self.selected) {
+            Some(row) => row,
+            None => return,
+        };
+        match &row.value {
+            None => {
+                if !self.expanded.remove(&row.name) {
+                    self.expanded.insert(row.name.clone());
+                }
+            }
+            Some(ParamValue::Other) => (),
+            Some(value) => self.state = State::Editing(row.name.clone(), value.to_string()),
+        }
+    }
+}
+
+impl AppMode for ParamBrowser {
+    fn run(&mut self) {
+        if !self.loaded {
+            self.load();
+        }
+    }
+
+    fn reset(&mut self) {
+        // Parameters are fetched again when entering the mode
+        self.loaded = false;
+        self.state = State::Browsing;
+        self.status = None;
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        // Any other key than confirm aborts a pending change
+        if let State::Confirming(name, value) = std::mem::replace(&mut self.state, State::Browsing)
+        {
+            if input == input::CONFIRM {
+                let result = value.store(&name);
+                self.status = Some(result.map(|_| format!("Set {} to {}", name, value)));
+                if let Some(Ok(_)) = self.status {
+                    self.params.insert(name, value);
+                }
+            }
+            return;
+        }
+        match input.as_str() {
+            input::UP => self.select(-1),
+            input::DOWN => self.select(1),
+            input::CONFIRM => self.activate_selected(),
+            input::CANCEL => {
+                self.expanded.clear();
+                self.selected = 0;
+            }
+            _ => (),
+        }
+    }
+
+    fn is_capturing_text(&self) -> bool {
+        matches!(self.state, State::Editing(_, _))
+    }
+
+    fn handle_text_input(&mut self, key: KeyCode) {
+        let (name, text) = match &mut self.state {
+            State::Editing(name, text) => (name, text),
+            _ => return,
+        };
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => {
+                let parsed = self
+                    .params
+                    .get(name.as_str())
+                    .and_then(|value| value.parse_same_type(text));
+                self.state = match parsed {
+                    Some(value) => State::Confirming(name.clone(), value),
+                    None => {
+                        self.status = Some(Err(format!("Invalid value {}", text)));
+                        State::Browsing
+                    }
+                };
+            }
+            KeyCode::Esc => self.state = State::Browsing,
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the parameters of the parameter server as a tree.".to_string(),
+            "Booleans, numbers and strings can be edited, after a confirmation.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous entry.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next entry.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Expands or collapses the selected namespace, or edits the selected parameter. Enter to stop editing, then confirm to set the value.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Collapses all namespaces.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Parameters".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for ParamBrowser {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let mut header_spans = vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" - {} parameters", self.params.len())),
+        ];
+        match &self.state {
+            State::Editing(name, text) => {
+                header_spans.push(Span::raw(format!(" - {}: {}", name, text)));
+                header_spans.push(Span::styled(
+                    "_",
+                    Style::default().add_modifier(Modifier::SLOW_BLINK),
+                ));
+            }
+            State::Confirming(name, value) => header_spans.push(Span::styled(
+                format!(" - Set {} to {}? Confirm to proceed", name, value),
+                Style::default().fg(self.theme.warning),
+            )),
+            State::Browsing => match &self.status {
+                Some(Ok(msg)) => header_spans.push(Span::styled(
+                    format!(" - {}", msg),
+                    Style::default().fg(self.theme.ok),
+                )),
+                Some(Err(msg)) => header_spans.push(Span::styled(
+                    format!(" - {}", msg),
+                    Style::default().fg(self.theme.error),
+                )),
+                None => (),
+            },
+        }
+        let header = Paragraph::new(Spans::from(header_spans))
+            .block(Block::default().borders(Borders::NONE))
+            .style(Style::default().fg(self.theme.text))
+            .alignment(Alignment::Left)
+            .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let items: Vec<ListItem> = self
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let indent = "  ".repeat(row.depth);
+                let basename = row.name.rsplit('/').next().unwrap_or("").to_string();
+                match row.value {
+                    None => ListItem::new(format!(
+                        "{}{} {}/",
+                        indent,
+                        if self.expanded.contains(&row.name) {
+                            "v"
+                        } else {
+                            ">"
+                        },
+                        basename
+                    )),
+                    Some(value) => ListItem::new(Spans::from(vec![
+                        Span::raw(format!("{}  {}: ", indent, basename)),
+                        Span::styled(value.to_string(), Style::default().fg(self.theme.accent)),
+                    ])),
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .highlight_style(Style::default().fg(self.theme.highlight))
+            .highlight_symbol(">> ");
+        let mut state = ListState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(list, chunks[1], &mut state);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for ParamBrowser {}
@@ -0,0 +1,226 @@
+//! Polygon editor mode allows to draw a polygon vertex by vertex in the viewport
+//! and to publish it as a geometry_msgs::PolygonStamped, e.g. for keep-out zones.
+
+use crate::app_modes::cursor::Cursor;
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use crate::config::PolygonEditorConfig;
+use rosrust_msg::geometry_msgs::{Point32, PolygonStamped};
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::style::Color;
+use tui::widgets::canvas::Line;
+
+/// Length of the dashes of the polygon being drawn, in meters at zoom 1.
+const DASH_LENGTH: f64 = 0.1;
+
+/// Returns the dashes of the line between the two points.
+fn dashed_line(from: (f64, f64), to: (f64, f64), dash_length: f64, color: Color) -> Vec<Line> {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = dx.hypot(dy);
+    let dashes = (length / dash_length).ceil() as usize;
+    (0..dashes)
+        .step_by(2)
+        .map(|i| {
+            let start = i as f64 * dash_length / length;
+            let end = ((i + 1) as f64 * dash_length / length).min(1.0);
+            Line {
+                x1: from.0 + dx * start,
+                y1: from.1 + dy * start,
+                x2: from.0 + dx * end,
+                y2: from.1 + dy * end,
+                color,
+            }
+        })
+        .collect()
+}
+
+pub struct PolygonEditor {
+    viewport: Rc<RefCell<Viewport>>,
+    config: PolygonEditorConfig,
+    cursor: Cursor,
+    vertices: Vec<(f64, f64)>,
+    published: Vec<(f64, f64)>,
+    publisher: rosrust::Publisher<PolygonStamped>,
+}
+
+impl PolygonEditor {
+    pub fn new(viewport: Rc<RefCell<Viewport>>, config: PolygonEditorConfig) -> PolygonEditor {
+        let robot_pose = viewport.borrow().robot_pose();
+        PolygonEditor {
+            viewport,
+            publisher: rosrust::publish(&config.topic, 1).unwrap(),
+            config,
+            cursor: Cursor::new(robot_pose, false),
+            vertices: Vec::new(),
+            published: Vec::new(),
+        }
+    }
+
+    /// Publishes the polygon being drawn, which becomes the published one.
+    fn publish(&mut self) {
+        if self.vertices.len() < 3 {
+            return;
+        }
+        let mut msg = PolygonStamped::default();
+        msg.header.frame_id = self.viewport.borrow().static_frame.clone();
+        msg.header.stamp = rosrust::now();
+        msg.polygon.points = self
+            .vertices
+            .iter()
+            .map(|&(x, y)| Point32 {
+                x: x as f32,
+                y: y as f32,
+                z: 0.0,
+            })
+            .collect();
+        self.publisher.send(msg).unwrap();
+        self.published = std::mem::take(&mut self.vertices);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for PolygonEditor {}
+
+impl AppMode for PolygonEditor {
+    fn run(&mut self) {
+        export::record(self, &self.viewport);
+    }
+
+    fn reset(&mut self) {
+        if self.vertices.is_empty() {
+            self.cursor.pose = self.viewport.borrow().robot_pose();
+        }
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
+        if self.cursor.handle_input(input) {
+            return;
+        }
+        match input.as_str() {
+            input::CONFIRM => self.vertices.push(self.cursor.position()),
+            input::DELETE => {
+                self.vertices.pop();
+            }
+            input::CANCEL => self.vertices.clear(),
+            input::PUBLISH => self.publish(),
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Polygon Editor".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to draw a polygon and to publish it, e.g. as a keep-out zone."
+                .to_string(),
+            "The polygon being drawn is dashed, the last published one is solid.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = self.cursor.get_keymap();
+        keymap.extend([
+            [
+                input::CONFIRM.to_string(),
+                "Adds a vertex at the cursor.".to_string(),
+            ],
+            [
+                input::DELETE.to_string(),
+                "Removes the last vertex.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Removes all the vertices.".to_string(),
+            ],
+            [
+                input::PUBLISH.to_string(),
+                "Publishes the polygon.".to_string(),
+            ],
+        ]);
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for PolygonEditor {
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        let rotation = viewport.rotation();
+        for (i, from) in self.published.iter().enumerate() {
+            let to = self.published[(i + 1) % self.published.len()];
+            ctx.draw_line(&rotation.line(&Line {
+                x1: from.0,
+                y1: from.1,
+                x2: to.0,
+                y2: to.1,
+                color: viewport.theme.ok,
+            }));
+        }
+        if let (Some(&first), Some(&last)) = (self.vertices.first(), self.vertices.last()) {
+            let dash_length = DASH_LENGTH / viewport.zoom;
+            let mut lines = Vec::new();
+            for pair in self.vertices.windows(2) {
+                lines.extend(dashed_line(
+                    pair[0],
+                    pair[1],
+                    dash_length,
+                    viewport.theme.highlight,
+                ));
+            }
+            // Preview of the edges closing the polygon through the cursor
+            let cursor = self.cursor.position();
+            lines.extend(dashed_line(
+                last,
+                cursor,
+                dash_length,
+                viewport.theme.inactive,
+            ));
+            lines.extend(dashed_line(
+                cursor,
+                first,
+                dash_length,
+                viewport.theme.inactive,
+            ));
+            for line in lines {
+                ctx.draw_line(&rotation.line(&line));
+            }
+        }
+        self.cursor.draw(ctx, &viewport, viewport.theme.accent);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .x_bounds_around(self.cursor.position())
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .y_bounds_around(self.cursor.position())
+    }
+
+    fn info(&self) -> String {
+        let (x, y) = self.cursor.position();
+        format!(
+            "Topic: /{}, Vertices: {}, Cursor: ({:.2}, {:.2}), Cursor step: {:.2}",
+            self.config.topic,
+            self.vertices.len(),
+            x,
+            y,
+            self.cursor.step
+        )
+    }
+}
@@ -1,21 +1,34 @@
 //! Send pose mode allows to send a pose on the given topic.
 
-use crate::app_modes::viewport::{UseViewport, Viewport};
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
 use crate::app_modes::{input, AppMode, BaseMode};
-use crate::config::SendPoseConfig;
+use crate::config::{GoalQueueConfig, PosePresetConfig, SendPoseConfig};
 use crate::footprint::get_current_footprint;
+use crate::move_base::MoveBaseClient;
 use crate::transformation;
 use approx::AbsDiffEq;
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
 use nalgebra::{Isometry2, Vector2};
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
+use std::collections::VecDeque;
 use std::rc::Rc;
 use tui::backend::Backend;
-use tui::style::Color;
-use tui::widgets::canvas::{Context, Line};
+use tui::style::Style;
+use tui::text::Span;
+use tui::widgets::canvas::Line;
 
 trait BasePosePubWrapper {
     fn get_topic(&self) -> &String;
     fn send(&self, msg: rosrust_msg::geometry_msgs::Pose, frame_id: String);
+
+    /// Cancels the last sent pose, for publishers that support it.
+    fn cancel(&self) {}
+
+    /// Returns a description of the state of the last sent pose, for publishers that track it.
+    fn describe(&self) -> Option<String> {
+        None
+    }
 }
 
 struct PosePubWrapper {
@@ -108,6 +121,41 @@ impl BasePosePubWrapper for PoseCovPubWrapper {
     }
 }
 
+struct MoveBaseActionWrapper {
+    topic: String,
+    client: MoveBaseClient,
+}
+
+impl MoveBaseActionWrapper {
+    pub fn new(topic: &String) -> MoveBaseActionWrapper {
+        MoveBaseActionWrapper {
+            topic: topic.clone(),
+            client: MoveBaseClient::new(topic),
+        }
+    }
+}
+
+impl BasePosePubWrapper for MoveBaseActionWrapper {
+    fn get_topic(&self) -> &String {
+        &self.topic
+    }
+
+    fn send(&self, msg: rosrust_msg::geometry_msgs::Pose, frame_id: String) {
+        let mut msg_stamped = rosrust_msg::geometry_msgs::PoseStamped::default();
+        msg_stamped.header.frame_id = frame_id;
+        msg_stamped.pose = msg;
+        self.client.send_goal(msg_stamped);
+    }
+
+    fn cancel(&self) {
+        self.client.cancel_goal();
+    }
+
+    fn describe(&self) -> Option<String> {
+        self.client.describe()
+    }
+}
+
 /// Represents the send pose mode.
 pub struct SendPose {
     viewport: Rc<RefCell<Viewport>>,
@@ -117,10 +165,60 @@ pub struct SendPose {
     current_topic: usize,
     publishers: Vec<Box<dyn BasePosePubWrapper>>,
     ghost_active: bool,
+    queue_config: GoalQueueConfig,
+    queue: VecDeque<Isometry2<f64>>,
+    /// Last pose dispatched from the queue, until the robot reaches it.
+    queued_goal: Option<Isometry2<f64>>,
+    /// Terminal cell where the left button was pressed, while dragging.
+    drag_start: Option<(u16, u16)>,
+    /// Coordinates of the desired pose being typed, if any.
+    coordinates_input: Option<String>,
+    /// True if the typed coordinates could not be read.
+    invalid_coordinates: bool,
+    /// Frames in which the poses can be sent, starting with the fixed frame.
+    target_frames: Vec<String>,
+    current_frame: usize,
+    /// Error of the last pose which could not be sent.
+    send_error: Option<String>,
+    /// Named poses of the fixed frame where the desired pose can be placed.
+    presets: Vec<PosePresetConfig>,
+    /// Index of the selected preset while selecting one, with the desired pose and whether it
+    /// was moved before, to restore them if the selection is canceled.
+    preset_selection: Option<(usize, Isometry2<f64>, bool)>,
+    /// Name of the preset being typed, to save the desired pose as a new preset.
+    preset_name_input: Option<String>,
+}
+
+/// Reads the x, y and optional yaw in degrees of a pose, separated by spaces or commas.
+fn parse_coordinates(text: &str) -> Option<(f64, f64, Option<f64>)> {
+    let values: Vec<f64> = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f64>().ok())
+        .collect::<Option<Vec<f64>>>()?;
+    match values[..] {
+        [x, y] => Some((x, y, None)),
+        [x, y, yaw] => Some((x, y, Some(yaw.to_radians()))),
+        _ => None,
+    }
 }
 
 impl SendPose {
-    pub fn new(topics: &Vec<SendPoseConfig>, viewport: Rc<RefCell<Viewport>>) -> SendPose {
+    pub fn new(
+        topics: &Vec<SendPoseConfig>,
+        frames: &Vec<String>,
+        presets: &Vec<PosePresetConfig>,
+        queue_config: GoalQueueConfig,
+        viewport: Rc<RefCell<Viewport>>,
+    ) -> SendPose {
+        let static_frame = viewport.borrow().static_frame.clone();
+        let mut target_frames = vec![static_frame.clone()];
+        target_frames.extend(
+            frames
+                .iter()
+                .filter(|frame| **frame != static_frame)
+                .cloned(),
+        );
         let base_link_pose = viewport.borrow().tf_listener.lookup_transform(
             &viewport.borrow().static_frame,
             &viewport.borrow().robot_frame,
@@ -144,6 +242,9 @@ impl SendPose {
                 "PoseWithCovarianceStamped" => {
                     publishers.push(Box::new(PoseCovPubWrapper::new(&topic.topic)))
                 }
+                "MoveBaseAction" => {
+                    publishers.push(Box::new(MoveBaseActionWrapper::new(&topic.topic)))
+                }
                 _ => {}
             }
         }
@@ -156,9 +257,57 @@ impl SendPose {
             robot_pose: robot_pose.clone(),
             new_pose: robot_pose,
             ghost_active: false,
+            queue_config,
+            queue: VecDeque::new(),
+            queued_goal: None,
+            drag_start: None,
+            coordinates_input: None,
+            invalid_coordinates: false,
+            target_frames,
+            current_frame: 0,
+            send_error: None,
+            presets: presets.clone(),
+            preset_selection: None,
+            preset_name_input: None,
         }
     }
 
+    /// Places the desired pose on the preset of the given index.
+    fn place_on_preset(&mut self, index: usize) {
+        let preset = &self.presets[index];
+        self.new_pose = Isometry2::new(Vector2::new(preset.x, preset.y), preset.yaw.to_radians());
+        self.ghost_active = true;
+    }
+
+    /// Handles the input while selecting a preset. Returns false if no preset is selected.
+    fn handle_preset_selection(&mut self, input: &str) -> bool {
+        let (index, pose, ghost_active) = match self.preset_selection {
+            Some(selection) => selection,
+            None => return false,
+        };
+        let count = self.presets.len();
+        match input {
+            input::UP | input::PREVIOUS => {
+                let index = (index + count - 1) % count;
+                self.place_on_preset(index);
+                self.preset_selection = Some((index, pose, ghost_active));
+            }
+            input::DOWN | input::NEXT | input::SELECT_PRESET => {
+                let index = (index + 1) % count;
+                self.place_on_preset(index);
+                self.preset_selection = Some((index, pose, ghost_active));
+            }
+            input::CONFIRM => self.preset_selection = None,
+            input::CANCEL => {
+                self.new_pose = pose;
+                self.ghost_active = ghost_active;
+                self.preset_selection = None;
+            }
+            _ => (),
+        }
+        true
+    }
+
     fn move_new_pose(&mut self, x: f64, y: f64, yaw: f64) {
         let new_yaw = self.new_pose.rotation.angle() + yaw;
         let new_x = x * new_yaw.cos() - y * new_yaw.sin() + self.new_pose.translation.x;
@@ -169,26 +318,79 @@ impl SendPose {
 
     fn send_new_pose(&mut self) {
         if self.new_pose.abs_diff_ne(&self.robot_pose, 0.01) {
-            let pose = transformation::iso2d_to_ros(&self.new_pose);
-            let frame_id = self.viewport.borrow().static_frame.to_string();
-            let mut msg = rosrust_msg::geometry_msgs::Pose::default();
-            msg.orientation.x = pose.rotation.x;
-            msg.orientation.y = pose.rotation.y;
-            msg.orientation.z = pose.rotation.z;
-            msg.orientation.w = pose.rotation.w;
-            msg.position.x = pose.translation.x;
-            msg.position.y = pose.translation.y;
-            msg.position.z = 0.0;
-            self.publishers[self.current_topic].send(msg, frame_id);
+            let new_pose = self.new_pose;
+            if self.send_pose(&new_pose) {
+                self.ghost_active = false;
+            }
+        }
+    }
+
+    /// Sends the pose of the fixed frame in the target frame. Returns false if it could not be
+    /// transformed into it.
+    fn send_pose(&mut self, new_pose: &Isometry2<f64>) -> bool {
+        let frame_id = self.target_frames[self.current_frame].clone();
+        let new_pose = {
+            let viewport = self.viewport.borrow();
+            if frame_id == viewport.static_frame {
+                Ok(*new_pose)
+            } else {
+                viewport
+                    .tf_listener
+                    .lookup_transform(&frame_id, &viewport.static_frame, rosrust::Time::new())
+                    .map(|res| transformation::ros_to_iso2d(&res.transform) * new_pose)
+            }
+        };
+        let new_pose = match new_pose {
+            Ok(new_pose) => new_pose,
+            Err(_e) => {
+                self.send_error = Some(format!(
+                    "No transform from {} to {}",
+                    self.viewport.borrow().static_frame,
+                    frame_id
+                ));
+                return false;
+            }
+        };
+        self.send_error = None;
+        let pose = transformation::iso2d_to_ros(&new_pose);
+        let mut msg = rosrust_msg::geometry_msgs::Pose::default();
+        msg.orientation.x = pose.rotation.x;
+        msg.orientation.y = pose.rotation.y;
+        msg.orientation.z = pose.rotation.z;
+        msg.orientation.w = pose.rotation.w;
+        msg.position.x = pose.translation.x;
+        msg.position.y = pose.translation.y;
+        msg.position.z = 0.0;
+        self.publishers[self.current_topic].send(msg, frame_id);
+        true
+    }
+
+    fn enqueue_new_pose(&mut self) {
+        if self.new_pose.abs_diff_ne(&self.robot_pose, 0.01) {
+            self.queue.push_back(self.new_pose);
             self.ghost_active = false;
         }
     }
+
+    /// Sends the first pose of the queue, if any.
+    fn dispatch_next(&mut self) {
+        self.queued_goal = self.queue.pop_front();
+        if let Some(goal) = self.queued_goal {
+            self.send_pose(&goal);
+        }
+    }
+
+    fn goal_reached(&self, goal: &Isometry2<f64>) -> bool {
+        (goal.translation.vector - self.robot_pose.translation.vector).norm()
+            <= self.queue_config.tolerance
+    }
 }
 
 impl<B: Backend> BaseMode<B> for SendPose {}
 
 impl AppMode for SendPose {
     fn run(&mut self) {
+        export::record(self, &self.viewport);
         let base_link_pose = self.viewport.borrow().tf_listener.lookup_transform(
             &self.viewport.borrow().static_frame,
             &self.viewport.borrow().robot_frame,
@@ -204,13 +406,66 @@ impl AppMode for SendPose {
         if !self.ghost_active {
             self.new_pose = self.robot_pose.clone();
         }
+
+        if let Some(goal) = self.queued_goal {
+            if self.queue_config.auto_dispatch && self.goal_reached(&goal) {
+                self.dispatch_next();
+            }
+        }
     }
     fn reset(&mut self) {
         self.ghost_active = false;
+        self.preset_selection = None;
         self.run(); // Update the robot pose
     }
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                let point = self.viewport.borrow().terminal_to_world(
+                    self.x_bounds(),
+                    self.y_bounds(),
+                    event.column,
+                    event.row,
+                );
+                if let Some((x, y)) = point {
+                    let yaw = self.new_pose.rotation.angle();
+                    self.new_pose = Isometry2::new(Vector2::new(x, y), yaw);
+                    self.ghost_active = true;
+                    self.drag_start = Some((event.column, event.row));
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                // Both cells are converted with the same bounds, so the direction between them
+                // holds even though the view is now centered on the ghost
+                if let Some((column, row)) = self
+                    .drag_start
+                    .filter(|&start| start != (event.column, event.row))
+                {
+                    let (start, end) = {
+                        let viewport = self.viewport.borrow();
+                        let (x_bounds, y_bounds) = (self.x_bounds(), self.y_bounds());
+                        (
+                            viewport.terminal_to_world(x_bounds, y_bounds, column, row),
+                            viewport.terminal_to_world(x_bounds, y_bounds, event.column, event.row),
+                        )
+                    };
+                    if let (Some(start), Some(end)) = (start, end) {
+                        let yaw = (end.1 - start.1).atan2(end.0 - start.0);
+                        self.new_pose = Isometry2::new(self.new_pose.translation.vector, yaw);
+                    }
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.drag_start = None,
+            _ => (),
+        }
+    }
+
     fn handle_input(&mut self, input: &String) {
+        if self.handle_preset_selection(input) {
+            return;
+        }
         self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
         match input.as_str() {
             input::UP => self.move_new_pose(self.increment, 0.0, 0.0),
             input::DOWN => self.move_new_pose(-self.increment, 0.0, 0.0),
@@ -228,8 +483,94 @@ impl AppMode for SendPose {
                     self.publishers.len() - 1
                 };
             }
-            input::CANCEL => self.reset(),
-            input::CONFIRM => self.send_new_pose(),
+            input::CANCEL => {
+                // Without a desired pose to reset, the last goal is canceled
+                if self.ghost_active {
+                    self.reset();
+                } else {
+                    self.publishers[self.current_topic].cancel();
+                    self.queue.clear();
+                    self.queued_goal = None;
+                }
+            }
+            input::CONFIRM => {
+                // Without a desired pose to send, the next queued pose is sent
+                if self.ghost_active {
+                    self.send_new_pose();
+                } else {
+                    self.dispatch_next();
+                }
+            }
+            input::ENQUEUE => self.enqueue_new_pose(),
+            input::NEXT_TARGET_FRAME => {
+                self.current_frame = (self.current_frame + 1) % self.target_frames.len();
+                self.send_error = None;
+            }
+            input::ENTER_COORDINATES => {
+                self.coordinates_input = Some(String::new());
+                self.invalid_coordinates = false;
+            }
+            input::SELECT_PRESET => {
+                if !self.presets.is_empty() {
+                    self.preset_selection = Some((0, self.new_pose, self.ghost_active));
+                    self.place_on_preset(0);
+                }
+            }
+            input::SAVE_PRESET => self.preset_name_input = Some(String::new()),
+            _ => (),
+        }
+    }
+
+    fn is_capturing_text(&self) -> bool {
+        self.coordinates_input.is_some() || self.preset_name_input.is_some()
+    }
+
+    fn handle_text_input(&mut self, key: KeyCode) {
+        if let Some(name) = self.preset_name_input.as_mut() {
+            match key {
+                KeyCode::Char(c) => name.push(c),
+                KeyCode::Backspace => {
+                    name.pop();
+                }
+                KeyCode::Enter => {
+                    let name = self.preset_name_input.take().unwrap();
+                    let name = if name.is_empty() {
+                        format!("Preset {}", self.presets.len() + 1)
+                    } else {
+                        name
+                    };
+                    self.presets.push(PosePresetConfig {
+                        name,
+                        x: self.new_pose.translation.x,
+                        y: self.new_pose.translation.y,
+                        yaw: self.new_pose.rotation.angle().to_degrees(),
+                    });
+                }
+                KeyCode::Esc => self.preset_name_input = None,
+                _ => (),
+            }
+            return;
+        }
+        let text = match self.coordinates_input.as_mut() {
+            Some(text) => text,
+            None => return,
+        };
+        match key {
+            KeyCode::Char(c) => text.push(c),
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Enter => match parse_coordinates(text) {
+                Some((x, y, yaw)) => {
+                    let yaw = yaw.unwrap_or_else(|| self.new_pose.rotation.angle());
+                    self.new_pose = Isometry2::new(Vector2::new(x, y), yaw);
+                    self.ghost_active = true;
+                    self.coordinates_input = None;
+                }
+                // The text stays to be corrected
+                None => self.invalid_coordinates = true,
+            },
+            KeyCode::Esc => self.coordinates_input = None,
             _ => (),
         }
     }
@@ -275,11 +616,35 @@ impl AppMode for SendPose {
             ],
             [
                 input::CANCEL.to_string(),
-                "Resets the desired pose.".to_string(),
+                "Resets the desired pose, or cancels the last goal and clears the queue if the desired pose is not moved."
+                    .to_string(),
             ],
             [
                 input::CONFIRM.to_string(),
-                "Sends the desired pose.".to_string(),
+                "Sends the desired pose, or the next queued pose if the desired pose is not moved."
+                    .to_string(),
+            ],
+            [
+                input::ENQUEUE.to_string(),
+                "Appends the desired pose to the queue.".to_string(),
+            ],
+            [
+                input::NEXT_TARGET_FRAME.to_string(),
+                "Switches to the next frame in which the poses are sent.".to_string(),
+            ],
+            [
+                input::SELECT_PRESET.to_string(),
+                "Places the desired pose on the presets in turn, also with up and down. Confirm to keep it, Cancel to go back."
+                    .to_string(),
+            ],
+            [
+                input::SAVE_PRESET.to_string(),
+                "Saves the desired pose as a preset, under the typed name.".to_string(),
+            ],
+            [
+                input::ENTER_COORDINATES.to_string(),
+                "Types the x, y and optional yaw in degrees of the desired pose in the fixed frame. Enter to place it, Esc to abort."
+                    .to_string(),
             ],
             [
                 input::INCREMENT_STEP.to_string(),
@@ -304,56 +669,108 @@ impl AppMode for SendPose {
 }
 
 impl UseViewport for SendPose {
-    fn draw_in_viewport(&self, ctx: &mut Context) {
-        self.viewport.borrow().draw_in_viewport(ctx);
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        let rotation = viewport.rotation();
+        for (i, pose) in self.queue.iter().enumerate() {
+            let pose_ros = transformation::iso2d_to_ros(pose);
+            for elem in &get_current_footprint(&pose_ros, &self.viewport.borrow().footprint) {
+                ctx.draw_line(&rotation.line(&Line {
+                    x1: elem.0,
+                    y1: elem.1,
+                    x2: elem.2,
+                    y2: elem.3,
+                    color: viewport.theme.inactive,
+                }));
+            }
+            let (x, y) = rotation.apply((pose.translation.x, pose.translation.y));
+            ctx.print(
+                x,
+                y,
+                Span::styled(
+                    (i + 1).to_string(),
+                    Style::default().fg(viewport.theme.inactive),
+                ),
+            );
+        }
         if self.new_pose.abs_diff_ne(&self.robot_pose, 0.01) {
             let pose_estimate_ros = transformation::iso2d_to_ros(&self.new_pose);
             for elem in
                 &get_current_footprint(&pose_estimate_ros, &self.viewport.borrow().footprint)
             {
-                ctx.draw(&Line {
+                ctx.draw_line(&rotation.line(&Line {
                     x1: elem.0,
                     y1: elem.1,
                     x2: elem.2,
                     y2: elem.3,
-                    color: Color::Gray,
-                });
+                    color: viewport.theme.text,
+                }));
             }
-            for mut line in
-                Viewport::get_frame_lines(&pose_estimate_ros, self.viewport.borrow().axis_length)
+            let color = viewport.theme.text;
+            for line in
+                Viewport::get_frame_lines(&pose_estimate_ros, viewport.axis_length, (color, color))
             {
-                line.color = Color::Gray;
-                ctx.draw(&line);
+                ctx.draw_line(&rotation.line(&line));
             }
         }
     }
     fn x_bounds(&self) -> [f64; 2] {
-        let scale_factor = self.viewport.borrow().terminal_size.0 as f64
-            / self.viewport.borrow().terminal_size.1 as f64
-            * 0.5;
-        [
-            self.new_pose.translation.x
-                + self.viewport.borrow().initial_bounds[0] / self.viewport.borrow().zoom
-                    * scale_factor,
-            self.new_pose.translation.x
-                + self.viewport.borrow().initial_bounds[1] / self.viewport.borrow().zoom
-                    * scale_factor,
-        ]
+        self.viewport
+            .borrow()
+            .x_bounds_around((self.new_pose.translation.x, self.new_pose.translation.y))
     }
     fn y_bounds(&self) -> [f64; 2] {
-        [
-            self.new_pose.translation.y
-                + self.viewport.borrow().initial_bounds[2] / self.viewport.borrow().zoom,
-            self.new_pose.translation.y
-                + self.viewport.borrow().initial_bounds[3] / self.viewport.borrow().zoom,
-        ]
+        self.viewport
+            .borrow()
+            .y_bounds_around((self.new_pose.translation.x, self.new_pose.translation.y))
     }
 
     fn info(&self) -> String {
-        format!(
+        if let Some(name) = &self.preset_name_input {
+            return format!("Name of the preset: {}_", name);
+        }
+        if let Some((index, _, _)) = self.preset_selection {
+            return format!(
+                "Preset {}/{}: {}, Confirm to keep it, Cancel to go back",
+                index + 1,
+                self.presets.len(),
+                self.presets[index].name
+            );
+        }
+        if let Some(text) = &self.coordinates_input {
+            return format!(
+                "{}x y [yaw°]: {}_",
+                if self.invalid_coordinates {
+                    "Invalid coordinates, "
+                } else {
+                    ""
+                },
+                text
+            );
+        }
+        let mut info = format!(
             "Topic: /{}, Cursor step: {:.2}",
             &self.publishers[self.current_topic].get_topic(),
             &self.increment
-        )
+        );
+        if self.target_frames.len() > 1 {
+            info += &format!(", Frame: {}", self.target_frames[self.current_frame]);
+        }
+        if let Some(error) = &self.send_error {
+            info += &format!(", {}", error);
+        }
+        if !self.queue.is_empty() {
+            info += &format!(", Queue: {}", self.queue.len());
+        }
+        if let Some(description) = self.publishers[self.current_topic].describe() {
+            info += &format!(", {}", description);
+        }
+        info
     }
 }
@@ -0,0 +1,146 @@
+//! Split view mode shows the viewport of the teleoperation next to the camera images, so that
+//! the robot can be driven with both in sight.
+
+use crate::app_modes::image_view::ImageView;
+use crate::app_modes::teleoperate::Teleoperate;
+use crate::app_modes::viewport::{draw_viewport, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::SplitViewConfig;
+use crossterm::event::MouseEvent;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::Style;
+use tui::widgets::{Block, Borders};
+use tui::Frame;
+
+#[derive(PartialEq)]
+enum Focus {
+    Viewport,
+    Image,
+}
+
+pub struct SplitView {
+    viewport: Rc<RefCell<Viewport>>,
+    teleop: Teleoperate,
+    image_view: ImageView,
+    config: SplitViewConfig,
+    focus: Focus,
+}
+
+impl SplitView {
+    pub fn new(
+        viewport: Rc<RefCell<Viewport>>,
+        teleop: Teleoperate,
+        image_view: ImageView,
+        config: SplitViewConfig,
+    ) -> SplitView {
+        SplitView {
+            viewport,
+            teleop,
+            image_view,
+            config,
+            focus: Focus::Viewport,
+        }
+    }
+
+    /// Splits the given area into the viewport pane and the image pane.
+    fn panes(&self, area: Rect) -> (Rect, Rect) {
+        let percentage = self.config.viewport_percentage.min(100);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(
+                [
+                    Constraint::Percentage(percentage),
+                    Constraint::Percentage(100 - percentage),
+                ]
+                .as_ref(),
+            )
+            .split(area);
+        (chunks[0], chunks[1])
+    }
+}
+
+impl<B: Backend> BaseMode<B> for SplitView {}
+
+impl AppMode for SplitView {
+    fn run(&mut self) {
+        // The viewport keeps its proportions in the narrower pane
+        if let Ok((width, height)) = crossterm::terminal::size() {
+            let (pane, _) = self.panes(Rect::new(0, 0, width, height));
+            self.viewport.borrow_mut().terminal_size = (pane.width.max(1), pane.height);
+        }
+        self.teleop.run();
+        self.image_view.run();
+    }
+
+    fn reset(&mut self) {
+        self.teleop.reset();
+        self.image_view.reset();
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        if input == input::SWITCH_FOCUS {
+            self.focus = match self.focus {
+                Focus::Viewport => Focus::Image,
+                Focus::Image => Focus::Viewport,
+            };
+            // The robot is not left driving without the keys to stop it
+            self.teleop.reset();
+            return;
+        }
+        match self.focus {
+            Focus::Viewport => self.teleop.handle_input(input),
+            Focus::Image => self.image_view.handle_input(input),
+        }
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        // The width of the viewport is the one of its pane
+        if self.focus == Focus::Viewport && event.column < self.viewport.borrow().terminal_size.0 {
+            self.teleop.handle_mouse(event);
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the viewport of the teleoperation next to the camera images.".to_string(),
+            "The keys act on the focused pane. The separator is highlighted while the image has the focus."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = vec![[
+            input::SWITCH_FOCUS.to_string(),
+            "Switches the focus between the viewport and the image.".to_string(),
+        ]];
+        keymap.extend(match self.focus {
+            Focus::Viewport => self.teleop.get_keymap(),
+            Focus::Image => self.image_view.get_keymap(),
+        });
+        keymap
+    }
+
+    fn get_name(&self) -> String {
+        "Split view".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for SplitView {
+    fn draw(&self, f: &mut Frame<B>) {
+        let (viewport_pane, image_pane) = self.panes(f.size());
+        let theme = self.viewport.borrow().theme.clone();
+        draw_viewport(&self.teleop, f, viewport_pane);
+        let block = Block::default()
+            .borders(Borders::LEFT)
+            .border_style(Style::default().fg(match self.focus {
+                Focus::Image => theme.highlight,
+                Focus::Viewport => theme.inactive,
+            }));
+        let image_area = block.inner(image_pane);
+        f.render_widget(block, image_pane);
+        self.image_view.draw_in(f, image_area);
+    }
+}
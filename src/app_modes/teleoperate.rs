@@ -1,46 +1,574 @@
-use crate::app_modes::viewport::{UseViewport, Viewport};
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
 use crate::app_modes::{input, AppMode, BaseMode};
 use crate::config::TeleopConfig;
+use crate::footprint::get_current_footprint;
+use crate::source;
+use crate::transformation;
+use crossterm::event::MouseEvent;
+use nalgebra::{Isometry2, Point2, Rotation2, Vector2};
 use rosrust;
 use rosrust_msg;
-use std::cell::RefCell;
+use std::cell::{Ref, RefCell};
 use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tui::backend::Backend;
-use tui::widgets::canvas::Context;
+use tui::widgets::canvas::Line;
+
+/// Time after which the measured velocities are considered outdated.
+const MEASURED_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Number of steps of the predicted trajectory, at each of which the footprint is drawn.
+const PREVIEW_STEPS: usize = 10;
+
+/// Returns the poses of the robot driven from the given pose at the constant velocities, in the
+/// fixed frame of the start pose, over the given duration.
+fn predict_poses(
+    start: &Isometry2<f64>,
+    (x, y, theta): (f64, f64, f64),
+    duration: f64,
+) -> Vec<Isometry2<f64>> {
+    let dt = duration / PREVIEW_STEPS as f64;
+    // The translation of each step is taken along the mean heading of the step
+    let step = Isometry2::new(
+        Rotation2::new(theta * dt / 2.0) * Vector2::new(x * dt, y * dt),
+        theta * dt,
+    );
+    let mut poses = vec![*start];
+    for _ in 0..PREVIEW_STEPS {
+        poses.push(poses[poses.len() - 1] * step);
+    }
+    poses
+}
+
+/// Publisher of the commands, depending on the configured teleoperation mode.
+trait BaseCmdPubWrapper {
+    fn send(&self, velocities: &Velocities);
+
+    /// Returns true if the commands are a speed and a steering angle rather than velocities.
+    fn is_ackermann(&self) -> bool {
+        false
+    }
+}
+
+struct TwistPubWrapper {
+    publisher: rosrust::Publisher<rosrust_msg::geometry_msgs::Twist>,
+}
+
+impl TwistPubWrapper {
+    pub fn new(topic: &String) -> TwistPubWrapper {
+        TwistPubWrapper {
+            publisher: rosrust::publish(topic, 1).unwrap(),
+        }
+    }
+}
+
+/// Returns the twist of the velocities.
+fn to_twist(velocities: &Velocities) -> rosrust_msg::geometry_msgs::Twist {
+    let mut twist = rosrust_msg::geometry_msgs::Twist::default();
+    twist.linear.x = velocities.x;
+    twist.linear.y = velocities.y;
+    twist.angular.z = velocities.theta;
+    twist
+}
+
+impl BaseCmdPubWrapper for TwistPubWrapper {
+    fn send(&self, velocities: &Velocities) {
+        self.publisher.send(to_twist(velocities)).unwrap();
+    }
+}
+
+struct TwistStampedPubWrapper {
+    frame_id: String,
+    publisher: rosrust::Publisher<rosrust_msg::geometry_msgs::TwistStamped>,
+}
+
+impl TwistStampedPubWrapper {
+    pub fn new(topic: &String, frame_id: String) -> TwistStampedPubWrapper {
+        TwistStampedPubWrapper {
+            frame_id,
+            publisher: rosrust::publish(topic, 1).unwrap(),
+        }
+    }
+}
+
+impl BaseCmdPubWrapper for TwistStampedPubWrapper {
+    fn send(&self, velocities: &Velocities) {
+        let mut msg = rosrust_msg::geometry_msgs::TwistStamped::default();
+        msg.header.stamp = rosrust::now();
+        msg.header.frame_id = self.frame_id.clone();
+        msg.twist = to_twist(velocities);
+        self.publisher.send(msg).unwrap();
+    }
+}
+
+struct AckermannPubWrapper {
+    publisher: rosrust::Publisher<rosrust_msg::ackermann_msgs::AckermannDriveStamped>,
+}
+
+impl AckermannPubWrapper {
+    pub fn new(topic: &String) -> AckermannPubWrapper {
+        AckermannPubWrapper {
+            publisher: rosrust::publish(topic, 1).unwrap(),
+        }
+    }
+}
+
+impl BaseCmdPubWrapper for AckermannPubWrapper {
+    fn send(&self, velocities: &Velocities) {
+        let mut drive_cmd = rosrust_msg::ackermann_msgs::AckermannDriveStamped::default();
+        drive_cmd.header.stamp = rosrust::now();
+        drive_cmd.drive.speed = velocities.x as f32;
+        drive_cmd.drive.steering_angle = velocities.steering_angle as f32;
+        self.publisher.send(drive_cmd).unwrap();
+    }
+
+    fn is_ackermann(&self) -> bool {
+        true
+    }
+}
 
 pub struct Teleoperate {
     viewport: Rc<RefCell<Viewport>>,
-    current_velocities: Velocities,
-    cmd_vel_pub: rosrust::Publisher<rosrust_msg::geometry_msgs::Twist>,
+    /// Velocities set with the keys.
+    target_velocities: Velocities,
+    /// Published velocities, which follow the target ones within the acceleration limits.
+    commanded_velocities: Velocities,
+    max_linear_acceleration: f64,
+    max_angular_acceleration: f64,
+    /// Highest absolute velocities along each axis, if limited.
+    max_vel_x: Option<f64>,
+    max_vel_y: Option<f64>,
+    max_vel_theta: Option<f64>,
+    /// Time of the latest run, from which the velocities are ramped.
+    last_run: Option<Instant>,
+    /// Publishers of the commands by topic, of which only the active one is used.
+    cmd_vel_pubs: Vec<(String, Box<dyn BaseCmdPubWrapper>)>,
+    active_pub: usize,
     increment: f64,
     increment_step: f64,
+    steering_increment: f64,
+    steering_increment_step: f64,
     publish_cmd_vel_when_idle: bool,
     has_published_zero_once: bool,
+    /// Time without a movement key after which the robot is stopped, if it is only driven
+    /// while the keys are held.
+    deadman_timeout: Option<Duration>,
+    /// Time of the latest movement key.
+    last_movement: Option<Instant>,
+    /// Velocities of the latest odometry, with the time they were received.
+    measured_velocities: Arc<RwLock<Option<(Velocities, Instant)>>>,
+    odom_subscriber: Option<source::Subscriber>,
+    /// Time over which the trajectory of the robot is predicted, 0 to not draw it.
+    preview_time: f64,
+    /// Distance between the axles, with which the rotation of the Ackermann mode is predicted.
+    wheelbase: f64,
+    collision_guard: bool,
+    stop_distance: f64,
+    slowdown_distance: f64,
+    /// Distance to the closest obstacle in the way of the robot and the factor by which the
+    /// linear velocities were scaled down for it, if any.
+    obstacle: Option<(f64, f64)>,
+    /// Target velocities set during the recording, by time since its start in seconds. The
+    /// last ones mark its end.
+    sequence: Vec<(f64, Velocities)>,
+    /// Start of the ongoing recording.
+    recording: Option<Instant>,
+    /// True while waiting for the confirmation to replay the sequence.
+    replay_request: bool,
+    /// Start of the ongoing replay.
+    replay: Option<Instant>,
 }
 
+#[derive(Clone, Default, PartialEq)]
 pub struct Velocities {
     x: f64,
     y: f64,
     theta: f64,
+    steering_angle: f64,
 }
 
 impl Teleoperate {
     pub fn new(viewport: Rc<RefCell<Viewport>>, config: TeleopConfig) -> Teleoperate {
-        let cmd_vel_publisher = rosrust::publish(&config.cmd_vel_topic, 1).unwrap();
-        let initial_velocities = Velocities {
-            x: 0.,
-            y: 0.,
-            theta: 0.,
-        };
+        let frame_id = config
+            .frame_id
+            .clone()
+            .unwrap_or_else(|| viewport.borrow().robot_frame.clone());
+        let cmd_vel_pubs = std::iter::once(&config.cmd_vel_topic)
+            .chain(config.cmd_vel_topics.iter())
+            .map(|topic| {
+                let publisher: Box<dyn BaseCmdPubWrapper> = match config.mode.as_str() {
+                    "Ackermann" => Box::new(AckermannPubWrapper::new(topic)),
+                    "TwistStamped" => {
+                        Box::new(TwistStampedPubWrapper::new(topic, frame_id.clone()))
+                    }
+                    _ => Box::new(TwistPubWrapper::new(topic)),
+                };
+                (topic.clone(), publisher)
+            })
+            .collect();
+        let measured_velocities = Arc::new(RwLock::new(None));
+        let odom_subscriber = config.odom_topic.as_ref().map(|topic| {
+            let cb_measured = measured_velocities.clone();
+            source::subscribe(topic, 1, move |odom: rosrust_msg::nav_msgs::Odometry| {
+                let velocities = Velocities {
+                    x: odom.twist.twist.linear.x,
+                    y: odom.twist.twist.linear.y,
+                    theta: odom.twist.twist.angular.z,
+                    steering_angle: 0.,
+                };
+                *cb_measured.write().unwrap() = Some((velocities, Instant::now()));
+            })
+            .unwrap()
+        });
         Teleoperate {
             viewport: viewport,
-            cmd_vel_pub: cmd_vel_publisher,
-            current_velocities: initial_velocities,
+            cmd_vel_pubs,
+            active_pub: 0,
+            target_velocities: Velocities::default(),
+            commanded_velocities: Velocities::default(),
+            max_linear_acceleration: config.max_linear_acceleration,
+            max_angular_acceleration: config.max_angular_acceleration,
+            max_vel_x: config.max_vel_x,
+            max_vel_y: config.max_vel_y,
+            max_vel_theta: config.max_vel_theta,
+            last_run: None,
             increment: config.default_increment,
             increment_step: config.increment_step,
+            steering_increment: config.default_steering_increment,
+            steering_increment_step: config.steering_increment_step,
             publish_cmd_vel_when_idle: config.publish_cmd_vel_when_idle,
             has_published_zero_once: true, // Initialize to true so the robot is not stopped when entering the mode
+            deadman_timeout: if config.deadman {
+                Some(Duration::from_secs_f64(config.deadman_timeout.max(0.0)))
+            } else {
+                None
+            },
+            last_movement: None,
+            measured_velocities,
+            odom_subscriber,
+            preview_time: config.preview_time,
+            wheelbase: config.wheelbase,
+            collision_guard: config.collision_guard,
+            stop_distance: config.stop_distance,
+            slowdown_distance: config.slowdown_distance,
+            obstacle: None,
+            sequence: Vec::new(),
+            recording: None,
+            replay_request: false,
+            replay: None,
+        }
+    }
+
+    /// Starts recording the target velocities, replacing the recorded sequence, or ends the
+    /// ongoing recording.
+    fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(start) => self.sequence.push((
+                start.elapsed().as_secs_f64(),
+                self.target_velocities.clone(),
+            )),
+            None => {
+                self.sequence = vec![(0.0, self.target_velocities.clone())];
+                self.recording = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Adds the target velocities to the ongoing recording if they changed.
+    fn record_velocities(&mut self) {
+        if let Some(start) = self.recording {
+            let changed = self.sequence.last().map_or(true, |(_, velocities)| {
+                velocities != &self.target_velocities
+            });
+            if changed {
+                self.sequence.push((
+                    start.elapsed().as_secs_f64(),
+                    self.target_velocities.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Returns the duration of the recorded sequence, in seconds.
+    fn sequence_duration(&self) -> f64 {
+        self.sequence.last().map_or(0.0, |(time, _)| *time)
+    }
+
+    /// Sets the target velocities to the ones of the sequence at the time of the ongoing
+    /// replay, and stops the robot at its end.
+    fn replay_velocities(&mut self) {
+        let elapsed = match self.replay {
+            Some(start) => start.elapsed().as_secs_f64(),
+            None => return,
+        };
+        if elapsed > self.sequence_duration() {
+            self.replay = None;
+            self.stop();
+            return;
+        }
+        if let Some((_, velocities)) = self
+            .sequence
+            .iter()
+            .rev()
+            .find(|(time, _)| *time <= elapsed)
+        {
+            self.target_velocities = velocities.clone();
+        }
+        // The sequence plays as if its keys were held
+        self.last_movement = Some(Instant::now());
+    }
+
+    /// Returns the distance from the footprint to the closest point of the latest scans in the
+    /// corridor swept by the footprint along the direction of motion, if any.
+    fn obstacle_distance(&self, (x, y): (f64, f64)) -> Option<f64> {
+        let speed = x.hypot(y);
+        if speed == 0.0 {
+            return None;
+        }
+        let direction = Vector2::new(x / speed, y / speed);
+        let viewport = self.viewport.borrow();
+        // Extent of the footprint along the direction of motion and across it
+        let along = |pt: &Vector2<f64>| pt.dot(&direction);
+        let across = |pt: &Vector2<f64>| (pt.x * direction.y - pt.y * direction.x).abs();
+        let footprint: Vec<Vector2<f64>> = viewport
+            .footprint
+            .iter()
+            .map(|&(x, y)| Vector2::new(x, y))
+            .collect();
+        let front = footprint
+            .iter()
+            .map(along)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let back = footprint.iter().map(along).fold(f64::INFINITY, f64::min);
+        let half_width = footprint.iter().map(across).fold(0.0, f64::max);
+        let robot_pose = viewport.robot_pose();
+        viewport
+            .listeners
+            .lasers
+            .iter()
+            .flat_map(|laser| laser.latest_points())
+            .map(|(x, y)| {
+                robot_pose
+                    .inverse_transform_point(&Point2::new(x, y))
+                    .coords
+            })
+            .filter(|pt| along(pt) > back && across(pt) <= half_width)
+            .map(|pt| (along(&pt) - front).max(0.0))
+            .fold(None, |closest: Option<f64>, distance| {
+                Some(closest.map_or(distance, |closest| closest.min(distance)))
+            })
+    }
+
+    /// Looks for an obstacle in the way of the robot closer than the slowdown distance, for
+    /// which the linear velocities are scaled down, down to 0 at the stop distance.
+    fn update_collision_guard(&mut self) {
+        self.obstacle = None;
+        if !self.collision_guard {
+            return;
+        }
+        let commanded = &self.commanded_velocities;
+        let distance = match self.obstacle_distance((commanded.x, commanded.y)) {
+            Some(distance) => distance,
+            None => return,
+        };
+        let scale = if distance <= self.stop_distance {
+            0.0
+        } else if distance < self.slowdown_distance {
+            (distance - self.stop_distance) / (self.slowdown_distance - self.stop_distance)
+        } else {
+            return;
+        };
+        self.obstacle = Some((distance, scale));
+    }
+
+    /// Returns the commanded velocities, with the linear ones scaled down by the collision
+    /// guard. They are not scaled in place so that the ramp resumes from the commanded ones.
+    fn published_velocities(&self) -> Velocities {
+        let scale = self.obstacle.map_or(1.0, |(_, scale)| scale);
+        Velocities {
+            x: self.commanded_velocities.x * scale,
+            y: self.commanded_velocities.y * scale,
+            ..self.commanded_velocities
+        }
+    }
+
+    /// Draws the trajectory the robot follows at the commanded velocities over the preview
+    /// time, with the footprint swept along it.
+    fn draw_preview(&self, ctx: &mut dyn ViewPainter, viewport: &Viewport) {
+        let commanded = self.published_velocities();
+        let velocities = if !self.is_ackermann() {
+            (commanded.x, commanded.y, commanded.theta)
+        } else if self.wheelbase > 0.0 {
+            let theta = commanded.x * commanded.steering_angle.tan() / self.wheelbase;
+            (commanded.x, 0.0, theta)
+        } else {
+            return;
+        };
+        if self.preview_time <= 0.0 || velocities == (0.0, 0.0, 0.0) {
+            return;
         }
+        let rotation = viewport.rotation();
+        let poses = predict_poses(&viewport.robot_pose(), velocities, self.preview_time);
+        for pose in poses.iter().skip(1) {
+            let pose_ros = transformation::iso2d_to_ros(pose);
+            for elem in get_current_footprint(&pose_ros, &viewport.footprint) {
+                ctx.draw_line(&rotation.line(&Line {
+                    x1: elem.0,
+                    y1: elem.1,
+                    x2: elem.2,
+                    y2: elem.3,
+                    color: viewport.theme.inactive,
+                }));
+            }
+        }
+        for pair in poses.windows(2) {
+            ctx.draw_line(&rotation.line(&Line {
+                x1: pair[0].translation.x,
+                y1: pair[0].translation.y,
+                x2: pair[1].translation.x,
+                y2: pair[1].translation.y,
+                color: viewport.theme.accent,
+            }));
+        }
+    }
+
+    /// Returns the commanded velocities next to the measured ones, if an odometry topic is set.
+    fn velocities_info(&self) -> Option<String> {
+        // Nothing is measured without an odometry topic
+        self.odom_subscriber.as_ref()?;
+        let commanded = self.published_velocities();
+        let measured = match &*self.measured_velocities.read().unwrap() {
+            Some((measured, received)) if received.elapsed() < MEASURED_TIMEOUT => {
+                if self.is_ackermann() {
+                    format!("{:.2}", measured.x)
+                } else {
+                    format!(
+                        "({:.2}, {:.2}, {:.2})",
+                        measured.x, measured.y, measured.theta
+                    )
+                }
+            }
+            _ => "no odometry".to_string(),
+        };
+        Some(if self.is_ackermann() {
+            format!(
+                "Commanded speed: {:.2}, Measured: {}",
+                commanded.x, measured
+            )
+        } else {
+            format!(
+                "Commanded: ({:.2}, {:.2}, {:.2}), Measured: {}",
+                commanded.x, commanded.y, commanded.theta, measured
+            )
+        })
+    }
+
+    /// Keeps the target velocities within the configured limits.
+    fn clamp_velocities(&mut self) {
+        let limits = [
+            (&mut self.target_velocities.x, self.max_vel_x),
+            (&mut self.target_velocities.y, self.max_vel_y),
+            (&mut self.target_velocities.theta, self.max_vel_theta),
+        ];
+        for (velocity, limit) in limits {
+            if let Some(limit) = limit {
+                *velocity = (*velocity).max(-limit.abs()).min(limit.abs());
+            }
+        }
+    }
+
+    /// Stops the robot at once, without ramping the velocities down.
+    fn stop(&mut self) {
+        self.target_velocities = Velocities::default();
+        self.commanded_velocities = Velocities::default();
+    }
+
+    /// Moves the commanded velocities toward the target ones, as far as the acceleration
+    /// limits allow in the time since the latest run.
+    fn ramp_velocities(&mut self) {
+        let now = Instant::now();
+        let dt = self
+            .last_run
+            .map_or(0.0, |last_run| (now - last_run).as_secs_f64());
+        self.last_run = Some(now);
+        let (linear, angular) = (self.max_linear_acceleration, self.max_angular_acceleration);
+        let (target, commanded) = (&self.target_velocities, &mut self.commanded_velocities);
+        commanded.x = ramp(commanded.x, target.x, linear, dt);
+        commanded.y = ramp(commanded.y, target.y, linear, dt);
+        commanded.theta = ramp(commanded.theta, target.theta, angular, dt);
+        commanded.steering_angle = target.steering_angle;
+    }
+
+    /// Returns true if the robot is driven while the keys are held and none was pressed lately.
+    fn deadman_released(&self) -> bool {
+        match (self.deadman_timeout, self.last_movement) {
+            (Some(timeout), Some(last_movement)) => last_movement.elapsed() > timeout,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+
+    /// Sets the target velocities for a movement key held in deadman mode: the velocity step in
+    /// the direction of the key, so that the repeats of the held key don't accelerate the robot.
+    /// Returns false if the input is not a movement key.
+    fn hold_movement(&mut self, input: &str) -> bool {
+        if self.is_ackermann() {
+            match input {
+                input::UP => self.target_velocities.x = self.increment,
+                input::DOWN => self.target_velocities.x = -self.increment,
+                input::LEFT | input::ROTATE_LEFT => {
+                    self.target_velocities.steering_angle = self.steering_increment
+                }
+                input::RIGHT | input::ROTATE_RIGHT => {
+                    self.target_velocities.steering_angle = -self.steering_increment
+                }
+                _ => return false,
+            }
+        } else {
+            let (x, y, theta) = match input {
+                input::UP => (1.0, 0.0, 0.0),
+                input::DOWN => (-1.0, 0.0, 0.0),
+                input::LEFT => (0.0, 1.0, 0.0),
+                input::RIGHT => (0.0, -1.0, 0.0),
+                input::ROTATE_LEFT => (0.0, 0.0, 1.0),
+                input::ROTATE_RIGHT => (0.0, 0.0, -1.0),
+                _ => return false,
+            };
+            self.target_velocities = Velocities {
+                x: x * self.increment,
+                y: y * self.increment,
+                theta: theta * self.increment,
+                ..Velocities::default()
+            };
+        }
+        self.clamp_velocities();
+        true
+    }
+
+    fn is_ackermann(&self) -> bool {
+        self.cmd_vel_pubs[self.active_pub].1.is_ackermann()
+    }
+
+    /// Stops the robot on the active topic, and publishes the next commands on the given one.
+    fn switch_topic(&mut self, index: usize) {
+        self.stop();
+        self.publish_current_cmd_val();
+        self.active_pub = index;
+        // The robot is not stopped again through the other topic
+        self.has_published_zero_once = true;
+    }
+}
+
+/// Returns the velocity moved toward the target within the acceleration over the time step,
+/// or the target if the acceleration is not positive, i.e. not limited.
+fn ramp(velocity: f64, target: f64, acceleration: f64, dt: f64) -> f64 {
+    let max_step = acceleration * dt;
+    if acceleration <= 0.0 || (target - velocity).abs() <= max_step {
+        target
+    } else {
+        velocity + max_step.copysign(target - velocity)
     }
 }
 
@@ -48,24 +576,99 @@ impl<B: Backend> BaseMode<B> for Teleoperate {}
 
 impl Teleoperate {
     fn publish_current_cmd_val(&mut self) {
-        let mut vel_cmd = rosrust_msg::geometry_msgs::Twist::default();
-        vel_cmd.linear.x = self.current_velocities.x;
-        vel_cmd.linear.y = self.current_velocities.y;
-        vel_cmd.angular.z = self.current_velocities.theta;
-        self.cmd_vel_pub.send(vel_cmd).unwrap();
+        self.cmd_vel_pubs[self.active_pub]
+            .1
+            .send(&self.published_velocities());
     }
 }
 
 impl AppMode for Teleoperate {
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        self.viewport.borrow_mut().handle_mouse(event);
+    }
+
     fn handle_input(&mut self, input: &String) {
+        // Any other key than confirm aborts a pending replay
+        if self.replay_request {
+            self.replay_request = false;
+            if input == input::CONFIRM {
+                self.replay = Some(Instant::now());
+            }
+            return;
+        }
+        // Any key aborts an ongoing replay and stops the robot
+        if self.replay.is_some() {
+            self.reset();
+            return;
+        }
+        match input.as_str() {
+            input::RECORD_SEQUENCE => {
+                self.toggle_recording();
+                return;
+            }
+            input::REPLAY_SEQUENCE => {
+                self.replay_request = self.recording.is_none() && !self.sequence.is_empty();
+                return;
+            }
+            _ => (),
+        }
         self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
         match input.as_str() {
-            input::UP => self.current_velocities.x += 1 as f64 * self.increment,
-            input::DOWN => self.current_velocities.x += -1 as f64 * self.increment,
-            input::LEFT => self.current_velocities.y += 1 as f64 * self.increment,
-            input::RIGHT => self.current_velocities.y -= 1 as f64 * self.increment,
-            input::ROTATE_LEFT => self.current_velocities.theta += 1 as f64 * self.increment,
-            input::ROTATE_RIGHT => self.current_velocities.theta += -1 as f64 * self.increment,
+            input::UP
+            | input::DOWN
+            | input::LEFT
+            | input::RIGHT
+            | input::ROTATE_LEFT
+            | input::ROTATE_RIGHT => self.last_movement = Some(Instant::now()),
+            input::NEXT => {
+                self.switch_topic((self.active_pub + 1) % self.cmd_vel_pubs.len());
+                return;
+            }
+            input::PREVIOUS => {
+                let count = self.cmd_vel_pubs.len();
+                self.switch_topic((self.active_pub + count - 1) % count);
+                return;
+            }
+            _ => (),
+        }
+        if self.deadman_timeout.is_some() && self.hold_movement(input) {
+            return;
+        }
+        if self.is_ackermann() {
+            match input.as_str() {
+                input::UP => self.target_velocities.x += self.increment,
+                input::DOWN => self.target_velocities.x -= self.increment,
+                input::LEFT | input::ROTATE_LEFT => {
+                    self.target_velocities.steering_angle += self.steering_increment
+                }
+                input::RIGHT | input::ROTATE_RIGHT => {
+                    self.target_velocities.steering_angle -= self.steering_increment
+                }
+                input::INCREMENT_STEP => {
+                    self.increment += self.increment_step;
+                    self.steering_increment += self.steering_increment_step;
+                }
+                input::DECREMENT_STEP => {
+                    self.increment = self
+                        .increment_step
+                        .max(self.increment - self.increment_step);
+                    self.steering_increment = self
+                        .steering_increment_step
+                        .max(self.steering_increment - self.steering_increment_step);
+                }
+                _ => self.reset(),
+            }
+            self.clamp_velocities();
+            return;
+        }
+        match input.as_str() {
+            input::UP => self.target_velocities.x += 1 as f64 * self.increment,
+            input::DOWN => self.target_velocities.x += -1 as f64 * self.increment,
+            input::LEFT => self.target_velocities.y += 1 as f64 * self.increment,
+            input::RIGHT => self.target_velocities.y -= 1 as f64 * self.increment,
+            input::ROTATE_LEFT => self.target_velocities.theta += 1 as f64 * self.increment,
+            input::ROTATE_RIGHT => self.target_velocities.theta += -1 as f64 * self.increment,
             input::INCREMENT_STEP => self.increment += self.increment_step,
             input::DECREMENT_STEP => {
                 self.increment = self
@@ -74,16 +677,28 @@ impl AppMode for Teleoperate {
             }
             _ => self.reset(),
         }
+        self.clamp_velocities();
     }
 
     fn run(&mut self) {
+        export::record(self, &self.viewport);
+        self.replay_velocities();
+        if self.deadman_released() {
+            // The zero velocities are published right away below
+            self.stop();
+            self.last_movement = None;
+        }
+        self.record_velocities();
+        self.ramp_velocities();
+        self.update_collision_guard();
         // If the velocity is reset to 0 only publish it once
         // this prevents the robot from being blocked if the
         // app mode is not closed
         if !self.publish_cmd_vel_when_idle
-            && self.current_velocities.x == 0 as f64
-            && self.current_velocities.y == 0 as f64
-            && self.current_velocities.theta == 0 as f64
+            && self.commanded_velocities.x == 0 as f64
+            && self.commanded_velocities.y == 0 as f64
+            && self.commanded_velocities.theta == 0 as f64
+            && self.commanded_velocities.steering_angle == 0 as f64
         {
             // If we did not publish the stop, do it once
             if !self.has_published_zero_once {
@@ -98,11 +713,9 @@ impl AppMode for Teleoperate {
     }
 
     fn reset(&mut self) {
-        self.current_velocities = Velocities {
-            x: 0.,
-            y: 0.,
-            theta: 0.,
-        };
+        self.replay_request = false;
+        self.replay = None;
+        self.stop();
         self.run(); // Send 0 velocities just in case
     }
 
@@ -111,46 +724,107 @@ impl AppMode for Teleoperate {
     }
 
     fn get_description(&self) -> Vec<String> {
-        vec!["This mode allows to teleoperate the robot by publishing velocity commands on the given topic.".to_string(),
-        "The viewport is centered on the robot.".to_string()]
+        let mut description = vec!["This mode allows to teleoperate the robot by publishing velocity commands on the given topic.".to_string(),
+        "The viewport is centered on the robot.".to_string()];
+        if self.deadman_timeout.is_some() {
+            description.push(
+                "The robot only moves while a movement key is held, at the velocity step in the direction of the key."
+                    .to_string(),
+            );
+        }
+        description
     }
 
     fn get_keymap(&self) -> Vec<[String; 2]> {
-        let mut keymap = vec![
-            [
-                input::UP.to_string(),
-                "Moves positively along the x axis.".to_string(),
-            ],
-            [
-                input::DOWN.to_string(),
-                "Moves negatively along the x axis.".to_string(),
-            ],
-            [
-                input::RIGHT.to_string(),
-                "Moves positively along the y axis.".to_string(),
-            ],
-            [
-                input::LEFT.to_string(),
-                "Moves negatively along the y axis.".to_string(),
-            ],
-            [
-                input::ROTATE_LEFT.to_string(),
-                "Rotates counter-clockwise.".to_string(),
-            ],
+        let mut keymap = if self.is_ackermann() {
+            vec![
+                [input::UP.to_string(), "Increases the speed.".to_string()],
+                [input::DOWN.to_string(), "Decreases the speed.".to_string()],
+                [input::LEFT.to_string(), "Steers to the left.".to_string()],
+                [input::RIGHT.to_string(), "Steers to the right.".to_string()],
+                [
+                    input::ROTATE_LEFT.to_string(),
+                    "Steers to the left.".to_string(),
+                ],
+                [
+                    input::ROTATE_RIGHT.to_string(),
+                    "Steers to the right.".to_string(),
+                ],
+                [
+                    input::INCREMENT_STEP.to_string(),
+                    "Increases the speed and steering steps.".to_string(),
+                ],
+                [
+                    input::DECREMENT_STEP.to_string(),
+                    "Decreases the speed and steering steps.".to_string(),
+                ],
+            ]
+        } else {
+            vec![
+                [
+                    input::UP.to_string(),
+                    "Moves positively along the x axis.".to_string(),
+                ],
+                [
+                    input::DOWN.to_string(),
+                    "Moves negatively along the x axis.".to_string(),
+                ],
+                [
+                    input::RIGHT.to_string(),
+                    "Moves positively along the y axis.".to_string(),
+                ],
+                [
+                    input::LEFT.to_string(),
+                    "Moves negatively along the y axis.".to_string(),
+                ],
+                [
+                    input::ROTATE_LEFT.to_string(),
+                    "Rotates counter-clockwise.".to_string(),
+                ],
+                [
+                    input::ROTATE_RIGHT.to_string(),
+                    "Rotates clockwise.".to_string(),
+                ],
+                [
+                    input::INCREMENT_STEP.to_string(),
+                    "Increases the velocity step.".to_string(),
+                ],
+                [
+                    input::DECREMENT_STEP.to_string(),
+                    "Decreases the velocity step.".to_string(),
+                ],
+            ]
+        };
+        keymap.extend([
             [
-                input::ROTATE_RIGHT.to_string(),
-                "Rotates clockwise.".to_string(),
+                input::RECORD_SEQUENCE.to_string(),
+                "Starts recording the commands, replacing the recorded sequence, or stops it."
+                    .to_string(),
             ],
             [
-                input::INCREMENT_STEP.to_string(),
-                "Increases the velocity step.".to_string(),
+                input::REPLAY_SEQUENCE.to_string(),
+                "Asks to replay the recorded sequence. Any key aborts the replay and stops the robot."
+                    .to_string(),
             ],
             [
-                input::DECREMENT_STEP.to_string(),
-                "Decreases the velocity step.".to_string(),
+                input::CONFIRM.to_string(),
+                "Confirms the replay. Any other key aborts it.".to_string(),
             ],
-        ];
+        ]);
+        if self.cmd_vel_pubs.len() > 1 {
+            keymap.extend([
+                [
+                    input::NEXT.to_string(),
+                    "Stops the robot and publishes on the next topic.".to_string(),
+                ],
+                [
+                    input::PREVIOUS.to_string(),
+                    "Stops the robot and publishes on the previous topic.".to_string(),
+                ],
+            ]);
+        }
         keymap.extend(self.viewport.borrow().get_keymap());
+        keymap.extend(self.viewport.borrow().get_pan_keymap());
         keymap.push([
             input::UNMAPPED.to_string(),
             "Reset the velocities.".to_string(),
@@ -160,19 +834,60 @@ impl AppMode for Teleoperate {
 }
 
 impl UseViewport for Teleoperate {
-    fn draw_in_viewport(&self, ctx: &mut Context) {
-        self.viewport.borrow().draw_in_viewport(ctx);
-    }
-
-    fn x_bounds(&self) -> [f64; 2] {
-        self.viewport.borrow().x_bounds()
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
     }
 
-    fn y_bounds(&self) -> [f64; 2] {
-        self.viewport.borrow().y_bounds()
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        self.draw_preview(ctx, &viewport);
     }
 
     fn info(&self) -> String {
-        format!("Velocity step: {:.2}", &self.increment)
+        let mut info = if self.is_ackermann() {
+            format!(
+                "Speed: {:.2}, Steering angle: {:.2}, Speed step: {:.2}, Steering step: {:.2}",
+                self.target_velocities.x,
+                self.target_velocities.steering_angle,
+                self.increment,
+                self.steering_increment
+            )
+        } else {
+            format!("Velocity step: {:.2}", &self.increment)
+        };
+        if let Some(start) = self.recording {
+            info += &format!(", Recording: {:.1} s", start.elapsed().as_secs_f64());
+        } else if self.replay_request {
+            info += &format!(
+                ", Replay the sequence of {:.1} s? Confirm to proceed",
+                self.sequence_duration()
+            );
+        } else if let Some(start) = self.replay {
+            info += &format!(
+                ", Replaying: {:.1}/{:.1} s",
+                start.elapsed().as_secs_f64(),
+                self.sequence_duration()
+            );
+        }
+        if let Some((distance, scale)) = self.obstacle {
+            info += &if scale == 0.0 {
+                format!(", Obstacle at {:.2} m: stopped", distance)
+            } else {
+                format!(
+                    ", Obstacle at {:.2} m: slowed to {:.0}%",
+                    distance,
+                    scale * 100.0
+                )
+            };
+        }
+        if let Some(velocities_info) = self.velocities_info() {
+            info += &format!(", {}", velocities_info);
+        }
+        if self.cmd_vel_pubs.len() > 1 {
+            info += &format!(", Topic: /{}", self.cmd_vel_pubs[self.active_pub].0);
+        }
+        info
     }
 }
@@ -1,11 +1,14 @@
 use crate::app_modes::{input, AppMode, BaseMode, Drawable};
-use crate::config::Color as ConfigColor;
 use crate::config::TermvizConfig;
-use crate::config::{ImageListenerConfig, ListenerConfig, ListenerConfigColor, PoseListenerConfig};
-use rand::Rng;
+use crate::config::{
+    default_class_colors, ImageListenerConfig, LaserListenerConfig, ListenerConfig,
+    ListenerConfigColor, PoseListenerConfig, ZRange,
+};
+use crate::source;
+use crate::theme::Theme;
 use tui::backend::Backend;
 use tui::layout::{Alignment, Constraint, Direction, Layout};
-use tui::style::{Color, Modifier, Style};
+use tui::style::{Modifier, Style};
 use tui::text::{Span, Spans};
 use tui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
 use tui::Frame;
@@ -90,10 +93,11 @@ pub struct TopicManager {
     config: TermvizConfig,
     selection_mode: bool,
     was_saved: bool,
+    theme: Theme,
 }
 
 impl TopicManager {
-    pub fn new(config: TermvizConfig) -> TopicManager {
+    pub fn new(config: TermvizConfig, theme: Theme) -> TopicManager {
         let config = config.clone();
 
         // Get all topics currently active in the config and sort them by topic type
@@ -169,10 +173,9 @@ impl TopicManager {
         // Collect all topics, which:
         //  - are supported
         //  - are inactive
-        let mut supported_topics: Vec<[String; 2]> = rosrust::topics()
-            .unwrap()
-            .iter()
-            .map(|topic| [topic.name.to_string(), topic.datatype.to_string()])
+        let mut supported_topics: Vec<[String; 2]> = source::topics()
+            .into_iter()
+            .map(|(name, datatype)| [name, datatype])
             .filter(|el| supported_topic_types.contains(&el[1].to_string()))
             .filter(|el| !all_active_topics.contains(&el))
             .collect();
@@ -186,6 +189,7 @@ impl TopicManager {
             availible_topics: supported_topic_list,
             selected_topics: SelectableTopics::new(all_active_topics),
             config: config,
+            theme,
             selection_mode: true,
             was_saved: false,
         }
@@ -221,69 +225,55 @@ impl TopicManager {
         // Fill the respective topics
         // The current implementation hardcodes where the topics must go
         // This could be handled by a more descriptive config structure
-        let mut rng = rand::thread_rng();
+        let mut colors = (0..).map(|i| self.theme.layer_color(i));
         for topic in self.selected_topics.items.iter() {
             match topic[1].clone().as_ref() {
-                "sensor_msgs/LaserScan" => config.laser_topics.push(ListenerConfigColor {
+                "sensor_msgs/LaserScan" => config.laser_topics.push(LaserListenerConfig {
                     topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
+                    color: colors.next().unwrap(),
+                    decay_time: 0.0,
                 }),
                 "visualization_msgs/MarkerArray" => {
                     config.marker_array_topics.push(ListenerConfig {
                         topic: topic[0].clone(),
+                        z_range: ZRange::default(),
                     })
                 }
                 "visualization_msgs/Marker" => config.marker_topics.push(ListenerConfig {
                     topic: topic[0].clone(),
+                    z_range: ZRange::default(),
                 }),
                 "geometry_msgs/PoseStamped" => {
                     config.pose_stamped_topics.push(PoseListenerConfig {
                         topic: topic[0].clone(),
-                        color: ConfigColor {
-                            r: rng.gen_range(0..255),
-                            g: rng.gen_range(0..255),
-                            b: rng.gen_range(0..255),
-                        },
+                        color: colors.next().unwrap(),
                         length: 0.2,
                         style: "axis".to_string(),
                     })
                 }
                 "geometry_msgs/PoseArray" => config.pose_array_topics.push(PoseListenerConfig {
                     topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
+                    color: colors.next().unwrap(),
                     length: 0.2,
                     style: "axis".to_string(),
                 }),
                 "nav_msgs/Path" => config.path_topics.push(PoseListenerConfig {
                     topic: topic[0].clone(),
-                    color: ConfigColor {
-                        r: rng.gen_range(0..255),
-                        g: rng.gen_range(0..255),
-                        b: rng.gen_range(0..255),
-                    },
+                    color: colors.next().unwrap(),
                     length: 0.2,
                     style: "axis".to_string(),
                 }),
                 "sensor_msg/Image" => config.image_topics.push(ImageListenerConfig {
                     topic: topic[0].clone(),
                     rotation: 0,
+                    detections_topic: None,
+                    detection_colors: default_class_colors(),
+                    compressed: false,
                 }),
                 "geometry_msgs/PolygonStamped" => {
                     config.polygon_stamped_topics.push(ListenerConfigColor {
                         topic: topic[0].clone(),
-                        color: ConfigColor {
-                            r: rng.gen_range(0..255),
-                            g: rng.gen_range(0..255),
-                            b: rng.gen_range(0..255),
-                        },
+                        color: colors.next().unwrap(),
                     })
                 }
 
@@ -376,7 +366,9 @@ impl<B: Backend> Drawable<B> for TopicManager {
     fn draw(&self, f: &mut Frame<B>) {
         let title_text = vec![Spans::from(Span::styled(
             "Topic Manager",
-            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            Style::default()
+                .fg(self.theme.title)
+                .add_modifier(Modifier::BOLD),
         ))];
         let areas = Layout::default()
             .direction(Direction::Vertical)
@@ -392,7 +384,7 @@ impl<B: Backend> Drawable<B> for TopicManager {
             .split(f.size());
         let title = Paragraph::new(title_text)
             .block(Block::default().borders(Borders::ALL))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
 
@@ -452,7 +444,7 @@ impl<B: Backend> Drawable<B> for TopicManager {
                 "Config has been saved, restart termviz to use it. \n Switch to any other mode to continue"
             )))
             .block(Block::default().borders(Borders::NONE))
-            .style(Style::default().fg(Color::White))
+            .style(Style::default().fg(self.theme.text))
             .alignment(Alignment::Center)
             .wrap(Wrap { trim: false });
             f.render_widget(user_info, areas[1]);
@@ -0,0 +1,263 @@
+//! Topic monitor mode shows the frequency and bandwidth of the topics, similar to `rostopic hz`
+//! and `rostopic bw` for all of them at once.
+
+use crate::app_modes::{input, AppMode, BaseMode, Drawable};
+use crate::config::TopicMonitorConfig;
+use crate::source;
+use crate::theme::Theme;
+use rosrust;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tui::backend::Backend;
+use tui::layout::{Alignment, Constraint, Layout};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap};
+use tui::Frame;
+
+/// Arrival time and size of the messages received within the averaging window.
+struct TopicStats {
+    messages: VecDeque<(Instant, usize)>,
+    last: Option<Instant>,
+}
+
+impl TopicStats {
+    fn new() -> TopicStats {
+        TopicStats {
+            messages: VecDeque::new(),
+            last: None,
+        }
+    }
+
+    fn add(&mut self, now: Instant, size: usize, window: Duration) {
+        self.messages.push_back((now, size));
+        self.last = Some(now);
+        self.remove_old(now, window);
+    }
+
+    fn remove_old(&mut self, now: Instant, window: Duration) {
+        while let Some((stamp, _)) = self.messages.front() {
+            if now.duration_since(*stamp) <= window {
+                break;
+            }
+            self.messages.pop_front();
+        }
+    }
+
+    /// Returns the frequency in Hz, computed from the time between the first and the last message.
+    fn hz(&self) -> Option<f64> {
+        let first = self.messages.front()?.0;
+        let last = self.messages.back()?.0;
+        let elapsed = last.duration_since(first).as_secs_f64();
+        if self.messages.len() < 2 || elapsed <= 0.0 {
+            return None;
+        }
+        Some((self.messages.len() - 1) as f64 / elapsed)
+    }
+
+    /// Returns the bandwidth in bytes per second over the window.
+    fn bandwidth(&self, window: Duration) -> f64 {
+        let bytes: usize = self.messages.iter().map(|(_, size)| size).sum();
+        bytes as f64 / window.as_secs_f64()
+    }
+}
+
+struct MonitoredTopic {
+    datatype: String,
+    stats: Arc<Mutex<TopicStats>>,
+    _subscriber: source::Subscriber,
+}
+
+fn format_bandwidth(bytes_per_sec: f64) -> String {
+    if bytes_per_sec >= 1e6 {
+        format!("{:.2} MB/s", bytes_per_sec / 1e6)
+    } else if bytes_per_sec >= 1e3 {
+        format!("{:.2} KB/s", bytes_per_sec / 1e3)
+    } else {
+        format!("{:.0} B/s", bytes_per_sec)
+    }
+}
+
+pub struct TopicMonitor {
+    config: TopicMonitorConfig,
+    topics: BTreeMap<String, MonitoredTopic>,
+    selected: usize,
+    theme: Theme,
+}
+
+impl TopicMonitor {
+    pub fn new(config: TopicMonitorConfig, theme: Theme) -> TopicMonitor {
+        TopicMonitor {
+            config,
+            topics: BTreeMap::new(),
+            selected: 0,
+            theme,
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs_f64(self.config.window)
+    }
+
+    /// Subscribes to the monitored topics. The messages are not deserialized,
+    /// only their size is recorded.
+    fn subscribe(&mut self) {
+        let window = self.window();
+        for (topic, datatype) in source::topics() {
+            let name = topic.trim_start_matches('/').to_string();
+            if self.topics.contains_key(&name)
+                || (!self.config.topics.is_empty() && !self.config.topics.contains(&name))
+            {
+                continue;
+            }
+            let stats = Arc::new(Mutex::new(TopicStats::new()));
+            let cb_stats = stats.clone();
+            let sub = source::subscribe(&topic, 1, move |msg: rosrust::RawMessage| {
+                cb_stats
+                    .lock()
+                    .unwrap()
+                    .add(Instant::now(), msg.0.len(), window);
+            });
+            if let Ok(sub) = sub {
+                self.topics.insert(
+                    name,
+                    MonitoredTopic {
+                        datatype,
+                        stats,
+                        _subscriber: sub,
+                    },
+                );
+            }
+        }
+    }
+}
+
+impl AppMode for TopicMonitor {
+    fn run(&mut self) {
+        if self.topics.is_empty() {
+            self.subscribe();
+        }
+    }
+
+    fn reset(&mut self) {
+        // Unsubscribes when entering and leaving the mode, to not receive messages for nothing
+        self.topics.clear();
+        self.selected = 0;
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        match input.as_str() {
+            input::UP => self.selected = self.selected.saturating_sub(1),
+            input::DOWN => {
+                self.selected = (self.selected + 1).min(self.topics.len().saturating_sub(1))
+            }
+            input::CONFIRM => self.subscribe(),
+            _ => (),
+        }
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the frequency, the bandwidth and the age of the last message of the topics.".to_string(),
+            format!(
+                "Frequency and bandwidth are averaged over the last {} seconds.",
+                self.config.window
+            ),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [
+                input::UP.to_string(),
+                "Selects the previous topic.".to_string(),
+            ],
+            [
+                input::DOWN.to_string(),
+                "Selects the next topic.".to_string(),
+            ],
+            [
+                input::CONFIRM.to_string(),
+                "Subscribes to the topics advertised since entering the mode.".to_string(),
+            ],
+        ]
+    }
+
+    fn get_name(&self) -> String {
+        "Topic Monitor".to_string()
+    }
+}
+
+impl<B: Backend> Drawable<B> for TopicMonitor {
+    fn draw(&self, f: &mut Frame<B>) {
+        let chunks = Layout::default()
+            .constraints([Constraint::Length(1), Constraint::Percentage(100)].as_ref())
+            .split(f.size());
+        let header = Paragraph::new(Spans::from(vec![
+            Span::styled(
+                self.get_name(),
+                Style::default()
+                    .fg(self.theme.title)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(format!(" - {} topics", self.topics.len())),
+        ]))
+        .block(Block::default().borders(Borders::NONE))
+        .style(Style::default().fg(self.theme.text))
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+        f.render_widget(header, chunks[0]);
+
+        let now = Instant::now();
+        let window = self.window();
+        let rows: Vec<Row> = self
+            .topics
+            .iter()
+            .map(|(name, topic)| {
+                let mut stats = topic.stats.lock().unwrap();
+                stats.remove_old(now, window);
+                let hz = match stats.hz() {
+                    Some(hz) => format!("{:.2}", hz),
+                    None => "-".to_string(),
+                };
+                let age = match stats.last {
+                    Some(last) => format!("{:.1} s", now.duration_since(last).as_secs_f64()),
+                    None => "never".to_string(),
+                };
+                let style = if stats.messages.is_empty() {
+                    Style::default().fg(self.theme.inactive)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    name.clone(),
+                    topic.datatype.clone(),
+                    hz,
+                    format_bandwidth(stats.bandwidth(window)),
+                    age,
+                ])
+                .style(style)
+            })
+            .collect();
+        let table = Table::new(rows)
+            .header(
+                Row::new(vec!["Topic", "Type", "Hz", "Bandwidth", "Last message"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .widths(&[
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Length(10),
+                Constraint::Length(12),
+                Constraint::Length(13),
+            ])
+            .highlight_style(Style::default().fg(self.theme.highlight))
+            .highlight_symbol(">> ");
+        let mut state = TableState::default();
+        state.select(Some(self.selected));
+        f.render_stateful_widget(table, chunks[1], &mut state);
+    }
+}
+
+impl<B: Backend> BaseMode<B> for TopicMonitor {}
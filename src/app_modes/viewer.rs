@@ -0,0 +1,71 @@
+//! Viewer mode only shows the viewport, without sending anything.
+//! It also draws the views of the modes showing the viewport among other panes.
+
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
+use crate::app_modes::{AppMode, BaseMode};
+use crossterm::event::MouseEvent;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use tui::backend::Backend;
+
+pub struct Viewer {
+    viewport: Rc<RefCell<Viewport>>,
+    name: String,
+}
+
+impl Viewer {
+    /// Creates the viewer, with the name shown on its top bar.
+    pub fn new(name: &str, viewport: Rc<RefCell<Viewport>>) -> Viewer {
+        Viewer {
+            viewport,
+            name: name.to_string(),
+        }
+    }
+}
+
+impl<B: Backend> BaseMode<B> for Viewer {}
+
+impl AppMode for Viewer {
+    fn run(&mut self) {
+        export::record(self, &self.viewport);
+    }
+
+    fn reset(&mut self) {}
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        self.viewport.borrow_mut().handle_mouse(event);
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode shows the viewport, without sending anything.".to_string(),
+            "It allows to inspect the received data, e.g. when reading bags.".to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        self.viewport.borrow().get_keymap()
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+impl UseViewport for Viewer {
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+    }
+}
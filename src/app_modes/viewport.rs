@@ -1,86 +1,599 @@
 //! A viewport is where markers, maps and other information are shown.
 //! A mode can borrow the viewport to draw whatever is needed.
 
+use crate::app_modes::export::Recording;
 use crate::app_modes::{input, AppMode, Drawable};
+use crate::config::{
+    BookmarkConfig, Color as ConfigColor, ExportConfig, GridConfig, LocalizationQualityConfig,
+    MinimapConfig, PaneConfig, RecordConfig, TrailConfig, ZRange,
+};
 use crate::footprint::get_current_footprint;
-use crate::listeners::Listeners;
+use crate::listeners::{Listeners, MARKERS_LAYER};
+use crate::map::MapListener;
+use crate::source;
+use crate::theme::Theme;
 use crate::transformation::{self, iso2d_to_ros};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use crossterm::terminal::size;
 use nalgebra::Isometry2;
+use std::borrow::Cow;
+use std::cell::Ref;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f64::consts::{FRAC_PI_2, PI};
 use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tui::backend::Backend;
-use tui::layout::{Constraint, Layout};
+use tui::layout::{Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
+use tui::symbols::Marker;
 use tui::text::{Span, Spans};
 use tui::widgets::canvas::{Canvas, Context, Line, Points};
-use tui::widgets::{Block, Borders};
+use tui::widgets::{Block, Borders, Clear, Paragraph};
 use tui::Frame;
 
+/// Fraction of the visible area by which the view is moved when panning with the keys.
+const PAN_STEP: f64 = 0.1;
+
+/// Angle by which the view is rotated with the keys, in radians.
+const ROTATION_STEP: f64 = 5.0 * PI / 180.0;
+
+/// Maximum number of grid lines across the width of the view, above which the grid is coarsened.
+const MAX_GRID_LINES: f64 = 20.0;
+
+/// Name of the layer of the robot footprint and frame.
+pub const ROBOT_LAYER: &str = "Robot";
+
+/// Number of map bins across a terminal cell. Braille characters have two dots across, the
+/// margin keeps exports at a larger scale sharp.
+const MAP_BINS_PER_CELL: f64 = 4.0;
+
+/// Name of the layer of the laser scan matched against the map.
+pub const LOCALIZATION_LAYER: &str = "Localization";
+
+/// The legend takes at most this fraction of the terminal width.
+const MAX_LEGEND_FRACTION: u16 = 3;
+
+/// Height by which the z range of a layer is adjusted with the keys, in meters.
+const Z_STEP: f64 = 0.1;
+
+/// Heights from which an unbounded z range starts when adjusted, e.g. the ground and a ceiling.
+const DEFAULT_Z_MIN: f64 = 0.0;
+const DEFAULT_Z_MAX: f64 = 2.0;
+
+/// Markers with which the canvas can be drawn, from the finest to the most reliable.
+const CANVAS_MARKERS: [&str; 3] = ["braille", "dot", "block"];
+
+/// Half of the smallest width shown by the minimap, in meters.
+const MIN_MINIMAP_HALF_WIDTH: f64 = 1.0;
+
+/// Distance the robot has to move for a new pose to be added to the trail, in meters.
+const TRAIL_RESOLUTION: f64 = 0.05;
+
+/// Plane of the fixed frame onto which the view is projected.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// Seen from above, the usual view.
+    Top,
+    /// Seen from the side, with the x axis of the view horizontal and the z axis up.
+    SideX,
+    /// Seen from the side, with the y axis of the view horizontal and the z axis up.
+    SideY,
+}
+
+impl Projection {
+    fn next(self) -> Projection {
+        match self {
+            Projection::Top => Projection::SideX,
+            Projection::SideX => Projection::SideY,
+            Projection::SideY => Projection::Top,
+        }
+    }
+
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Projection::Top => None,
+            Projection::SideX => Some("Side view x-z"),
+            Projection::SideY => Some("Side view y-z"),
+        }
+    }
+}
+
+/// Rotation from the fixed frame to the view, around the origin of the fixed frame,
+/// followed by the projection of the view.
+#[derive(Clone, Copy)]
+pub struct ViewRotation {
+    angle: f64,
+    cos: f64,
+    sin: f64,
+    projection: Projection,
+}
+
+impl ViewRotation {
+    pub fn new(angle: f64) -> ViewRotation {
+        ViewRotation {
+            angle,
+            cos: angle.cos(),
+            sin: angle.sin(),
+            projection: Projection::Top,
+        }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> ViewRotation {
+        ViewRotation { projection, ..self }
+    }
+
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    /// Converts a point of the fixed frame to the view, at the height 0.
+    pub fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        self.apply3((x, y, 0.0))
+    }
+
+    /// Converts a point of the fixed frame to the view.
+    pub fn apply3(&self, (x, y, z): (f64, f64, f64)) -> (f64, f64) {
+        let (x, y) = (x * self.cos - y * self.sin, x * self.sin + y * self.cos);
+        match self.projection {
+            Projection::Top => (x, y),
+            Projection::SideX => (x, z),
+            Projection::SideY => (y, z),
+        }
+    }
+
+    /// Converts a point of the view to the fixed frame. From the side, the point is taken in
+    /// the vertical plane through the origin.
+    pub fn invert(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let (x, y) = match self.projection {
+            Projection::Top => (x, y),
+            Projection::SideX => (x, 0.0),
+            Projection::SideY => (0.0, x),
+        };
+        (x * self.cos + y * self.sin, y * self.cos - x * self.sin)
+    }
+
+    /// Returns the area of the fixed frame covering the given bounds of the view, which is
+    /// larger than them if rotated.
+    pub fn covering_area(&self, x_bounds: [f64; 2], y_bounds: [f64; 2]) -> ([f64; 2], [f64; 2]) {
+        let corners: Vec<(f64, f64)> = [
+            (x_bounds[0], y_bounds[0]),
+            (x_bounds[0], y_bounds[1]),
+            (x_bounds[1], y_bounds[0]),
+            (x_bounds[1], y_bounds[1]),
+        ]
+        .iter()
+        .map(|&corner| self.invert(corner))
+        .collect();
+        let x_min = corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min);
+        let x_max = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let y_min = corners.iter().map(|c| c.1).fold(f64::INFINITY, f64::min);
+        let y_max = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f64::NEG_INFINITY, f64::max);
+        ([x_min, x_max], [y_min, y_max])
+    }
+
+    pub fn line(&self, line: &Line) -> Line {
+        self.line_at(line, 0.0)
+    }
+
+    /// Converts a line of the fixed frame lying at the given height to the view.
+    pub fn line_at(&self, line: &Line, z: f64) -> Line {
+        let (x1, y1) = self.apply3((line.x1, line.y1, z));
+        let (x2, y2) = self.apply3((line.x2, line.y2, z));
+        Line {
+            x1,
+            y1,
+            x2,
+            y2,
+            color: line.color,
+        }
+    }
+
+    /// Converts points of the fixed frame to the view, without copying them if the view is not rotated.
+    pub fn points<'a>(&self, coords: &'a [(f64, f64)]) -> Cow<'a, [(f64, f64)]> {
+        if self.angle == 0.0 && self.projection == Projection::Top {
+            Cow::Borrowed(coords)
+        } else {
+            Cow::Owned(coords.iter().map(|&point| self.apply(point)).collect())
+        }
+    }
+}
+
+/// Clips the line to the given bounds, since the canvas skips lines leaving the view.
+pub fn clip_line(line: &Line, x_bounds: [f64; 2], y_bounds: [f64; 2]) -> Option<Line> {
+    let (dx, dy) = (line.x2 - line.x1, line.y2 - line.y1);
+    let (mut start, mut end) = (0.0_f64, 1.0_f64);
+    for (p, q) in [
+        (-dx, line.x1 - x_bounds[0]),
+        (dx, x_bounds[1] - line.x1),
+        (-dy, line.y1 - y_bounds[0]),
+        (dy, y_bounds[1] - line.y1),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else if p < 0.0 {
+            start = start.max(q / p);
+        } else {
+            end = end.min(q / p);
+        }
+    }
+    if start > end {
+        return None;
+    }
+    Some(Line {
+        x1: line.x1 + start * dx,
+        y1: line.y1 + start * dy,
+        x2: line.x1 + end * dx,
+        y2: line.y1 + end * dy,
+        color: line.color,
+    })
+}
+
+/// Returns the listeners of the given topic.
+fn on_topic<'a, T>(
+    listeners: &'a [T],
+    topic: &'a str,
+    get_topic: fn(&T) -> &String,
+) -> impl Iterator<Item = &'a T> {
+    listeners
+        .iter()
+        .filter(move |listener| get_topic(listener) == topic)
+}
+
+/// Converts the name of a canvas marker, unknown names falling back to braille.
+fn to_tui_marker(name: &str) -> Marker {
+    match name {
+        "dot" => Marker::Dot,
+        "block" => Marker::Block,
+        _ => Marker::Braille,
+    }
+}
+
+/// Overview shown in a corner of the viewport.
+pub struct Minimap {
+    /// Horizontal and vertical bounds of the maps in the fixed frame.
+    pub extent: ([f64; 2], [f64; 2]),
+    pub config: MinimapConfig,
+}
+
+impl Minimap {
+    /// Returns the area of the inset in the corner of the canvas, at most half of it.
+    fn area(&self, canvas: Rect) -> Rect {
+        let width = self.config.width.min(canvas.width / 2);
+        let height = self.config.height.min(canvas.height.saturating_sub(1) / 2);
+        // The top bar is left visible
+        let top = canvas.y + 1;
+        let bottom = canvas.y + canvas.height - height;
+        let left = canvas.x;
+        let right = canvas.x + canvas.width - width;
+        let (x, y) = match self.config.corner.as_str() {
+            "top_left" => (left, top),
+            "top_right" => (right, top),
+            "bottom_left" => (left, bottom),
+            _ => (right, bottom),
+        };
+        Rect::new(x, y, width, height)
+    }
+
+    /// Returns the bounds showing the whole extent in the given area, with the same scale
+    /// along both axes.
+    fn bounds(&self, area: Rect) -> ([f64; 2], [f64; 2]) {
+        let ([x_min, x_max], [y_min, y_max]) = self.extent;
+        let (center_x, center_y) = ((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+        // Terminal cells are about twice as high as wide
+        let aspect = area.width.max(1) as f64 / (2.0 * area.height.max(1) as f64);
+        let half_width = ((x_max - x_min) / 2.0)
+            .max((y_max - y_min) / 2.0 * aspect)
+            .max(MIN_MINIMAP_HALF_WIDTH);
+        let half_height = half_width / aspect;
+        (
+            [center_x - half_width, center_x + half_width],
+            [center_y - half_height, center_y + half_height],
+        )
+    }
+}
+
+/// Returns the width of the legend panel, or 0 if there is no legend.
+pub fn legend_width(legend: &[Spans], terminal_width: u16) -> u16 {
+    if legend.is_empty() {
+        return 0;
+    }
+    // Leaves room for the border and a margin
+    let width = legend.iter().map(|line| line.width()).max().unwrap_or(0) as u16 + 2;
+    width.min(terminal_width / MAX_LEGEND_FRACTION)
+}
+
+/// Surface on which the views are drawn with primitives of the view: the canvas of the
+/// terminal, or an image when the view is exported.
+pub trait ViewPainter {
+    fn draw_line(&mut self, line: &Line);
+
+    fn draw_points(&mut self, points: &Points);
+
+    /// Prints the text at the given position of the view.
+    fn print(&mut self, x: f64, y: f64, text: Span<'static>);
+
+    /// Draws the next primitives over the previous ones.
+    fn layer(&mut self);
+}
+
+impl<'a> ViewPainter for Context<'a> {
+    fn draw_line(&mut self, line: &Line) {
+        self.draw(line);
+    }
+
+    fn draw_points(&mut self, points: &Points) {
+        self.draw(points);
+    }
+
+    fn print(&mut self, x: f64, y: f64, text: Span<'static>) {
+        let text: Span<'a> = text;
+        Context::print(self, x, y, text);
+    }
+
+    fn layer(&mut self) {
+        Context::layer(self);
+    }
+}
+
 /// Represents modes that use the viewport.
 pub trait UseViewport: AppMode {
+    /// Returns the viewport shown by the mode.
+    fn viewport(&self) -> Ref<Viewport>;
+
     /// Draws in the viewport
     ///
     /// # Arguments
-    /// - `ctx`: the tui context where to draw.
-    fn draw_in_viewport(&self, ctx: &mut Context);
+    /// - `ctx`: the canvas of the terminal, or the image of an export, where to draw.
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter);
 
     /// Returns the horizontal bounds of the window.
     /// Useful for panning/zooming the view.
-    fn x_bounds(&self) -> [f64; 2];
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport().x_bounds()
+    }
 
     /// Returns the vertical bounds of the window.
     /// Useful for panning/zooming the view.
-    fn y_bounds(&self) -> [f64; 2];
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport().y_bounds()
+    }
+
+    /// Returns additional information that will be displayed on the top bar of the viewport,
+    /// before the information on the view.
+    fn info(&self) -> String {
+        String::new()
+    }
+
+    /// Returns the information displayed on the top bar: the one of the mode, then the one of the view.
+    fn top_bar_info(&self) -> String {
+        vec![self.info(), self.viewport().info()]
+            .into_iter()
+            .filter(|info| !info.is_empty())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// Returns styled status widgets that are always displayed at the end of the top bar.
+    fn status(&self) -> Vec<Span<'static>> {
+        self.viewport().status()
+    }
+
+    /// Returns the lines of the legend shown on the right of the viewport, or nothing to hide it.
+    fn legend(&self) -> Vec<Spans<'static>> {
+        self.viewport().legend()
+    }
 
-    /// Returns additional information that will be displayed on the top bar of the viewport.
-    fn info(&self) -> String;
+    /// Returns the marker with which the canvas is drawn.
+    fn canvas_marker(&self) -> Marker {
+        self.viewport().mode_canvas_marker(&self.get_name())
+    }
+
+    /// Returns the colors of the user interface.
+    fn theme(&self) -> Theme {
+        self.viewport().theme.clone()
+    }
+
+    /// Returns the color with which the canvas is filled, if any.
+    fn canvas_background(&self) -> Option<Color> {
+        self.viewport().canvas_background()
+    }
+
+    /// Returns the overview shown in a corner of the viewport, if it is shown.
+    fn minimap(&self) -> Option<Minimap> {
+        self.viewport().minimap()
+    }
+
+    /// Draws the overview in the fixed frame.
+    fn draw_minimap(&self, ctx: &mut dyn ViewPainter) {
+        self.viewport().draw_minimap(ctx);
+    }
 }
 
 impl<B: Backend, T: UseViewport> Drawable<B> for T {
     fn draw(&self, f: &mut Frame<B>) {
-        let chunks = Layout::default()
-            .constraints([Constraint::Percentage(100)].as_ref())
-            .split(f.size());
+        draw_viewport(self, f, f.size());
+    }
+}
 
+/// Draws the viewport of the mode with its top bar and legend in the given area.
+pub fn draw_viewport<B: Backend, T: UseViewport>(mode: &T, f: &mut Frame<B>, area: Rect) {
+    let legend = mode.legend();
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(legend_width(&legend, area.width)),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let mut title = vec![
+        Span::styled(
+            mode.get_name(),
+            Style::default()
+                .fg(mode.theme().title)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(" - "),
+        Span::raw(mode.top_bar_info()),
+    ];
+    for status in mode.status() {
+        title.push(Span::raw(" | "));
+        title.push(status);
+    }
+
+    let canvas = Canvas::default()
+        .block(
+            Block::default()
+                .title(Spans::from(title))
+                .borders(Borders::NONE),
+        )
+        .x_bounds(mode.x_bounds())
+        .y_bounds(mode.y_bounds())
+        .marker(mode.canvas_marker())
+        .paint(|ctx| {
+            mode.draw_in_viewport(ctx);
+        });
+    let canvas = match mode.canvas_background() {
+        Some(color) => canvas.background_color(color),
+        None => canvas,
+    };
+    f.render_widget(canvas, chunks[0]);
+    if let Some(minimap) = mode.minimap() {
+        let area = minimap.area(chunks[0]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(mode.theme().inactive));
+        let (x_bounds, y_bounds) = minimap.bounds(block.inner(area));
         let canvas = Canvas::default()
-            .block(
-                Block::default()
-                    .title(Spans::from(vec![
-                        Span::styled(
-                            self.get_name(),
-                            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
-                        ),
-                        Span::raw(" - "),
-                        Span::raw(self.info()),
-                    ]))
-                    .borders(Borders::NONE),
-            )
-            .x_bounds(self.x_bounds())
-            .y_bounds(self.y_bounds())
-            .paint(|ctx| {
-                self.draw_in_viewport(ctx);
-            });
-        f.render_widget(canvas, chunks[0]);
+            .block(block)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .marker(mode.canvas_marker())
+            .paint(|ctx| mode.draw_minimap(ctx));
+        let canvas = match mode.canvas_background() {
+            Some(color) => canvas.background_color(color),
+            None => canvas,
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(canvas, area);
+    }
+    if !legend.is_empty() {
+        let paragraph =
+            Paragraph::new(legend).block(Block::default().title("Legend").borders(Borders::LEFT));
+        f.render_widget(paragraph, chunks[1]);
     }
 }
 
+/// Where a view of the viewport looks and what it shows, for modes showing several views.
+#[derive(Clone)]
+pub struct ViewState {
+    zoom: f64,
+    followed_frame: usize,
+    offset: (f64, f64),
+    free_camera: Option<(f64, f64)>,
+    hidden_layers: HashSet<String>,
+    robot_up: bool,
+    view_angle: f64,
+    projection: Projection,
+    current_bookmark: Option<usize>,
+}
+
 pub struct Viewport {
     pub static_frame: String,
     pub robot_frame: String,
-    pub tf_listener: Arc<rustros_tf::TfListener>,
+    pub tf_listener: Arc<source::TfListener>,
     pub initial_bounds: Vec<f64>,
     pub footprint: Vec<(f64, f64)>,
     pub axis_length: f64,
     pub zoom: f64,
     pub zoom_factor: f64,
+    /// Size of the area where the viewport is drawn: the terminal, unless a mode shows the
+    /// viewport in a pane. Updated on every tick.
     pub terminal_size: (u16, u16),
     pub listeners: Listeners, // TODO split properly config and listeners
+    /// Frames that the view can follow, starting with the robot frame.
+    pub follow_frames: Vec<String>,
+    followed_frame: usize,
+    /// Offset of the center of the view from the followed frame, in the fixed frame.
+    pub offset: (f64, f64),
+    /// Position of the fixed frame the view stays on instead of following a frame, if any.
+    free_camera: Option<(f64, f64)>,
+    pub bookmarks: Vec<BookmarkConfig>,
+    pub grid: GridConfig,
+    pub trail_config: TrailConfig,
+    pub minimap: MinimapConfig,
+    pub localization_quality: LocalizationQualityConfig,
+    /// Points of the latest scan matched against the map, with their distance to the closest
+    /// occupied cell.
+    localization: Vec<((f64, f64), f64)>,
+    /// Positions of the robot in the static frame, from the oldest to the latest.
+    trail: VecDeque<(Instant, (f64, f64))>,
+    pub export: ExportConfig,
+    pub record: RecordConfig,
+    /// Frames captured since the recording of the view started, if it is being recorded.
+    pub recording: Option<Recording>,
+    /// Result of the last export or recording of the view: the name of the file or the error.
+    pub export_status: Option<Result<String, String>>,
+    pub background_color: ConfigColor,
+    /// If true, the canvas is filled with the background color instead of showing the terminal.
+    fill_background: bool,
+    footprint_color: ConfigColor,
+    axis_colors: (ConfigColor, ConfigColor),
+    pub theme: Theme,
+    /// Name of the marker with which the canvas is drawn, unless overridden for the mode.
+    pub canvas_marker: String,
+    /// Names of the canvas markers of the modes, by mode name.
+    mode_canvas_markers: HashMap<String, String>,
+    pub show_legend: bool,
+    /// Layers in the order they are drawn, each one over the previous ones.
+    layer_order: Vec<String>,
+    /// Layers which are not drawn, although their topics are still received.
+    hidden_layers: HashSet<String>,
+    /// If true, the view is rotated so that the robot always points up.
+    pub robot_up: bool,
+    /// Rotation of the view added to the one keeping the robot up, in radians.
+    pub view_angle: f64,
+    pub projection: Projection,
+    /// Index of the last recalled bookmark, while the view stays there.
+    current_bookmark: Option<usize>,
+    /// Last terminal cell of the mouse while dragging the view.
+    drag_position: Option<(u16, u16)>,
 }
 
 impl Viewport {
     pub fn new(
         static_frame: &String,
         robot_frame: &String,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        follow_frames: &Vec<String>,
+        bookmarks: &Vec<BookmarkConfig>,
+        grid: &GridConfig,
+        trail: &TrailConfig,
+        minimap: &MinimapConfig,
+        localization_quality: &LocalizationQualityConfig,
+        export: &ExportConfig,
+        record: &RecordConfig,
+        background_color: &ConfigColor,
+        fill_background: bool,
+        footprint_color: &ConfigColor,
+        axis_colors: (&ConfigColor, &ConfigColor),
+        theme: &Theme,
+        canvas_marker: &String,
+        mode_canvas_markers: &HashMap<String, String>,
+        robot_up: bool,
+        view_rotation: f64,
+        draw_order: &Vec<String>,
+        tf_listener: Arc<source::TfListener>,
         initial_bounds: &Vec<f64>,
         footprint: &Vec<(f64, f64)>,
         axis_length: f64,
@@ -88,6 +601,28 @@ impl Viewport {
         listeners: Listeners,
         terminal_size: (u16, u16),
     ) -> Viewport {
+        // The configured layers are drawn over the others, which keep the default order
+        let layers: Vec<String> = listeners
+            .layers()
+            .into_iter()
+            .chain(if localization_quality.enabled {
+                Some(LOCALIZATION_LAYER.to_string())
+            } else {
+                None
+            })
+            .chain(std::iter::once(ROBOT_LAYER.to_string()))
+            .collect();
+        let mut layer_order: Vec<String> = layers
+            .iter()
+            .filter(|layer| !draw_order.contains(layer))
+            .cloned()
+            .collect();
+        layer_order.extend(
+            draw_order
+                .iter()
+                .filter(|layer| layers.contains(layer))
+                .cloned(),
+        );
         Viewport {
             static_frame: static_frame.clone(),
             robot_frame: robot_frame.clone(),
@@ -99,11 +634,790 @@ impl Viewport {
             axis_length: axis_length,
             listeners: listeners,
             terminal_size: terminal_size,
+            follow_frames: std::iter::once(robot_frame.clone())
+                .chain(follow_frames.iter().cloned())
+                .collect(),
+            followed_frame: 0,
+            offset: (0.0, 0.0),
+            free_camera: None,
+            bookmarks: bookmarks.clone(),
+            grid: grid.clone(),
+            trail_config: trail.clone(),
+            trail: VecDeque::new(),
+            minimap: minimap.clone(),
+            localization_quality: localization_quality.clone(),
+            localization: Vec::new(),
+            export: export.clone(),
+            record: record.clone(),
+            recording: None,
+            export_status: None,
+            background_color: background_color.clone(),
+            fill_background,
+            footprint_color: footprint_color.clone(),
+            axis_colors: (axis_colors.0.clone(), axis_colors.1.clone()),
+            theme: theme.clone(),
+            canvas_marker: canvas_marker.clone(),
+            mode_canvas_markers: mode_canvas_markers.clone(),
+            robot_up,
+            show_legend: false,
+            layer_order,
+            hidden_layers: HashSet::new(),
+            view_angle: view_rotation.to_radians(),
+            projection: Projection::Top,
+            current_bookmark: None,
+            drag_position: None,
+        }
+    }
+
+    /// Returns the current state of the view.
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            zoom: self.zoom,
+            followed_frame: self.followed_frame,
+            offset: self.offset,
+            free_camera: self.free_camera,
+            hidden_layers: self.hidden_layers.clone(),
+            robot_up: self.robot_up,
+            view_angle: self.view_angle,
+            projection: self.projection,
+            current_bookmark: self.current_bookmark,
+        }
+    }
+
+    /// Replaces the state of the view, e.g. to draw another view of a mode.
+    pub fn set_view_state(&mut self, state: ViewState) {
+        self.zoom = state.zoom;
+        self.followed_frame = state.followed_frame;
+        self.offset = state.offset;
+        self.free_camera = state.free_camera;
+        self.hidden_layers = state.hidden_layers;
+        self.robot_up = state.robot_up;
+        self.view_angle = state.view_angle;
+        self.projection = state.projection;
+        self.current_bookmark = state.current_bookmark;
+        self.drag_position = None;
+    }
+
+    /// Returns the initial state of a configured view. An unknown followed frame falls back to
+    /// the robot frame.
+    pub fn pane_view_state(&self, pane: &PaneConfig) -> ViewState {
+        ViewState {
+            zoom: pane.zoom,
+            followed_frame: pane
+                .follow_frame
+                .as_ref()
+                .and_then(|frame| self.follow_frames.iter().position(|f| f == frame))
+                .unwrap_or(0),
+            offset: (0.0, 0.0),
+            free_camera: None,
+            hidden_layers: pane.hidden_layers.iter().cloned().collect(),
+            robot_up: pane.robot_up,
+            view_angle: self.view_angle,
+            projection: Projection::Top,
+            current_bookmark: None,
+        }
+    }
+
+    /// Returns the center of the view: the position of the followed frame, or the frozen
+    /// position of the free camera, shifted by the offset.
+    pub fn center(&self) -> (f64, f64) {
+        let position = match self.free_camera {
+            Some(position) => position,
+            None => self.followed_position(),
+        };
+        (position.0 + self.offset.0, position.1 + self.offset.1)
+    }
+
+    /// Returns the position of the followed frame, or the origin if the frame is unknown.
+    fn followed_position(&self) -> (f64, f64) {
+        match self.tf_listener.lookup_transform(
+            &self.static_frame,
+            &self.follow_frames[self.followed_frame],
+            rosrust::Time::new(),
+        ) {
+            Ok(res) => (res.transform.translation.x, res.transform.translation.y),
+            Err(_e) => (0.0, 0.0),
+        }
+    }
+
+    /// Freezes the view at its current center, or makes it follow the frame again.
+    fn toggle_free_camera(&mut self) {
+        self.free_camera = match self.free_camera {
+            Some(_) => None,
+            None => Some(self.center()),
+        };
+        self.offset = (0.0, 0.0);
+        self.current_bookmark = None;
+    }
+
+    fn recenter(&mut self) {
+        self.free_camera = None;
+        self.offset = (0.0, 0.0);
+        self.current_bookmark = None;
+    }
+
+    /// Saves the current center and zoom as a new bookmark.
+    fn save_bookmark(&mut self) {
+        let (x, y) = self.center();
+        self.bookmarks.push(BookmarkConfig {
+            name: format!("Bookmark {}", self.bookmarks.len() + 1),
+            x,
+            y,
+            zoom: self.zoom,
+        });
+        self.current_bookmark = Some(self.bookmarks.len() - 1);
+    }
+
+    /// Moves the view to the next bookmark, with the free camera.
+    fn recall_next_bookmark(&mut self) {
+        if self.bookmarks.is_empty() {
+            return;
+        }
+        let index = match self.current_bookmark {
+            Some(i) => (i + 1) % self.bookmarks.len(),
+            None => 0,
+        };
+        let bookmark = &self.bookmarks[index];
+        self.free_camera = Some((bookmark.x, bookmark.y));
+        self.offset = (0.0, 0.0);
+        self.zoom = bookmark.zoom;
+        self.current_bookmark = Some(index);
+    }
+
+    fn follow_next_frame(&mut self) {
+        self.followed_frame = (self.followed_frame + 1) % self.follow_frames.len();
+        self.recenter();
+    }
+
+    /// Moves the view by the given number of steps along each axis of the view.
+    fn pan(&mut self, x_steps: f64, y_steps: f64) {
+        let [x_min, x_max] = self.x_bounds();
+        let [y_min, y_max] = self.y_bounds();
+        let (dx, dy) = self.rotation().invert((
+            x_steps * PAN_STEP * (x_max - x_min),
+            y_steps * PAN_STEP * (y_max - y_min),
+        ));
+        self.offset.0 += dx;
+        self.offset.1 += dy;
+    }
+
+    /// Changes the zoom by the given number of steps, keeping the given point at the same place
+    /// on the screen.
+    fn zoom_around(&mut self, point: (f64, f64), steps: f64) {
+        let zoom = self.zoom + steps * self.zoom_factor;
+        if zoom <= 0.0 {
+            return;
         }
+        let center = self.center();
+        let ratio = self.zoom / zoom;
+        self.offset.0 += (point.0 - center.0) * (1.0 - ratio);
+        self.offset.1 += (point.1 - center.1) * (1.0 - ratio);
+        self.zoom = zoom;
     }
+
+    /// Returns the layers in the order they are drawn, with whether they are visible.
+    pub fn layers(&self) -> Vec<(String, bool)> {
+        self.layer_order
+            .iter()
+            .map(|layer| (layer.clone(), self.is_visible(layer)))
+            .collect()
+    }
+
+    /// Hides a visible layer or shows a hidden one.
+    pub fn toggle_layer(&mut self, layer: &str) {
+        if !self.hidden_layers.remove(layer) {
+            self.hidden_layers.insert(layer.to_string());
+        }
+    }
+
+    fn is_visible(&self, layer: &str) -> bool {
+        !self.hidden_layers.contains(layer)
+    }
+
+    /// Moves the layer at the given index in the draw order, over the next layer if forward, or
+    /// under the previous one. Returns the new index of the layer.
+    pub fn move_layer(&mut self, index: usize, forward: bool) -> usize {
+        let other = if forward {
+            index + 1
+        } else {
+            index.wrapping_sub(1)
+        };
+        if index < self.layer_order.len() && other < self.layer_order.len() {
+            self.layer_order.swap(index, other);
+            other
+        } else {
+            index
+        }
+    }
+
+    /// Returns the heights between which the layer is drawn, if it can be filtered by height.
+    pub fn z_range(&self, layer: &str) -> Option<ZRange> {
+        if layer == MARKERS_LAYER {
+            return Some(self.listeners.markers.get_z_range());
+        }
+        self.listeners
+            .pointclouds
+            .iter()
+            .find(|pointcloud| pointcloud.config.topic == layer)
+            .map(|pointcloud| pointcloud.config.z_range)
+    }
+
+    /// Sets the heights between which the layer is drawn, if it can be filtered by height.
+    pub fn set_z_range(&mut self, layer: &str, z_range: ZRange) {
+        if layer == MARKERS_LAYER {
+            self.listeners.markers.set_z_range(z_range);
+        }
+        for pointcloud in self.listeners.pointclouds.iter_mut() {
+            if pointcloud.config.topic == layer {
+                pointcloud.config.z_range = z_range;
+            }
+        }
+    }
+
+    /// Moves the lower and upper bounds of the heights drawn for the layer by the given number
+    /// of steps. An unbounded limit starts from a default height.
+    pub fn adjust_z_range(&mut self, layer: &str, min_steps: f64, max_steps: f64) {
+        if let Some(mut z_range) = self.z_range(layer) {
+            if min_steps != 0.0 {
+                z_range.min = Some(z_range.min.unwrap_or(DEFAULT_Z_MIN) + min_steps * Z_STEP);
+            }
+            if max_steps != 0.0 {
+                z_range.max = Some(z_range.max.unwrap_or(DEFAULT_Z_MAX) + max_steps * Z_STEP);
+            }
+            self.set_z_range(layer, z_range);
+        }
+    }
+
+    /// Returns the marker with which the canvas of the given mode is drawn.
+    pub fn mode_canvas_marker(&self, mode: &str) -> Marker {
+        to_tui_marker(
+            self.mode_canvas_markers
+                .get(mode)
+                .unwrap_or(&self.canvas_marker),
+        )
+    }
+
+    /// Draws the canvas of the given mode with the next marker.
+    pub fn next_canvas_marker(&mut self, mode: &str) {
+        let current = self
+            .mode_canvas_markers
+            .get(mode)
+            .unwrap_or(&self.canvas_marker);
+        // Unknown names are drawn as braille, so they are followed by the second marker
+        let index = CANVAS_MARKERS
+            .iter()
+            .position(|marker| marker == current)
+            .map_or(1, |i| (i + 1) % CANVAS_MARKERS.len());
+        self.mode_canvas_markers
+            .insert(mode.to_string(), CANVAS_MARKERS[index].to_string());
+    }
+
+    /// Saves the visible maps in the format of map_server, in the current directory, and keeps
+    /// the result to show it.
+    fn save_maps(&mut self) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let result: Result<Vec<String>, String> = self
+            .listeners
+            .maps
+            .iter()
+            .filter(|map| self.is_visible(&map.config.topic))
+            .map(|map| {
+                let basename = format!(
+                    "termviz_{}_{}",
+                    map.config.topic.trim_start_matches('/').replace('/', "_"),
+                    now.as_secs()
+                );
+                map.save(&basename)
+                    .map(|_| format!("{}.yaml", basename))
+                    .map_err(|e| format!("{}: {}", map.config.topic, e))
+            })
+            .collect();
+        self.export_status = Some(match result {
+            Ok(files) if files.is_empty() => Err("no visible map".to_string()),
+            Ok(files) => Ok(files.join(", ")),
+            Err(e) => Err(e),
+        });
+    }
+
+    /// Returns the size of the bins in which the maps are drawn, a fraction of a terminal cell.
+    fn map_bin_size(&self) -> f64 {
+        let [x_min, x_max] = self.x_bounds();
+        (x_max - x_min) / (self.terminal_size.0.max(1) as f64 * MAP_BINS_PER_CELL)
+    }
+
+    /// Returns the points of the map to draw by color, only where its base map is free if it
+    /// has one. Given the visible area of the fixed frame, the points far outside of it are
+    /// skipped.
+    fn map_points(
+        &self,
+        map: &MapListener,
+        area: Option<([f64; 2], [f64; 2])>,
+    ) -> Vec<(Vec<(f64, f64)>, Color)> {
+        let groups = map.get_points(self.map_bin_size(), area);
+        let base = map.config.only_where_free.as_ref().and_then(|topic| {
+            self.listeners
+                .maps
+                .iter()
+                .find(|base| &base.config.topic == topic)
+        });
+        let base = match base {
+            Some(base) => base,
+            None => return groups,
+        };
+        groups
+            .into_iter()
+            .map(|(points, color)| {
+                // Everything is drawn until the base map is received
+                match base.free_at(&points) {
+                    Some(free) => (
+                        points
+                            .iter()
+                            .zip(free)
+                            .filter(|(_, free)| *free)
+                            .map(|(pt, _)| *pt)
+                            .collect(),
+                        color,
+                    ),
+                    None => (points, color),
+                }
+            })
+            .collect()
+    }
+
+    /// Matches the latest scan of the configured laser against the configured map.
+    fn match_scan(&mut self) {
+        if !self.localization_quality.enabled {
+            return;
+        }
+        let config = &self.localization_quality;
+        let laser = self.listeners.lasers.iter().find(|laser| {
+            config
+                .laser_topic
+                .as_ref()
+                .map_or(true, |topic| &laser.config.topic == topic)
+        });
+        let map = self.listeners.maps.iter().find(|map| {
+            config
+                .map_topic
+                .as_ref()
+                .map_or(true, |topic| &map.config.topic == topic)
+        });
+        self.localization = match (laser, map) {
+            (Some(laser), Some(map)) => {
+                let points = laser.latest_points();
+                match map.distances_to_occupied(&points, config.max_distance) {
+                    Some(distances) => points.into_iter().zip(distances).collect(),
+                    None => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        };
+    }
+
+    /// Returns the percentage of the latest scan matching the map, if it could be matched.
+    fn localization_score(&self) -> Option<f64> {
+        if self.localization.is_empty() || self.localization_quality.max_distance <= 0.0 {
+            return None;
+        }
+        let max_distance = self.localization_quality.max_distance;
+        let matched: f64 = self
+            .localization
+            .iter()
+            .map(|(_, distance)| 1.0 - distance / max_distance)
+            .sum();
+        Some(100.0 * matched / self.localization.len() as f64)
+    }
+
+    /// Adds the current position of the robot to the trail, and drops the positions which are
+    /// too old or too far along the trail.
+    fn record_trail(&mut self) {
+        if !self.trail_config.enabled {
+            return;
+        }
+        let now = Instant::now();
+        if let Ok(res) = self.tf_listener.lookup_transform(
+            &self.static_frame,
+            &self.robot_frame,
+            rosrust::Time::new(),
+        ) {
+            let position = (res.transform.translation.x, res.transform.translation.y);
+            let moved = self.trail.back().map_or(true, |(_, last)| {
+                (position.0 - last.0).hypot(position.1 - last.1) >= TRAIL_RESOLUTION
+            });
+            if moved {
+                self.trail.push_back((now, position));
+            }
+        }
+
+        let max_age = self.trail_config.max_age;
+        while max_age > 0.0
+            && self.trail.front().map_or(false, |(received, _)| {
+                now.duration_since(*received).as_secs_f64() > max_age
+            })
+        {
+            self.trail.pop_front();
+        }
+        if self.trail_config.max_length > 0.0 {
+            // Walks back from the latest position until the trail is too long
+            let mut length = 0.0;
+            let mut start = 0;
+            for i in (1..self.trail.len()).rev() {
+                let (p1, p2) = (self.trail[i].1, self.trail[i - 1].1);
+                length += (p1.0 - p2.0).hypot(p1.1 - p2.1);
+                if length > self.trail_config.max_length {
+                    start = i;
+                    break;
+                }
+            }
+            self.trail.drain(..start);
+        }
+    }
+
+    /// Draws the trail of the robot, fading out toward its oldest end.
+    fn draw_trail(&self, ctx: &mut dyn ViewPainter, rotation: &ViewRotation) {
+        let len = self.trail.len();
+        for i in 1..len {
+            let (p1, p2) = (self.trail[i - 1].1, self.trail[i].1);
+            // The oldest end does not fade completely, so that the whole trail stays visible
+            let fade = 0.8 * (len - 1 - i) as f64 / (len - 1) as f64;
+            ctx.draw_line(&rotation.line(&Line {
+                x1: p1.0,
+                y1: p1.1,
+                x2: p2.0,
+                y2: p2.1,
+                color: self.trail_config.color.fade(&self.background_color, fade),
+            }));
+        }
+    }
+
+    fn draw_robot(&self, ctx: &mut dyn ViewPainter, rotation: &ViewRotation) {
+        let base_link_pose = self.tf_listener.lookup_transform(
+            &self.static_frame,
+            &self.robot_frame,
+            rosrust::Time::new(),
+        );
+
+        let robot_pose = if base_link_pose.is_ok() {
+            base_link_pose.unwrap().transform
+        } else {
+            iso2d_to_ros(&Isometry2::identity())
+        };
+
+        for elem in get_current_footprint(&robot_pose, &self.footprint) {
+            ctx.draw_line(&rotation.line(&Line {
+                x1: elem.0,
+                y1: elem.1,
+                x2: elem.2,
+                y2: elem.3,
+                color: self.footprint_color.to_tui(),
+            }));
+        }
+
+        let axis_colors = (self.axis_colors.0.to_tui(), self.axis_colors.1.to_tui());
+        for line in Viewport::get_frame_lines(&robot_pose, self.axis_length, axis_colors) {
+            ctx.draw_line(&rotation.line(&line));
+        }
+    }
+
+    /// Draws the listeners of the given layer. Given the visible area of the fixed frame, the
+    /// maps are only drawn around it.
+    fn draw_layer(
+        &self,
+        ctx: &mut dyn ViewPainter,
+        layer: &str,
+        rotation: &ViewRotation,
+        area: Option<([f64; 2], [f64; 2])>,
+    ) {
+        if layer == ROBOT_LAYER {
+            self.draw_trail(ctx, rotation);
+            self.draw_robot(ctx, rotation);
+        }
+        if layer == LOCALIZATION_LAYER {
+            let config = &self.localization_quality;
+            for &(pt, distance) in self.localization.iter() {
+                ctx.draw_points(&Points {
+                    coords: &[rotation.apply(pt)],
+                    color: config
+                        .matched_color
+                        .fade(&config.unmatched_color, distance / config.max_distance),
+                });
+            }
+        }
+        if layer == MARKERS_LAYER {
+            for (z, line) in self.listeners.markers.get_lines() {
+                ctx.draw_line(&rotation.line_at(&line, z));
+            }
+            for text in self.listeners.markers.get_texts() {
+                let (x, y) = rotation.apply3((text.x, text.y, text.z));
+                ctx.print(
+                    x,
+                    y,
+                    Span::styled(text.text, Style::default().fg(text.color)),
+                );
+            }
+        }
+        let listeners = &self.listeners;
+        for map in on_topic(&listeners.maps, layer, |l| &l.config.topic) {
+            for (points, color) in self.map_points(map, area) {
+                ctx.draw_points(&Points {
+                    coords: &rotation.points(&points),
+                    color,
+                });
+            }
+        }
+        for cells in on_topic(&listeners.grid_cells, layer, |l| &l.config.topic) {
+            ctx.draw_points(&Points {
+                coords: &rotation.points(&cells.points.read().unwrap()),
+                color: cells.config.color.to_tui(),
+            });
+        }
+        for pointcloud in on_topic(&listeners.pointclouds, layer, |l| &l.config.topic) {
+            let points = pointcloud.get_points();
+            let z_range = pointcloud.config.z_range;
+            for pt in points.iter().filter(|pt| z_range.contains(pt.point.z)) {
+                ctx.draw_points(&Points {
+                    coords: &[rotation.apply3((pt.point.x, pt.point.y, pt.point.z))],
+                    color: pt.color,
+                })
+            }
+        }
+        for navsat_fix in on_topic(&listeners.navsat_fixes, layer, |l| &l.config.topic) {
+            for (pt, color) in navsat_fix.get_points() {
+                ctx.draw_points(&Points {
+                    coords: &[rotation.apply(pt)],
+                    color,
+                })
+            }
+        }
+        for laser in on_topic(&listeners.lasers, layer, |l| &l.config.topic) {
+            ctx.draw_points(&Points {
+                coords: &rotation.points(&laser.get_memory_points()),
+                color: laser.memory_color(),
+            });
+            // Older scans first, so that the latest one is drawn on top
+            for (points, color) in laser.get_points() {
+                ctx.draw_points(&Points {
+                    coords: &rotation.points(&points),
+                    color,
+                });
+            }
+        }
+
+        let mut lines = Vec::new();
+        for detection in on_topic(&listeners.detections, layer, |l| &l.config.topic) {
+            lines.extend(detection.get_lines());
+        }
+        for range in on_topic(&listeners.ranges, layer, |l| &l.config.topic) {
+            lines.extend(range.get_lines());
+        }
+        for camera_info in on_topic(&listeners.camera_infos, layer, |l| &l.config.topic) {
+            lines.extend(camera_info.get_lines());
+        }
+        for pose_stamped in on_topic(&listeners.pose_stamped, layer, |l| &l.config.topic) {
+            lines.extend(pose_stamped.get_lines());
+        }
+        for polygon in on_topic(&listeners.polygons, layer, |l| &l.config.topic) {
+            lines.extend(polygon.get_lines());
+        }
+        for path in on_topic(&listeners.paths, layer, |l| &l.config.topic) {
+            lines.extend(path.get_lines());
+        }
+        for pose_array in on_topic(&listeners.pose_array, layer, |l| &l.config.topic) {
+            lines.extend(pose_array.get_lines());
+        }
+        for pose in on_topic(&listeners.pose_with_covariance, layer, |l| &l.config.topic) {
+            lines.extend(pose.get_lines());
+        }
+        for odometry in on_topic(&listeners.odometries, layer, |l| &l.config.topic) {
+            lines.extend(odometry.get_lines());
+        }
+        for point in on_topic(&listeners.points, layer, |l| &l.config.topic) {
+            lines.extend(point.get_lines());
+        }
+        for imu in on_topic(&listeners.imus, layer, |l| &l.config.topic) {
+            lines.extend(imu.get_lines());
+        }
+        for twist in on_topic(&listeners.twists, layer, |l| &l.config.topic) {
+            lines.extend(twist.get_lines());
+        }
+        for wrench in on_topic(&listeners.wrenches, layer, |l| &l.config.topic) {
+            lines.extend(wrench.get_lines());
+        }
+        for line in lines {
+            ctx.draw_line(&rotation.line(&line));
+        }
+    }
+
+    /// Returns the rotation from the fixed frame to the view.
+    pub fn rotation(&self) -> ViewRotation {
+        let rotation = if self.robot_up {
+            ViewRotation::new(FRAC_PI_2 - self.robot_pose().rotation.angle() + self.view_angle)
+        } else {
+            ViewRotation::new(self.view_angle)
+        };
+        rotation.with_projection(self.projection)
+    }
+
+    /// Rotates the view by the given number of steps, counter-clockwise.
+    fn rotate(&mut self, steps: f64) {
+        self.view_angle = (self.view_angle + steps * ROTATION_STEP).rem_euclid(2.0 * PI);
+        // Avoids rotating the points for nothing once back to the fixed frame
+        if self.view_angle < 1e-9 || 2.0 * PI - self.view_angle < 1e-9 {
+            self.view_angle = 0.0;
+        }
+    }
+
+    /// Converts a position on the terminal to a position in the fixed frame, given the bounds
+    /// of the view shown in the area of the viewport, which is narrower than the terminal in
+    /// split modes. Returns None on the top bar.
+    pub fn terminal_to_world(
+        &self,
+        [x_min, x_max]: [f64; 2],
+        [y_min, y_max]: [f64; 2],
+        column: u16,
+        row: u16,
+    ) -> Option<(f64, f64)> {
+        let (width, height) = self.terminal_size;
+        // The canvas covers the area below the top bar
+        let width = width - legend_width(&self.legend(), width);
+        if row == 0 || width == 0 || height < 2 || column >= width {
+            return None;
+        }
+        let point = (
+            x_min + (column as f64 + 0.5) / width as f64 * (x_max - x_min),
+            y_max - (row as f64 - 0.5) / (height - 1) as f64 * (y_max - y_min),
+        );
+        Some(self.rotation().invert(point))
+    }
+
+    /// Returns the pose of the robot in the static frame, or the origin if unknown.
+    pub fn robot_pose(&self) -> Isometry2<f64> {
+        match self.tf_listener.lookup_transform(
+            &self.static_frame,
+            &self.robot_frame,
+            rosrust::Time::new(),
+        ) {
+            Ok(res) => transformation::ros_to_iso2d(&res.transform),
+            Err(_e) => Isometry2::identity(),
+        }
+    }
+
+    /// Returns the horizontal bounds of the window centered on the given point of the fixed frame.
+    pub fn x_bounds_around(&self, point: (f64, f64)) -> [f64; 2] {
+        let x = self.rotation().apply(point).0;
+        let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
+        [
+            x + self.initial_bounds[0] / self.zoom * scale_factor,
+            x + self.initial_bounds[1] / self.zoom * scale_factor,
+        ]
+    }
+
+    /// Returns the vertical bounds of the window centered on the given point of the fixed frame.
+    pub fn y_bounds_around(&self, point: (f64, f64)) -> [f64; 2] {
+        let y = if self.projection == Projection::Top {
+            self.rotation().apply(point).1
+        } else {
+            // From the side, the ground is shown a quarter of the height above the bottom
+            let height = self.initial_bounds[3] - self.initial_bounds[2];
+            -(self.initial_bounds[2] + height / 4.0) / self.zoom
+        };
+        [
+            y + self.initial_bounds[2] / self.zoom,
+            y + self.initial_bounds[3] / self.zoom,
+        ]
+    }
+
+    /// Returns the occupancy values of the maps at the given point, with the topic of each map.
+    pub fn map_values_at(&self, x: f64, y: f64) -> Vec<(String, i8)> {
+        self.listeners
+            .maps
+            .iter()
+            .filter_map(|map| Some((map.config.topic.clone(), map.value_at(x, y)?)))
+            .collect()
+    }
+
+    /// Returns the spacing of the grid, coarsened so that the lines don't clutter the view.
+    pub fn grid_spacing(&self) -> f64 {
+        let [x_min, x_max] = self.x_bounds_around((0.0, 0.0));
+        let mut spacing = self.grid.spacing;
+        while (x_max - x_min) / spacing > MAX_GRID_LINES {
+            spacing *= 2.0;
+        }
+        spacing
+    }
+
+    /// Draws the visible layers over the given bounds of the view, each over the previous ones.
+    pub fn draw_layers(&self, ctx: &mut dyn ViewPainter, x_bounds: [f64; 2], y_bounds: [f64; 2]) {
+        let rotation = self.rotation();
+        // From the side, the whole maps are in view
+        let area = if self.projection == Projection::Top {
+            Some(rotation.covering_area(x_bounds, y_bounds))
+        } else {
+            None
+        };
+        for layer in &self.layer_order {
+            if self.is_visible(layer) {
+                self.draw_layer(ctx, layer, &rotation, area);
+                ctx.layer();
+            }
+        }
+    }
+
+    /// Draws the grid of the fixed frame over the given bounds of the view, if enabled.
+    /// The grid is meant as a background, so it should be drawn before the viewport.
+    pub fn draw_grid(&self, ctx: &mut dyn ViewPainter, x_bounds: [f64; 2], y_bounds: [f64; 2]) {
+        if !self.grid.enabled || self.grid.spacing <= 0.0 {
+            return;
+        }
+        let spacing = self.grid_spacing();
+        let color = self.grid.color.to_tui();
+        let rotation = self.rotation();
+        let ([x_min, x_max], [y_min, y_max]) = rotation.covering_area(x_bounds, y_bounds);
+        let mut lines = Vec::new();
+        let mut x = (x_min / spacing).ceil() * spacing;
+        while x <= x_max {
+            lines.push(Line {
+                x1: x,
+                y1: y_min,
+                x2: x,
+                y2: y_max,
+                color,
+            });
+            x += spacing;
+        }
+        let mut y = (y_min / spacing).ceil() * spacing;
+        while y <= y_max {
+            lines.push(Line {
+                x1: x_min,
+                y1: y,
+                x2: x_max,
+                y2: y,
+                color,
+            });
+            y += spacing;
+        }
+        for line in lines {
+            if let Some(line) = clip_line(&rotation.line(&line), x_bounds, y_bounds) {
+                ctx.draw_line(&line);
+            }
+        }
+    }
+
+    /// Returns the descriptions of the monitored values which are out of their range.
+    pub fn alerts(&self) -> Vec<String> {
+        self.listeners
+            .fields
+            .iter()
+            .filter_map(|field| field.alert())
+            .collect()
+    }
+
     pub fn get_frame_lines(
         tf: &rosrust_msg::geometry_msgs::Transform,
         axis_length: f64,
+        (x_color, y_color): (Color, Color),
     ) -> Vec<Line> {
         let mut result: Vec<Line> = Vec::new();
         let base_x = transformation::transform_relative_pt(&tf, (axis_length, 0.0));
@@ -113,30 +1427,156 @@ impl Viewport {
             y1: tf.translation.y,
             x2: base_x.0,
             y2: base_x.1,
-            color: Color::Red,
+            color: x_color,
         });
         result.push(Line {
             x1: tf.translation.x,
             y1: tf.translation.y,
             x2: base_y.0,
             y2: base_y.1,
-            color: Color::Green,
+            color: y_color,
         });
         result
     }
+
+    /// Returns the key mapping of the panning, for the modes where the view follows the robot.
+    pub fn get_pan_keymap(&self) -> Vec<[String; 2]> {
+        vec![
+            [input::PAN_UP.to_string(), "Pans the view up.".to_string()],
+            [
+                input::PAN_DOWN.to_string(),
+                "Pans the view down.".to_string(),
+            ],
+            [
+                input::PAN_LEFT.to_string(),
+                "Pans the view to the left.".to_string(),
+            ],
+            [
+                input::PAN_RIGHT.to_string(),
+                "Pans the view to the right.".to_string(),
+            ],
+            [
+                input::RECENTER.to_string(),
+                "Centers the view on the followed frame.".to_string(),
+            ],
+            [
+                input::FREE_CAMERA.to_string(),
+                "Freezes the view at its current position, or makes it follow the frame again."
+                    .to_string(),
+            ],
+            [
+                input::SAVE_BOOKMARK.to_string(),
+                "Saves the current view as a bookmark.".to_string(),
+            ],
+            [
+                input::NEXT_BOOKMARK.to_string(),
+                "Moves the view to the next bookmark.".to_string(),
+            ],
+            [
+                input::FOLLOW_NEXT_FRAME.to_string(),
+                "Follows the next frame among the robot frame and the configured ones.".to_string(),
+            ],
+            [
+                input::ROTATE_VIEW_LEFT.to_string(),
+                "Rotates the view counter-clockwise.".to_string(),
+            ],
+            [
+                input::ROTATE_VIEW_RIGHT.to_string(),
+                "Rotates the view clockwise.".to_string(),
+            ],
+            [
+                input::ROBOT_UP.to_string(),
+                "Toggles the rotation of the view keeping the robot pointing up.".to_string(),
+            ],
+        ]
+    }
 }
 
 impl AppMode for Viewport {
-    fn run(&mut self) {}
+    fn run(&mut self) {
+        if let Ok(terminal_size) = size() {
+            self.terminal_size = terminal_size;
+        }
+        for map in self.listeners.maps.iter() {
+            map.refresh();
+        }
+        self.record_trail();
+        self.match_scan();
+    }
     fn reset(&mut self) {}
     fn handle_input(&mut self, input: &String) {
         match input.as_str() {
             input::ZOOM_IN => self.zoom += self.zoom_factor,
             input::ZOOM_OUT => self.zoom -= self.zoom_factor,
+            input::PAN_UP => self.pan(0.0, 1.0),
+            input::PAN_DOWN => self.pan(0.0, -1.0),
+            input::PAN_LEFT => self.pan(-1.0, 0.0),
+            input::PAN_RIGHT => self.pan(1.0, 0.0),
+            input::RECENTER => self.recenter(),
+            input::FREE_CAMERA => self.toggle_free_camera(),
+            input::SAVE_BOOKMARK => self.save_bookmark(),
+            input::NEXT_BOOKMARK => self.recall_next_bookmark(),
+            input::FOLLOW_NEXT_FRAME => self.follow_next_frame(),
+            input::ROBOT_UP => self.robot_up = !self.robot_up,
+            input::SHOW_LEGEND => self.show_legend = !self.show_legend,
+            input::SHOW_MINIMAP => self.minimap.enabled = !self.minimap.enabled,
+            input::NEXT_PROJECTION => self.projection = self.projection.next(),
+            input::ROTATE_VIEW_LEFT => self.rotate(1.0),
+            input::ROTATE_VIEW_RIGHT => self.rotate(-1.0),
+            input::CLEAR_TRAIL => self.trail.clear(),
+            input::SAVE_MAP => self.save_maps(),
+            input::CLEAR_MEMORY => {
+                for laser in self.listeners.lasers.iter() {
+                    laser.clear_memory();
+                }
+            }
             _ => return,
         }
     }
 
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        match event.kind {
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let steps = if event.kind == MouseEventKind::ScrollUp {
+                    1.0
+                } else {
+                    -1.0
+                };
+                if let Some(point) = self.terminal_to_world(
+                    self.x_bounds(),
+                    self.y_bounds(),
+                    event.column,
+                    event.row,
+                ) {
+                    self.zoom_around(point, steps);
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.drag_position = Some((event.column, event.row))
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some((column, row)) = self.drag_position {
+                    let previous =
+                        self.terminal_to_world(self.x_bounds(), self.y_bounds(), column, row);
+                    let current = self.terminal_to_world(
+                        self.x_bounds(),
+                        self.y_bounds(),
+                        event.column,
+                        event.row,
+                    );
+                    if let (Some(previous), Some(current)) = (previous, current) {
+                        // Moves the view so that the dragged point follows the mouse
+                        self.offset.0 -= current.0 - previous.0;
+                        self.offset.1 -= current.1 - previous.1;
+                    }
+                    self.drag_position = Some((event.column, event.row));
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) => self.drag_position = None,
+            _ => (),
+        }
+    }
+
     fn get_name(&self) -> String {
         "".to_string()
     }
@@ -155,148 +1595,234 @@ impl AppMode for Viewport {
                 input::ZOOM_OUT.to_string(),
                 "Decreases the zoom.".to_string(),
             ],
+            [
+                input::SHOW_LEGEND.to_string(),
+                "Shows or hides the legend of the drawn topics.".to_string(),
+            ],
+            [
+                input::SHOW_MINIMAP.to_string(),
+                "Shows or hides the overview of the maps.".to_string(),
+            ],
+            [
+                input::NEXT_PROJECTION.to_string(),
+                "Switches between the views from above, from the side along x and along y."
+                    .to_string(),
+            ],
+            [
+                input::CLEAR_TRAIL.to_string(),
+                "Clears the trail of the robot.".to_string(),
+            ],
+            [
+                input::CLEAR_MEMORY.to_string(),
+                "Clears the obstacles remembered from the laser scans.".to_string(),
+            ],
+            [
+                input::SAVE_MAP.to_string(),
+                "Saves the visible maps to PGM and YAML files.".to_string(),
+            ],
+            [
+                input::EXPORT.to_string(),
+                "Exports the view to an image file.".to_string(),
+            ],
+            [
+                input::RECORD.to_string(),
+                "Starts or stops recording the view to an animation.".to_string(),
+            ],
         ]
     }
 }
 
-impl UseViewport for Viewport {
-    fn x_bounds(&self) -> [f64; 2] {
-        let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
-        let res = self.tf_listener.clone().lookup_transform(
-            &self.static_frame,
-            &self.robot_frame,
-            rosrust::Time::new(),
-        );
-        match &res {
-            Ok(res) => res,
-            Err(_e) => {
-                return [
-                    self.initial_bounds[0] / self.zoom * scale_factor,
-                    self.initial_bounds[1] / self.zoom * scale_factor,
-                ]
-            }
-        };
-        let tf = res.as_ref().unwrap();
-
-        [
-            tf.transform.translation.x + self.initial_bounds[0] / self.zoom * scale_factor,
-            tf.transform.translation.x + self.initial_bounds[1] / self.zoom * scale_factor,
-        ]
-    }
-    fn y_bounds(&self) -> [f64; 2] {
-        let scale_factor = self.terminal_size.0 as f64 / self.terminal_size.1 as f64 * 0.5;
-        let res = self.tf_listener.clone().lookup_transform(
-            &self.static_frame,
-            &self.robot_frame,
-            rosrust::Time::new(),
-        );
-        match &res {
-            Ok(res) => res,
-            Err(_e) => {
-                return [
-                    self.initial_bounds[2] / self.zoom * scale_factor,
-                    self.initial_bounds[3] / self.zoom * scale_factor,
-                ]
-            }
-        };
-        let tf = res.as_ref().unwrap();
-        [
-            tf.transform.translation.y + self.initial_bounds[2] / self.zoom,
-            tf.transform.translation.y + self.initial_bounds[3] / self.zoom,
-        ]
+impl Viewport {
+    /// Returns the horizontal bounds of the window centered on the view.
+    pub fn x_bounds(&self) -> [f64; 2] {
+        self.x_bounds_around(self.center())
     }
 
-    fn info(&self) -> String {
-        "".to_string()
+    /// Returns the vertical bounds of the window centered on the view.
+    pub fn y_bounds(&self) -> [f64; 2] {
+        self.y_bounds_around(self.center())
     }
-    fn draw_in_viewport(&self, ctx: &mut Context) {
-        for map in &self.listeners.maps {
-            ctx.draw(&Points {
-                coords: &map.points.read().unwrap(),
-                color: Color::Rgb(map.config.color.r, map.config.color.g, map.config.color.b),
-            });
-        }
-
-        ctx.layer();
-        for pointcloud in &self.listeners.pointclouds {
-            let points = &pointcloud.points.read().unwrap().clone();
-            for pt in points {
-                ctx.draw(&Points {
-                    coords: &[(pt.point.x, pt.point.y)],
-                    color: pt.color,
-                })
-            }
-        }
 
-        ctx.layer();
-        for line in self.listeners.markers.get_lines() {
-            ctx.draw(&line);
-        }
+    /// Returns the information on the view, shown on the top bar after the one of the mode.
+    pub fn info(&self) -> String {
+        let following = if let Some(i) = self.current_bookmark {
+            Some(format!("Bookmark: {}", self.bookmarks[i].name))
+        } else if self.free_camera.is_some() {
+            Some("Free camera".to_string())
+        } else if self.followed_frame != 0 {
+            Some(format!(
+                "Following: {}",
+                self.follow_frames[self.followed_frame]
+            ))
+        } else {
+            None
+        };
+        following
+            .into_iter()
+            .chain(if self.robot_up {
+                Some("Robot up".to_string())
+            } else {
+                None
+            })
+            .chain(self.projection.name().map(|name| name.to_string()))
+            .chain(if self.view_angle != 0.0 {
+                Some(format!("Rotation: {:.0}°", self.view_angle.to_degrees()))
+            } else {
+                None
+            })
+            .chain(if self.grid.enabled && self.grid.spacing > 0.0 {
+                Some(format!("Grid: {}m", self.grid_spacing()))
+            } else {
+                None
+            })
+            .chain(
+                self.localization_score()
+                    .map(|score| format!("Localization: {:.0}%", score)),
+            )
+            .chain(self.listeners.imus.iter().filter_map(|imu| imu.info()))
+            .chain(
+                self.listeners
+                    .navsat_fixes
+                    .iter()
+                    .filter_map(|navsat_fix| navsat_fix.info()),
+            )
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
 
-        ctx.layer();
-        for laser in &self.listeners.lasers {
-            ctx.draw(&Points {
-                coords: &laser.points.read().unwrap(),
-                color: Color::Rgb(
-                    laser.config.color.r,
-                    laser.config.color.g,
-                    laser.config.color.b,
+    /// Returns the status widgets of the listeners, the recording and the last export.
+    pub fn status(&self) -> Vec<Span<'static>> {
+        self.listeners
+            .batteries
+            .iter()
+            .filter_map(|battery| battery.status())
+            .chain(
+                self.listeners
+                    .move_base_statuses
+                    .iter()
+                    .filter_map(|move_base| move_base.status()),
+            )
+            .chain(self.listeners.fields.iter().map(|field| field.status()))
+            .chain(self.recording.as_ref().map(|recording| {
+                Span::styled(
+                    format!("Recording {}s", recording.elapsed().as_secs()),
+                    Style::default().fg(self.theme.error),
+                )
+            }))
+            .chain(self.export_status.as_ref().map(|status| match status {
+                Ok(filename) => Span::styled(
+                    format!("Exported to {}", filename),
+                    Style::default().fg(self.theme.ok),
                 ),
-            });
-        }
-
-        ctx.layer();
-        let base_link_pose = self.tf_listener.lookup_transform(
-            &self.static_frame,
-            &self.robot_frame,
-            rosrust::Time::new(),
-        );
+                Err(e) => Span::styled(
+                    format!("Export failed: {}", e),
+                    Style::default().fg(self.theme.error),
+                ),
+            }))
+            .collect()
+    }
 
-        let robot_pose = if base_link_pose.is_ok() {
-            base_link_pose.unwrap().transform
+    /// Returns the color with which the canvas is filled, if any.
+    pub fn canvas_background(&self) -> Option<Color> {
+        if self.fill_background {
+            Some(self.background_color.to_tui())
         } else {
-            iso2d_to_ros(&Isometry2::identity())
-        };
-        get_current_footprint(&robot_pose, &self.footprint);
-
-        for elem in get_current_footprint(&robot_pose, &self.footprint) {
-            ctx.draw(&Line {
-                x1: elem.0,
-                y1: elem.1,
-                x2: elem.2,
-                y2: elem.3,
-                color: Color::Blue,
-            });
+            None
         }
+    }
 
-        for line in Viewport::get_frame_lines(&robot_pose, self.axis_length) {
-            ctx.draw(&line);
+    /// Returns the overview of the maps, if it is shown.
+    pub fn minimap(&self) -> Option<Minimap> {
+        if !self.minimap.enabled {
+            return None;
         }
-
-        for pose_stamped in &self.listeners.pose_stamped {
-            for line in pose_stamped.get_lines() {
-                ctx.draw(&line);
+        let mut extent: Option<([f64; 2], [f64; 2])> = None;
+        for map in self.listeners.maps.iter() {
+            if !self.is_visible(&map.config.topic) {
+                continue;
             }
-        }
-
-        for polygon in &self.listeners.polygons {
-            for line in polygon.get_lines() {
-                ctx.draw(&line);
+            if let Some(([x0, x1], [y0, y1])) = map.extent() {
+                let ([x_min, x_max], [y_min, y_max]) = extent.unwrap_or(([x0, x1], [y0, y1]));
+                extent = Some((
+                    [x_min.min(x0), x_max.max(x1)],
+                    [y_min.min(y0), y_max.max(y1)],
+                ));
             }
         }
+        Some(Minimap {
+            extent: extent?,
+            config: self.minimap.clone(),
+        })
+    }
 
-        for path in &self.listeners.paths {
-            for line in path.get_lines() {
-                ctx.draw(&line)
+    /// Draws the overview in the fixed frame, with the shown area and the robot.
+    pub fn draw_minimap(&self, ctx: &mut dyn ViewPainter) {
+        let bin_size = self.minimap().map_or(0.0, |minimap| {
+            let ([x_min, x_max], [y_min, y_max]) = minimap.extent;
+            (x_max - x_min).max(y_max - y_min)
+                / (self.minimap.width.max(1) as f64 * MAP_BINS_PER_CELL)
+        });
+        for map in self.listeners.maps.iter() {
+            if self.is_visible(&map.config.topic) {
+                for (points, color) in map.get_points(bin_size, None) {
+                    ctx.draw_points(&Points {
+                        coords: &points,
+                        color,
+                    });
+                }
             }
         }
-
-        for pose_array in &self.listeners.pose_array {
-            for line in pose_array.get_lines() {
-                ctx.draw(&line);
-            }
+        // The shown area, which is rotated with the view
+        let rotation = self.rotation();
+        let [x_min, x_max] = self.x_bounds();
+        let [y_min, y_max] = self.y_bounds();
+        let corners = [
+            (x_min, y_min),
+            (x_max, y_min),
+            (x_max, y_max),
+            (x_min, y_max),
+        ]
+        .map(|corner| rotation.invert(corner));
+        for i in 0..corners.len() {
+            let (start, end) = (corners[i], corners[(i + 1) % corners.len()]);
+            ctx.draw_line(&Line {
+                x1: start.0,
+                y1: start.1,
+                x2: end.0,
+                y2: end.1,
+                color: self.theme.highlight,
+            });
         }
+        let robot = self.robot_pose().translation;
+        ctx.draw_points(&Points {
+            coords: &[(robot.vector.x, robot.vector.y)],
+            color: self.footprint_color.to_tui(),
+        });
+    }
 
-        ctx.layer();
+    /// Returns the lines of the legend of the drawn topics, or nothing if it is hidden.
+    pub fn legend(&self) -> Vec<Spans<'static>> {
+        if !self.show_legend {
+            return vec![];
+        }
+        if self.listeners.legend.is_empty() {
+            return vec![Spans::from("No topic")];
+        }
+        self.listeners
+            .legend
+            .iter()
+            .map(|entry| {
+                // Hollow squares mark topics colored from their messages
+                let square = match &entry.color {
+                    Some(color) => Span::styled("■ ", Style::default().fg(color.to_tui())),
+                    None => Span::raw("□ "),
+                };
+                let description = match &entry.style {
+                    Some(style) => format!("{} ({}, {})", entry.topic, entry.kind, style),
+                    None => format!("{} ({})", entry.topic, entry.kind),
+                };
+                Spans::from(vec![square, Span::raw(description)])
+            })
+            .collect()
     }
 }
@@ -0,0 +1,255 @@
+//! Waypoint editor mode allows to place a sequence of poses in the viewport
+//! and to publish it as a nav_msgs::Path.
+
+use crate::app_modes::cursor::Cursor;
+use crate::app_modes::export;
+use crate::app_modes::viewport::{UseViewport, ViewPainter, Viewport};
+use crate::app_modes::{input, AppMode, BaseMode};
+use crate::config::WaypointEditorConfig;
+use crate::footprint::get_current_footprint;
+use crate::transformation;
+use nalgebra::Isometry2;
+use rosrust_msg::geometry_msgs::PoseStamped;
+use rosrust_msg::nav_msgs::Path;
+use std::cell::{Ref, RefCell};
+use std::rc::Rc;
+use tui::backend::Backend;
+use tui::style::Style;
+use tui::text::Span;
+use tui::widgets::canvas::Line;
+
+pub struct WaypointEditor {
+    viewport: Rc<RefCell<Viewport>>,
+    config: WaypointEditorConfig,
+    cursor: Cursor,
+    waypoints: Vec<Isometry2<f64>>,
+    selected: Option<usize>,
+    publisher: rosrust::Publisher<Path>,
+}
+
+impl WaypointEditor {
+    pub fn new(viewport: Rc<RefCell<Viewport>>, config: WaypointEditorConfig) -> WaypointEditor {
+        let robot_pose = viewport.borrow().robot_pose();
+        WaypointEditor {
+            viewport,
+            publisher: rosrust::publish(&config.topic, 1).unwrap(),
+            config,
+            cursor: Cursor::new(robot_pose, true),
+            waypoints: Vec::new(),
+            selected: None,
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = Some(index);
+        self.cursor.pose = self.waypoints[index];
+    }
+
+    fn select_offset(&mut self, offset: i64) {
+        if self.waypoints.is_empty() {
+            return;
+        }
+        let len = self.waypoints.len() as i64;
+        let index = match self.selected {
+            Some(i) => (i as i64 + offset).rem_euclid(len),
+            None if offset > 0 => 0,
+            None => len - 1,
+        };
+        self.select(index as usize);
+    }
+
+    /// Moves the selected waypoint forward or backward in the sequence.
+    fn move_selected(&mut self, offset: i64) {
+        if let Some(i) = self.selected {
+            let j = i as i64 + offset;
+            if j >= 0 && (j as usize) < self.waypoints.len() {
+                self.waypoints.swap(i, j as usize);
+                self.selected = Some(j as usize);
+            }
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        if let Some(i) = self.selected {
+            self.waypoints.remove(i);
+            self.selected = None;
+        }
+    }
+
+    fn publish(&self) {
+        let mut path = Path::default();
+        path.header.frame_id = self.viewport.borrow().static_frame.clone();
+        path.header.stamp = rosrust::now();
+        for waypoint in &self.waypoints {
+            let tf = transformation::iso2d_to_ros(waypoint);
+            let mut pose = PoseStamped::default();
+            pose.header = path.header.clone();
+            pose.pose.position.x = tf.translation.x;
+            pose.pose.position.y = tf.translation.y;
+            pose.pose.orientation = tf.rotation;
+            path.poses.push(pose);
+        }
+        self.publisher.send(path).unwrap();
+    }
+}
+
+impl<B: Backend> BaseMode<B> for WaypointEditor {}
+
+impl AppMode for WaypointEditor {
+    fn run(&mut self) {
+        export::record(self, &self.viewport);
+    }
+
+    fn reset(&mut self) {
+        self.selected = None;
+        if self.waypoints.is_empty() {
+            self.cursor.pose = self.viewport.borrow().robot_pose();
+        }
+    }
+
+    fn handle_input(&mut self, input: &String) {
+        self.viewport.borrow_mut().handle_input(input);
+        export::handle_input(self, &self.viewport, input);
+        if self.cursor.handle_input(input) {
+            return;
+        }
+        match input.as_str() {
+            input::CONFIRM => match self.selected {
+                Some(i) => self.waypoints[i] = self.cursor.pose,
+                None => self.waypoints.push(self.cursor.pose),
+            },
+            input::NEXT => self.select_offset(1),
+            input::PREVIOUS => self.select_offset(-1),
+            input::MOVE_FORWARD => self.move_selected(1),
+            input::MOVE_BACKWARD => self.move_selected(-1),
+            input::DELETE => self.delete_selected(),
+            input::CANCEL => self.selected = None,
+            input::PUBLISH => self.publish(),
+            _ => (),
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "Waypoint Editor".to_string()
+    }
+
+    fn get_description(&self) -> Vec<String> {
+        vec![
+            "This mode allows to place a sequence of waypoints and to publish it as a path."
+                .to_string(),
+            "Waypoints are appended at the cursor, or the selected one is moved to the cursor."
+                .to_string(),
+        ]
+    }
+
+    fn get_keymap(&self) -> Vec<[String; 2]> {
+        let mut keymap = self.cursor.get_keymap();
+        keymap.extend([
+            [
+                input::CONFIRM.to_string(),
+                "Appends a waypoint at the cursor, or moves the selected one there.".to_string(),
+            ],
+            [
+                input::NEXT.to_string(),
+                "Selects the next waypoint.".to_string(),
+            ],
+            [
+                input::PREVIOUS.to_string(),
+                "Selects the previous waypoint.".to_string(),
+            ],
+            [
+                input::MOVE_FORWARD.to_string(),
+                "Moves the selected waypoint later in the sequence.".to_string(),
+            ],
+            [
+                input::MOVE_BACKWARD.to_string(),
+                "Moves the selected waypoint earlier in the sequence.".to_string(),
+            ],
+            [
+                input::DELETE.to_string(),
+                "Deletes the selected waypoint.".to_string(),
+            ],
+            [
+                input::CANCEL.to_string(),
+                "Deselects the waypoint.".to_string(),
+            ],
+            [
+                input::PUBLISH.to_string(),
+                "Publishes the waypoints as a path.".to_string(),
+            ],
+        ]);
+        keymap.extend(self.viewport.borrow().get_keymap());
+        keymap
+    }
+}
+
+impl UseViewport for WaypointEditor {
+    fn viewport(&self) -> Ref<Viewport> {
+        self.viewport.borrow()
+    }
+
+    fn draw_in_viewport(&self, ctx: &mut dyn ViewPainter) {
+        let viewport = self.viewport.borrow();
+        viewport.draw_grid(ctx, self.x_bounds(), self.y_bounds());
+        viewport.draw_layers(ctx, self.x_bounds(), self.y_bounds());
+        let rotation = viewport.rotation();
+        for pair in self.waypoints.windows(2) {
+            ctx.draw_line(&rotation.line(&Line {
+                x1: pair[0].translation.x,
+                y1: pair[0].translation.y,
+                x2: pair[1].translation.x,
+                y2: pair[1].translation.y,
+                color: viewport.theme.inactive,
+            }));
+        }
+        for (i, waypoint) in self.waypoints.iter().enumerate() {
+            let color = if self.selected == Some(i) {
+                viewport.theme.highlight
+            } else {
+                viewport.theme.text
+            };
+            let pose = transformation::iso2d_to_ros(waypoint);
+            for elem in &get_current_footprint(&pose, &viewport.footprint) {
+                ctx.draw_line(&rotation.line(&Line {
+                    x1: elem.0,
+                    y1: elem.1,
+                    x2: elem.2,
+                    y2: elem.3,
+                    color,
+                }));
+            }
+            let (x, y) = rotation.apply((waypoint.translation.x, waypoint.translation.y));
+            ctx.print(
+                x,
+                y,
+                Span::styled((i + 1).to_string(), Style::default().fg(color)),
+            );
+        }
+        self.cursor.draw(ctx, &viewport, viewport.theme.accent);
+    }
+
+    fn x_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .x_bounds_around(self.cursor.position())
+    }
+
+    fn y_bounds(&self) -> [f64; 2] {
+        self.viewport
+            .borrow()
+            .y_bounds_around(self.cursor.position())
+    }
+
+    fn info(&self) -> String {
+        let mut info = format!(
+            "Topic: /{}, Waypoints: {}, Cursor step: {:.2}",
+            self.config.topic,
+            self.waypoints.len(),
+            self.cursor.step
+        );
+        if let Some(i) = self.selected {
+            info += &format!(", Selected: {}", i + 1);
+        }
+        info
+    }
+}
@@ -0,0 +1,65 @@
+use crate::config::BatteryListenerConfig;
+use crate::source;
+use std::sync::{Arc, RwLock};
+use tui::style::{Color, Style};
+use tui::text::Span;
+
+use rosrust_msg::sensor_msgs::BatteryState;
+
+/// Charge (percentage) and voltage of the last received battery state.
+struct BatteryData {
+    percentage: f32,
+    voltage: f32,
+}
+
+pub struct BatteryListener {
+    pub config: BatteryListenerConfig,
+    data: Arc<RwLock<Option<BatteryData>>>,
+    _subscriber: source::Subscriber,
+}
+
+impl BatteryListener {
+    pub fn new(config: BatteryListenerConfig) -> BatteryListener {
+        let data = Arc::new(RwLock::new(None));
+        let cb_data = data.clone();
+
+        let battery_sub = source::subscribe(&config.topic, 1, move |msg: BatteryState| {
+            *cb_data.write().unwrap() = Some(BatteryData {
+                percentage: msg.percentage,
+                voltage: msg.voltage,
+            });
+        })
+        .unwrap();
+
+        BatteryListener {
+            config,
+            data,
+            _subscriber: battery_sub,
+        }
+    }
+
+    /// Returns the charge and voltage of the battery, colored according to the thresholds.
+    /// Nothing is shown until the first message is received.
+    pub fn status(&self) -> Option<Span<'static>> {
+        let data = self.data.read().unwrap();
+        let data = data.as_ref()?;
+        // Unmeasured values are set to NaN
+        let mut text = "Battery:".to_string();
+        if !data.percentage.is_nan() {
+            text += &format!(" {:.0}%", data.percentage * 100.0);
+        }
+        if !data.voltage.is_nan() {
+            text += &format!(" {:.1}V", data.voltage);
+        }
+        let color = if data.percentage.is_nan() {
+            Color::Reset
+        } else if (data.percentage as f64) < self.config.critical_threshold {
+            Color::Red
+        } else if (data.percentage as f64) < self.config.warning_threshold {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+        Some(Span::styled(text, Style::default().fg(color)))
+    }
+}
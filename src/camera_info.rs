@@ -0,0 +1,111 @@
+use crate::config::CameraInfoListenerConfig;
+use crate::source;
+use crate::transformation::ros_transform_to_isometry;
+use nalgebra::geometry::Point3;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+/// Returns the corners of the image plane at the given distance from the camera,
+/// in the optical frame (z forward, x right, y down).
+fn get_frustum_corners(
+    camera_info: &rosrust_msg::sensor_msgs::CameraInfo,
+    range: f64,
+) -> Option<Vec<Point3<f64>>> {
+    let (fx, cx, fy, cy) = (
+        camera_info.K[0],
+        camera_info.K[2],
+        camera_info.K[4],
+        camera_info.K[5],
+    );
+    // An uncalibrated camera has a zero intrinsic matrix
+    if fx == 0.0 || fy == 0.0 {
+        return None;
+    }
+    let (width, height) = (camera_info.width as f64, camera_info.height as f64);
+    Some(
+        [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+            .iter()
+            .map(|(u, v)| Point3::new((u - cx) / fx * range, (v - cy) / fy * range, range))
+            .collect(),
+    )
+}
+
+pub struct CameraInfoListener {
+    pub config: CameraInfoListenerConfig,
+    pub lines: Arc<RwLock<Vec<Line>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl CameraInfoListener {
+    pub fn new(
+        config: CameraInfoListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> CameraInfoListener {
+        let frustum_lines = Arc::new(RwLock::new(Vec::<Line>::new()));
+        let cb_frustum_lines = frustum_lines.clone();
+        let str_ = static_frame.clone();
+        let color = config.color.to_tui();
+        let range = config.range;
+
+        let local_listener = tf_listener.clone();
+        let camera_info_sub = source::subscribe(
+            &config.topic,
+            1,
+            move |camera_info: rosrust_msg::sensor_msgs::CameraInfo| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &camera_info.header.frame_id,
+                    camera_info.header.stamp,
+                );
+                let transform = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                let corners: Vec<Point3<f64>> = match get_frustum_corners(&camera_info, range) {
+                    Some(corners) => corners
+                        .iter()
+                        .map(|corner| transform.transform_point(corner))
+                        .collect(),
+                    None => return,
+                };
+                let origin = transform.transform_point(&Point3::new(0.0, 0.0, 0.0));
+                let mut lines: Vec<Line> = Vec::new();
+                for (i, corner) in corners.iter().enumerate() {
+                    let next = &corners[(i + 1) % corners.len()];
+                    lines.push(Line {
+                        x1: origin.x,
+                        y1: origin.y,
+                        x2: corner.x,
+                        y2: corner.y,
+                        color,
+                    });
+                    lines.push(Line {
+                        x1: corner.x,
+                        y1: corner.y,
+                        x2: next.x,
+                        y2: next.y,
+                        color,
+                    });
+                }
+                let mut cb_frustum_lines = cb_frustum_lines.write().unwrap();
+                *cb_frustum_lines = lines;
+            },
+        )
+        .unwrap();
+
+        CameraInfoListener {
+            config,
+            lines: frustum_lines,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: camera_info_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        self.lines.read().unwrap().to_vec()
+    }
+}
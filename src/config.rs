@@ -11,14 +11,74 @@ fn default_int() -> i64 {
     0
 }
 
-fn default_map_threshold() -> i8 {
+fn default_map_min() -> i8 {
     1
 }
 
+fn default_map_max() -> i8 {
+    100
+}
+
+fn default_true() -> bool {
+    true
+}
+
 fn default_pose_length() -> f64 {
     0.2
 }
 
+fn default_point_size() -> f64 {
+    0.1
+}
+
+fn default_acceleration_scale() -> f64 {
+    0.1
+}
+
+fn default_vector_scale() -> f64 {
+    1.0
+}
+
+fn default_frustum_range() -> f64 {
+    1.0
+}
+
+fn default_battery_warning_threshold() -> f64 {
+    0.3
+}
+
+fn default_battery_critical_threshold() -> f64 {
+    0.15
+}
+
+fn default_teleop_mode() -> String {
+    "Twist".to_string()
+}
+
+fn default_steering_increment() -> f64 {
+    0.05
+}
+
+fn default_deadman_timeout() -> f64 {
+    0.5
+}
+
+fn default_stop_distance() -> f64 {
+    0.2
+}
+
+fn default_slowdown_distance() -> f64 {
+    1.0
+}
+
+fn default_field_precision() -> usize {
+    2
+}
+
+fn default_history_length() -> usize {
+    1000
+}
+
 fn color_white() -> Color {
     Color {
         r: 255,
@@ -27,10 +87,53 @@ fn color_white() -> Color {
     }
 }
 
+fn color_black() -> Color {
+    Color { r: 0, g: 0, b: 0 }
+}
+
+fn default_theme_preset() -> String {
+    "dark".to_string()
+}
+
+fn default_canvas_marker() -> String {
+    "braille".to_string()
+}
+
 fn color_red() -> Color {
     Color { r: 255, g: 0, b: 0 }
 }
 
+fn color_green() -> Color {
+    Color { r: 0, g: 255, b: 0 }
+}
+
+fn color_blue() -> Color {
+    Color { r: 0, g: 0, b: 255 }
+}
+
+pub fn default_class_colors() -> Vec<Color> {
+    vec![
+        Color { r: 255, g: 0, b: 0 },
+        Color { r: 0, g: 255, b: 0 },
+        Color { r: 0, g: 0, b: 255 },
+        Color {
+            r: 255,
+            g: 255,
+            b: 0,
+        },
+        Color {
+            r: 0,
+            g: 255,
+            b: 255,
+        },
+        Color {
+            r: 255,
+            g: 0,
+            b: 255,
+        },
+    ]
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Color {
     pub r: u8,
@@ -42,11 +145,44 @@ impl Color {
     pub fn to_tui(&self) -> TuiColor {
         return TuiColor::Rgb(self.r, self.g, self.b);
     }
+
+    /// Blends the color toward the background color, fully when the fraction is 1.
+    pub fn fade(&self, background: &Color, fraction: f64) -> TuiColor {
+        let fraction = fraction.max(0.0).min(1.0);
+        let blend = |c: u8, bg: u8| (c as f64 + (bg as f64 - c as f64) * fraction) as u8;
+        TuiColor::Rgb(
+            blend(self.r, background.r),
+            blend(self.g, background.g),
+            blend(self.b, background.b),
+        )
+    }
+}
+
+/// Heights in the fixed frame between which 3D data is drawn, unbounded if not given.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct ZRange {
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl ZRange {
+    pub fn contains(&self, z: f64) -> bool {
+        self.min.map_or(true, |min| z >= min) && self.max.map_or(true, |max| z <= max)
+    }
+
+    pub fn describe(&self) -> String {
+        let bound = |bound: Option<f64>| bound.map_or("-".to_string(), |z| format!("{:.2}", z));
+        format!("z: {} to {}", bound(self.min), bound(self.max))
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListenerConfig {
     pub topic: String,
+    #[serde(default)]
+    pub z_range: ZRange,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,6 +190,48 @@ pub struct PointCloud2ListenerConfig {
     pub topic: String,
     #[serde(default = "bool::default")]
     pub use_rgb: bool,
+    /// Coordinate mapped to the gradient: "x", "y" or "z" in the static frame, or "distance"
+    /// to the sensor.
+    #[serde(default = "default_color_axis")]
+    pub color_axis: String,
+    /// Field of the points mapped to the gradient instead of the color axis, e.g. "intensity",
+    /// "ring" or "label".
+    #[serde(default)]
+    pub color_field: Option<String>,
+    /// Value mapped to the start of the gradient, the minimum of each cloud if unset.
+    #[serde(default)]
+    pub color_min: Option<f64>,
+    /// Value mapped to the end of the gradient, the maximum of each cloud if unset.
+    #[serde(default)]
+    pub color_max: Option<f64>,
+    /// Name of the gradient through which the points are colorized, e.g. "turbo" or "viridis".
+    #[serde(default = "default_gradient")]
+    pub gradient: String,
+    #[serde(default)]
+    pub z_range: ZRange,
+    /// Seconds during which the points of past clouds stay visible, only the last cloud is
+    /// drawn if 0.
+    #[serde(default)]
+    pub accumulation_time: f64,
+    /// Only every n-th point of each cloud is kept.
+    #[serde(default = "default_decimation")]
+    pub decimation: usize,
+    /// Size in meters of the voxels of the grid in which only one point is kept, no
+    /// downsampling if 0.
+    #[serde(default)]
+    pub voxel_size: f64,
+}
+
+fn default_color_axis() -> String {
+    "z".to_string()
+}
+
+fn default_gradient() -> String {
+    "turbo".to_string()
+}
+
+fn default_decimation() -> usize {
+    1
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -66,11 +244,118 @@ pub struct PoseListenerConfig {
     pub length: f64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PointListenerConfig {
+    pub topic: String,
+    #[serde(default = "color_red")]
+    pub color: Color,
+    #[serde(default = "default_point_size")]
+    pub size: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OdometryListenerConfig {
+    pub topic: String,
+    pub style: String,
+    #[serde(default = "color_blue")]
+    pub color: Color,
+    #[serde(default = "default_pose_length")]
+    pub length: f64,
+    #[serde(default = "bool::default")]
+    pub show_twist: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VectorListenerConfig {
+    pub topic: String,
+    #[serde(default = "color_red")]
+    pub color: Color,
+    #[serde(default = "default_vector_scale")]
+    pub scale: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatteryListenerConfig {
+    pub topic: String,
+    #[serde(default = "default_battery_warning_threshold")]
+    pub warning_threshold: f64,
+    #[serde(default = "default_battery_critical_threshold")]
+    pub critical_threshold: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldListenerConfig {
+    pub topic: String,
+    pub field: String,
+    #[serde(default)]
+    pub msg_type: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(default = "default_field_precision")]
+    pub precision: usize,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MoveBaseStatusListenerConfig {
+    pub status_topic: String,
+    pub plan_topic: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CameraInfoListenerConfig {
+    pub topic: String,
+    #[serde(default = "color_white")]
+    pub color: Color,
+    #[serde(default = "default_frustum_range")]
+    pub range: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DetectionListenerConfig {
+    pub topic: String,
+    #[serde(default = "default_class_colors")]
+    pub colors: Vec<Color>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImuListenerConfig {
+    pub topic: String,
+    #[serde(default = "default_pose_length")]
+    pub length: f64,
+    #[serde(default = "bool::default")]
+    pub show_acceleration: bool,
+    #[serde(default = "default_acceleration_scale")]
+    pub acceleration_scale: f64,
+    #[serde(default = "color_red")]
+    pub color: Color,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NavSatFixListenerConfig {
+    pub topic: String,
+    #[serde(default)]
+    pub origin_latitude: Option<f64>,
+    #[serde(default)]
+    pub origin_longitude: Option<f64>,
+    #[serde(default = "default_history_length")]
+    pub history_length: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ImageListenerConfig {
     pub topic: String,
     #[serde(default = "default_int")]
     pub rotation: i64,
+    #[serde(default)]
+    pub detections_topic: Option<String>,
+    #[serde(default = "default_class_colors")]
+    pub detection_colors: Vec<Color>,
+    #[serde(default = "bool::default")]
+    pub compressed: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -79,19 +364,282 @@ pub struct SendPoseConfig {
     pub msg_type: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GridConfig {
+    pub enabled: bool,
+    pub spacing: f64,
+    pub color: Color,
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        GridConfig {
+            enabled: false,
+            spacing: 1.0,
+            color: Color {
+                r: 60,
+                g: 60,
+                b: 60,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThemeConfig {
+    /// Built-in theme: dark, light, solarized or colorblind.
+    #[serde(default = "default_theme_preset")]
+    pub preset: String,
+    #[serde(default)]
+    pub title: Option<Color>,
+    #[serde(default)]
+    pub text: Option<Color>,
+    #[serde(default)]
+    pub highlight: Option<Color>,
+    #[serde(default)]
+    pub accent: Option<Color>,
+    #[serde(default)]
+    pub ok: Option<Color>,
+    #[serde(default)]
+    pub warning: Option<Color>,
+    #[serde(default)]
+    pub error: Option<Color>,
+    #[serde(default)]
+    pub inactive: Option<Color>,
+    #[serde(default)]
+    pub layer_colors: Vec<Color>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            preset: default_theme_preset(),
+            title: None,
+            text: None,
+            highlight: None,
+            accent: None,
+            ok: None,
+            warning: None,
+            error: None,
+            inactive: None,
+            layer_colors: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TrailConfig {
+    pub enabled: bool,
+    /// Length of the trail in meters, unlimited if 0.
+    pub max_length: f64,
+    /// Age of the oldest pose of the trail in seconds, unlimited if 0.
+    pub max_age: f64,
+    pub color: Color,
+}
+
+impl Default for TrailConfig {
+    fn default() -> Self {
+        TrailConfig {
+            enabled: false,
+            max_length: 20.0,
+            max_age: 0.0,
+            color: Color {
+                r: 0,
+                g: 128,
+                b: 255,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LocalizationQualityConfig {
+    pub enabled: bool,
+    /// Laser topic matched against the map, the first one if unset.
+    pub laser_topic: Option<String>,
+    /// Map topic against which the scan is matched, the first one if unset.
+    pub map_topic: Option<String>,
+    /// Distance in meters to the closest occupied cell from which a point is not matched.
+    pub max_distance: f64,
+    pub matched_color: Color,
+    pub unmatched_color: Color,
+}
+
+impl Default for LocalizationQualityConfig {
+    fn default() -> Self {
+        LocalizationQualityConfig {
+            enabled: false,
+            laser_topic: None,
+            map_topic: None,
+            max_distance: 0.2,
+            matched_color: Color { r: 0, g: 255, b: 0 },
+            unmatched_color: Color { r: 255, g: 0, b: 0 },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MinimapConfig {
+    pub enabled: bool,
+    /// Size of the inset in terminal cells, including its border.
+    pub width: u16,
+    pub height: u16,
+    /// Corner of the viewport where the inset is shown: "top_left", "top_right", "bottom_left"
+    /// or "bottom_right".
+    pub corner: String,
+}
+
+impl Default for MinimapConfig {
+    fn default() -> Self {
+        MinimapConfig {
+            enabled: false,
+            width: 30,
+            height: 10,
+            corner: "bottom_right".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportConfig {
+    /// Format of the exported views, "png" or "svg".
+    pub format: String,
+    /// Number of pixels per braille dot of the canvas in each direction.
+    pub scale: u16,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        ExportConfig {
+            format: "png".to_string(),
+            scale: 4,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordConfig {
+    /// Format of the recordings, "gif" or "png" for a sequence of numbered files.
+    pub format: String,
+    /// Duration after which the recording stops by itself, in seconds.
+    pub duration: f64,
+    /// Number of pixels per braille dot of the canvas in each direction.
+    pub scale: u16,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        RecordConfig {
+            format: "gif".to_string(),
+            duration: 10.0,
+            scale: 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BookmarkConfig {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+}
+
+/// Named pose of the fixed frame where the desired pose of the Send Pose mode can be placed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PosePresetConfig {
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    /// Yaw in degrees.
+    #[serde(default)]
+    pub yaw: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoalQueueConfig {
+    pub auto_dispatch: bool,
+    pub tolerance: f64,
+}
+
+impl Default for GoalQueueConfig {
+    fn default() -> Self {
+        GoalQueueConfig {
+            auto_dispatch: true,
+            tolerance: 0.3,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ListenerConfigColor {
     pub topic: String,
     pub color: Color,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LaserListenerConfig {
+    pub topic: String,
+    pub color: Color,
+    /// Seconds during which the points of past scans stay visible while fading out,
+    /// only the last scan is drawn if 0.
+    #[serde(default)]
+    pub decay_time: f64,
+    /// Range beyond which the returns are dropped, none are if unset.
+    #[serde(default)]
+    pub range_max_override: Option<f64>,
+    /// Only every n-th return of each scan is drawn.
+    #[serde(default = "default_stride")]
+    pub stride: usize,
+    /// Seconds during which the cells of the local obstacle memory are kept since they were
+    /// last hit, no memory if 0.
+    #[serde(default)]
+    pub memory_time: f64,
+    /// Size of the cells of the local obstacle memory in meters.
+    #[serde(default = "default_memory_resolution")]
+    pub memory_resolution: f64,
+    /// Distance to the sensor beyond which the cells of the local obstacle memory are dropped.
+    #[serde(default = "default_memory_radius")]
+    pub memory_radius: f64,
+}
+
+fn default_stride() -> usize {
+    1
+}
+
+fn default_memory_resolution() -> f64 {
+    0.05
+}
+
+fn default_memory_radius() -> f64 {
+    5.0
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MapListenerConfig {
     pub topic: String,
     #[serde(default = "color_white")]
     pub color: Color,
-    #[serde(default = "default_map_threshold")]
-    pub threshold: i8,
+    /// Lowest value of the cells drawn. Also read from the former threshold option.
+    #[serde(default = "default_map_min", alias = "threshold")]
+    pub min: i8,
+    /// Highest value of the cells drawn.
+    #[serde(default = "default_map_max")]
+    pub max: i8,
+    /// Maps with a higher z_order are drawn over the ones with a lower one.
+    #[serde(default)]
+    pub z_order: i32,
+    /// Topic of a base map: the cells are only drawn where the base map is free.
+    #[serde(default)]
+    pub only_where_free: Option<String>,
+    /// If true, the cells are colored by their value through the colormap instead of the color.
+    #[serde(default)]
+    pub gradient: bool,
+    /// Name of the colormap of the gradient, as for the point clouds.
+    #[serde(default = "default_gradient")]
+    pub colormap: String,
+    #[serde(default = "default_true")]
+    pub subscribe_to_updates: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -99,7 +647,59 @@ pub struct TeleopConfig {
     pub default_increment: f64,
     pub increment_step: f64,
     pub cmd_vel_topic: String,
+    /// Other topics on which the commands can be published instead, switched at runtime.
+    #[serde(default)]
+    pub cmd_vel_topics: Vec<String>,
     pub publish_cmd_vel_when_idle: bool,
+    #[serde(default = "default_teleop_mode")]
+    pub mode: String,
+    #[serde(default = "default_steering_increment")]
+    pub default_steering_increment: f64,
+    #[serde(default = "default_steering_increment")]
+    pub steering_increment_step: f64,
+    /// nav_msgs::Odometry topic of which the velocities are shown next to the commanded ones.
+    #[serde(default)]
+    pub odom_topic: Option<String>,
+    /// Time over which the trajectory at the commanded velocities is drawn, in seconds.
+    #[serde(default)]
+    pub preview_time: f64,
+    /// Distance between the axles, in meters, to predict the trajectory of the Ackermann mode.
+    #[serde(default)]
+    pub wheelbase: f64,
+    /// If true, the commands are slowed down and stopped before the obstacles seen by the
+    /// lasers in the way of the robot.
+    #[serde(default)]
+    pub collision_guard: bool,
+    /// Distance from the footprint to an obstacle at which the robot is stopped, in meters.
+    #[serde(default = "default_stop_distance")]
+    pub stop_distance: f64,
+    /// Distance from the footprint to an obstacle below which the robot is slowed down.
+    #[serde(default = "default_slowdown_distance")]
+    pub slowdown_distance: f64,
+    /// Frame of the TwistStamped commands. The robot frame if not set.
+    #[serde(default)]
+    pub frame_id: Option<String>,
+    /// If true, the velocities are only published while a movement key is held.
+    #[serde(default)]
+    pub deadman: bool,
+    /// Time after the last movement key after which the robot is stopped, in seconds.
+    #[serde(default = "default_deadman_timeout")]
+    pub deadman_timeout: f64,
+    /// Highest linear acceleration of the published velocities, in m/s². 0 to not limit it.
+    #[serde(default)]
+    pub max_linear_acceleration: f64,
+    /// Highest angular acceleration of the published velocities, in rad/s². 0 to not limit it.
+    #[serde(default)]
+    pub max_angular_acceleration: f64,
+    /// Highest absolute velocity along x, in m/s, also the speed of the Ackermann mode.
+    #[serde(default)]
+    pub max_vel_x: Option<f64>,
+    /// Highest absolute velocity along y, in m/s.
+    #[serde(default)]
+    pub max_vel_y: Option<f64>,
+    /// Highest absolute rotation velocity, in rad/s.
+    #[serde(default)]
+    pub max_vel_theta: Option<f64>,
 }
 
 impl Default for TeleopConfig {
@@ -108,7 +708,268 @@ impl Default for TeleopConfig {
             default_increment: 0.1,
             increment_step: 0.1,
             cmd_vel_topic: "cmd_vel".to_string(),
+            cmd_vel_topics: Vec::new(),
             publish_cmd_vel_when_idle: true,
+            mode: default_teleop_mode(),
+            default_steering_increment: 0.05,
+            steering_increment_step: 0.05,
+            odom_topic: None,
+            preview_time: 0.0,
+            wheelbase: 0.0,
+            collision_guard: false,
+            stop_distance: default_stop_distance(),
+            slowdown_distance: default_slowdown_distance(),
+            frame_id: None,
+            deadman: false,
+            deadman_timeout: default_deadman_timeout(),
+            max_linear_acceleration: 0.0,
+            max_angular_acceleration: 0.0,
+            max_vel_x: None,
+            max_vel_y: None,
+            max_vel_theta: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JointTeleopConfig {
+    pub joint_states_topic: String,
+    pub command_topic: String,
+    pub command_type: String,
+    pub default_increment: f64,
+    pub increment_step: f64,
+    pub trajectory_duration: f64,
+    /// Joints of the controller, listed in the trajectories.
+    #[serde(default)]
+    pub controlled_joints: Vec<String>,
+}
+
+impl Default for JointTeleopConfig {
+    fn default() -> JointTeleopConfig {
+        JointTeleopConfig {
+            joint_states_topic: "joint_states".to_string(),
+            command_topic: "{joint}_position_controller/command".to_string(),
+            command_type: "Float64".to_string(),
+            default_increment: 0.05,
+            increment_step: 0.01,
+            trajectory_duration: 0.5,
+            controlled_joints: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LogsConfig {
+    pub topic: String,
+    pub buffer_size: usize,
+    pub min_loglevel: String,
+}
+
+impl Default for LogsConfig {
+    fn default() -> LogsConfig {
+        LogsConfig {
+            topic: "rosout_agg".to_string(),
+            buffer_size: 1000,
+            min_loglevel: "DEBUG".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BagPlaybackConfig {
+    pub bags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaypointEditorConfig {
+    pub topic: String,
+}
+
+impl Default for WaypointEditorConfig {
+    fn default() -> Self {
+        WaypointEditorConfig {
+            topic: "waypoints".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolygonEditorConfig {
+    pub topic: String,
+}
+
+impl Default for PolygonEditorConfig {
+    fn default() -> Self {
+        PolygonEditorConfig {
+            topic: "polygon".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MapEditorConfig {
+    /// Topic of the edited map, the first one of the map topics if unset.
+    pub map_topic: Option<String>,
+    /// Topic on which the edited map is published.
+    pub topic: String,
+    /// Radius in meters around the cursor of the painted cells, only the cell under the cursor
+    /// if 0.
+    pub brush_radius: f64,
+}
+
+impl Default for MapEditorConfig {
+    fn default() -> Self {
+        MapEditorConfig {
+            map_topic: None,
+            topic: "map_edited".to_string(),
+            brush_radius: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SplitViewConfig {
+    /// Share of the terminal width taken by the viewport, in percent.
+    pub viewport_percentage: u16,
+}
+
+impl Default for SplitViewConfig {
+    fn default() -> Self {
+        SplitViewConfig {
+            viewport_percentage: 50,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaneConfig {
+    pub name: String,
+    #[serde(default = "default_pane_zoom")]
+    pub zoom: f64,
+    /// Frame followed by the view, among the follow_frames. The robot frame if not set.
+    #[serde(default)]
+    pub follow_frame: Option<String>,
+    #[serde(default)]
+    pub robot_up: bool,
+    #[serde(default)]
+    pub hidden_layers: Vec<String>,
+}
+
+fn default_pane_zoom() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultiViewConfig {
+    /// Views shown side by side, from left to right.
+    pub panes: Vec<PaneConfig>,
+}
+
+impl Default for MultiViewConfig {
+    fn default() -> Self {
+        MultiViewConfig {
+            panes: vec![
+                PaneConfig {
+                    name: "Overview".to_string(),
+                    zoom: 0.25,
+                    follow_frame: None,
+                    robot_up: false,
+                    hidden_layers: vec![],
+                },
+                PaneConfig {
+                    name: "Close-up".to_string(),
+                    zoom: 2.0,
+                    follow_frame: None,
+                    robot_up: true,
+                    hidden_layers: vec![],
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DashboardWidgetConfig {
+    /// What the widget shows: "viewport", "battery", "fields", "move_base", "transform" or "logs".
+    pub kind: String,
+    /// Title of the widget, the kind if not set.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Frame whose pose is shown by a transform widget, the robot frame if not set.
+    #[serde(default)]
+    pub source_frame: Option<String>,
+    /// Frame in which the pose is expressed, the fixed frame if not set.
+    #[serde(default)]
+    pub target_frame: Option<String>,
+    /// Number of lines shown by a logs widget.
+    #[serde(default = "default_dashboard_lines")]
+    pub lines: usize,
+}
+
+fn default_dashboard_lines() -> usize {
+    10
+}
+
+impl DashboardWidgetConfig {
+    fn new(kind: &str) -> DashboardWidgetConfig {
+        DashboardWidgetConfig {
+            kind: kind.to_string(),
+            title: None,
+            source_frame: None,
+            target_frame: None,
+            lines: default_dashboard_lines(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DashboardConfig {
+    /// Number of widgets in each row of the grid.
+    pub columns: u16,
+    /// Widgets filling the grid row by row.
+    pub widgets: Vec<DashboardWidgetConfig>,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        DashboardConfig {
+            columns: 2,
+            widgets: vec![
+                DashboardWidgetConfig::new("viewport"),
+                DashboardWidgetConfig::new("transform"),
+                DashboardWidgetConfig::new("battery"),
+                DashboardWidgetConfig::new("fields"),
+                DashboardWidgetConfig::new("move_base"),
+                DashboardWidgetConfig::new("logs"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicMonitorConfig {
+    pub topics: Vec<String>,
+    pub window: f64,
+}
+
+impl Default for TopicMonitorConfig {
+    fn default() -> TopicMonitorConfig {
+        TopicMonitorConfig {
+            topics: Vec::new(),
+            window: 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DiagnosticsConfig {
+    pub topic: String,
+}
+
+impl Default for DiagnosticsConfig {
+    fn default() -> DiagnosticsConfig {
+        DiagnosticsConfig {
+            topic: "diagnostics".to_string(),
         }
     }
 }
@@ -117,23 +978,118 @@ impl Default for TeleopConfig {
 pub struct TermvizConfig {
     pub fixed_frame: String,
     pub robot_frame: String,
+    #[serde(default)]
+    pub follow_frames: Vec<String>,
+    #[serde(default)]
+    pub bookmarks: Vec<BookmarkConfig>,
+    #[serde(default)]
+    pub grid: GridConfig,
+    #[serde(default)]
+    pub trail: TrailConfig,
+    #[serde(default)]
+    pub minimap: MinimapConfig,
+    #[serde(default)]
+    pub localization_quality: LocalizationQualityConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub record: RecordConfig,
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    #[serde(default = "default_canvas_marker")]
+    pub canvas_marker: String,
+    #[serde(default)]
+    pub mode_canvas_markers: HashMap<String, String>,
+    #[serde(default)]
+    pub robot_up: bool,
+    #[serde(default)]
+    pub view_rotation: f64,
+    #[serde(default)]
+    pub draw_order: Vec<String>,
+    #[serde(default = "color_black")]
+    pub background_color: Color,
+    #[serde(default)]
+    pub fill_background: bool,
+    #[serde(default = "color_blue")]
+    pub footprint_color: Color,
+    #[serde(default = "color_red")]
+    pub x_axis_color: Color,
+    #[serde(default = "color_green")]
+    pub y_axis_color: Color,
+    #[serde(default)]
+    pub battery_topics: Vec<BatteryListenerConfig>,
+    #[serde(default)]
+    pub camera_info_topics: Vec<CameraInfoListenerConfig>,
+    #[serde(default)]
+    pub detection3d_topics: Vec<DetectionListenerConfig>,
+    #[serde(default)]
+    pub field_topics: Vec<FieldListenerConfig>,
     pub map_topics: Vec<MapListenerConfig>,
-    pub laser_topics: Vec<ListenerConfigColor>,
+    #[serde(default)]
+    pub grid_cells_topics: Vec<ListenerConfigColor>,
+    pub laser_topics: Vec<LaserListenerConfig>,
     pub marker_topics: Vec<ListenerConfig>,
     pub image_topics: Vec<ImageListenerConfig>,
+    #[serde(default)]
+    pub imu_topics: Vec<ImuListenerConfig>,
     pub marker_array_topics: Vec<ListenerConfig>,
+    #[serde(default)]
+    pub move_base_status_topics: Vec<MoveBaseStatusListenerConfig>,
+    #[serde(default)]
+    pub navsat_fix_topics: Vec<NavSatFixListenerConfig>,
+    #[serde(default)]
+    pub odometry_topics: Vec<OdometryListenerConfig>,
     pub path_topics: Vec<PoseListenerConfig>,
     pub pointcloud2_topics: Vec<PointCloud2ListenerConfig>,
+    #[serde(default)]
+    pub point_stamped_topics: Vec<PointListenerConfig>,
     pub polygon_stamped_topics: Vec<ListenerConfigColor>,
     pub pose_array_topics: Vec<PoseListenerConfig>,
     pub pose_stamped_topics: Vec<PoseListenerConfig>,
+    #[serde(default)]
+    pub pose_with_covariance_topics: Vec<PoseListenerConfig>,
+    #[serde(default)]
+    pub range_topics: Vec<ListenerConfigColor>,
     pub send_pose_topics: Vec<SendPoseConfig>,
+    /// Frames in which the poses can be sent instead of the fixed frame.
+    #[serde(default)]
+    pub send_pose_frames: Vec<String>,
+    #[serde(default)]
+    pub pose_presets: Vec<PosePresetConfig>,
+    #[serde(default)]
+    pub goal_queue: GoalQueueConfig,
+    #[serde(default)]
+    pub twist_stamped_topics: Vec<VectorListenerConfig>,
+    #[serde(default)]
+    pub wrench_stamped_topics: Vec<VectorListenerConfig>,
     pub target_framerate: i64,
     pub axis_length: f64,
     pub visible_area: Vec<f64>, //Borders of map from center in Meter
     pub zoom_factor: f64,
     pub key_mapping: HashMap<String, String>,
     pub teleop: TeleopConfig,
+    #[serde(default)]
+    pub diagnostics: DiagnosticsConfig,
+    #[serde(default)]
+    pub joint_teleop: JointTeleopConfig,
+    #[serde(default)]
+    pub logs: LogsConfig,
+    #[serde(default)]
+    pub topic_monitor: TopicMonitorConfig,
+    #[serde(default)]
+    pub bag_playback: BagPlaybackConfig,
+    #[serde(default)]
+    pub waypoint_editor: WaypointEditorConfig,
+    #[serde(default)]
+    pub polygon_editor: PolygonEditorConfig,
+    #[serde(default)]
+    pub split_view: SplitViewConfig,
+    #[serde(default)]
+    pub multi_view: MultiViewConfig,
+    #[serde(default)]
+    pub dashboard: DashboardConfig,
+    #[serde(default)]
+    pub map_editor: MapEditorConfig,
 }
 
 impl Default for TermvizConfig {
@@ -141,6 +1097,52 @@ impl Default for TermvizConfig {
         TermvizConfig {
             fixed_frame: "map".to_string(),
             robot_frame: "base_link".to_string(),
+            follow_frames: vec![],
+            bookmarks: vec![],
+            grid: GridConfig::default(),
+            trail: TrailConfig::default(),
+            minimap: MinimapConfig::default(),
+            localization_quality: LocalizationQualityConfig::default(),
+            export: ExportConfig::default(),
+            record: RecordConfig::default(),
+            theme: ThemeConfig::default(),
+            canvas_marker: default_canvas_marker(),
+            mode_canvas_markers: HashMap::new(),
+            robot_up: false,
+            view_rotation: 0.0,
+            draw_order: vec![],
+            background_color: color_black(),
+            fill_background: false,
+            footprint_color: color_blue(),
+            x_axis_color: color_red(),
+            y_axis_color: color_green(),
+            battery_topics: vec![BatteryListenerConfig {
+                topic: "battery_state".to_string(),
+                warning_threshold: 0.3,
+                critical_threshold: 0.15,
+            }],
+            camera_info_topics: vec![CameraInfoListenerConfig {
+                topic: "camera_info".to_string(),
+                color: Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                },
+                range: 1.0,
+            }],
+            detection3d_topics: vec![DetectionListenerConfig {
+                topic: "detections".to_string(),
+                colors: default_class_colors(),
+            }],
+            field_topics: vec![FieldListenerConfig {
+                topic: "odom".to_string(),
+                field: "twist.twist.linear.x".to_string(),
+                msg_type: Some("nav_msgs/Odometry".to_string()),
+                label: Some("Speed".to_string()),
+                precision: 2,
+                min: None,
+                max: None,
+            }],
             map_topics: vec![MapListenerConfig {
                 topic: "map".to_string(),
                 color: Color {
@@ -148,21 +1150,67 @@ impl Default for TermvizConfig {
                     b: 255,
                     g: 255,
                 },
-                threshold: 1,
+                min: default_map_min(),
+                max: default_map_max(),
+                z_order: 0,
+                only_where_free: None,
+                gradient: false,
+                colormap: default_gradient(),
+                subscribe_to_updates: true,
             }],
-            laser_topics: vec![ListenerConfigColor {
+            grid_cells_topics: vec![ListenerConfigColor {
+                topic: "grid_cells".to_string(),
+                color: Color {
+                    r: 255,
+                    g: 0,
+                    b: 255,
+                },
+            }],
+            laser_topics: vec![LaserListenerConfig {
                 topic: "scan".to_string(),
                 color: Color { r: 200, b: 0, g: 0 },
+                decay_time: 0.0,
+                range_max_override: None,
+                stride: default_stride(),
+                memory_time: 0.0,
+                memory_resolution: default_memory_resolution(),
+                memory_radius: default_memory_radius(),
             }],
             marker_array_topics: vec![ListenerConfig {
                 topic: "marker_array".to_string(),
+                z_range: ZRange::default(),
             }],
             marker_topics: vec![ListenerConfig {
                 topic: "marker".to_string(),
+                z_range: ZRange::default(),
             }],
             image_topics: vec![ImageListenerConfig {
                 topic: "image_rect".to_string(),
                 rotation: 0,
+                detections_topic: None,
+                detection_colors: default_class_colors(),
+                compressed: false,
+            }],
+            pose_with_covariance_topics: vec![PoseListenerConfig {
+                topic: "amcl_pose".to_string(),
+                style: "arrow".to_string(),
+                color: Color {
+                    r: 255,
+                    g: 0,
+                    b: 255,
+                },
+                length: 0.2,
+            }],
+            imu_topics: vec![ImuListenerConfig {
+                topic: "imu".to_string(),
+                length: 0.2,
+                show_acceleration: false,
+                acceleration_scale: 0.1,
+                color: Color {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                },
             }],
             pose_stamped_topics: vec![PoseListenerConfig {
                 topic: "pose_stamped".to_string(),
@@ -176,6 +1224,23 @@ impl Default for TermvizConfig {
                 color: Color { r: 255, g: 0, b: 0 },
                 length: 0.2,
             }],
+            move_base_status_topics: vec![MoveBaseStatusListenerConfig {
+                status_topic: "move_base/status".to_string(),
+                plan_topic: "move_base/NavfnROS/plan".to_string(),
+            }],
+            navsat_fix_topics: vec![NavSatFixListenerConfig {
+                topic: "fix".to_string(),
+                origin_latitude: None,
+                origin_longitude: None,
+                history_length: 1000,
+            }],
+            odometry_topics: vec![OdometryListenerConfig {
+                topic: "odom".to_string(),
+                style: "arrow".to_string(),
+                color: Color { r: 0, g: 0, b: 255 },
+                length: 0.2,
+                show_twist: false,
+            }],
             path_topics: vec![PoseListenerConfig {
                 topic: "path".to_string(),
                 style: "line".to_string(),
@@ -185,15 +1250,58 @@ impl Default for TermvizConfig {
             pointcloud2_topics: vec![PointCloud2ListenerConfig {
                 topic: "pointcloud2".to_string(),
                 use_rgb: false,
+                color_axis: default_color_axis(),
+                color_field: None,
+                color_min: None,
+                color_max: None,
+                gradient: default_gradient(),
+                z_range: ZRange::default(),
+                accumulation_time: 0.0,
+                decimation: default_decimation(),
+                voxel_size: 0.0,
+            }],
+            point_stamped_topics: vec![PointListenerConfig {
+                topic: "clicked_point".to_string(),
+                color: Color { r: 255, g: 0, b: 0 },
+                size: 0.1,
             }],
             polygon_stamped_topics: vec![ListenerConfigColor {
                 topic: "footprint".to_string(),
                 color: Color { r: 200, b: 0, g: 0 },
             }],
+            range_topics: vec![ListenerConfigColor {
+                topic: "range".to_string(),
+                color: Color {
+                    r: 0,
+                    g: 200,
+                    b: 200,
+                },
+            }],
             send_pose_topics: vec![SendPoseConfig {
                 topic: "initialpose".to_string(),
                 msg_type: "PoseWithCovarianceStamped".to_string(),
             }],
+            send_pose_frames: Vec::new(),
+            pose_presets: Vec::new(),
+            goal_queue: GoalQueueConfig::default(),
+            twist_stamped_topics: vec![VectorListenerConfig {
+                topic: "cmd_vel_stamped".to_string(),
+                color: Color {
+                    r: 0,
+                    g: 255,
+                    b: 255,
+                },
+                scale: 1.0,
+            }],
+            wrench_stamped_topics: vec![VectorListenerConfig {
+                topic: "wrench".to_string(),
+                color: Color {
+                    r: 255,
+                    g: 128,
+                    b: 0,
+                },
+                scale: 0.01,
+            }],
             target_framerate: 30,
             axis_length: 0.5,
             visible_area: vec![-5., 5., -5., 5.],
@@ -214,10 +1322,60 @@ impl Default for TermvizConfig {
                 (input::NEXT.to_string(), "n".to_string()),
                 (input::PREVIOUS.to_string(), "b".to_string()),
                 (input::SHOW_HELP.to_string(), "h".to_string()),
+                (input::FILTER.to_string(), "f".to_string()),
+                (input::SEARCH.to_string(), "/".to_string()),
+                (input::EXPORT.to_string(), "x".to_string()),
+                (input::KILL.to_string(), "K".to_string()),
+                (input::DELETE.to_string(), "Backspace".to_string()),
+                (input::MOVE_FORWARD.to_string(), ">".to_string()),
+                (input::MOVE_BACKWARD.to_string(), "<".to_string()),
+                (input::PUBLISH.to_string(), "p".to_string()),
+                (input::ENQUEUE.to_string(), "g".to_string()),
+                (input::ENTER_COORDINATES.to_string(), ":".to_string()),
+                (input::NEXT_TARGET_FRAME.to_string(), "z".to_string()),
+                (input::SELECT_PRESET.to_string(), "Z".to_string()),
+                (input::SAVE_PRESET.to_string(), "Y".to_string()),
+                (input::PAN_UP.to_string(), "W".to_string()),
+                (input::PAN_DOWN.to_string(), "S".to_string()),
+                (input::PAN_LEFT.to_string(), "A".to_string()),
+                (input::PAN_RIGHT.to_string(), "D".to_string()),
+                (input::RECENTER.to_string(), "c".to_string()),
+                (input::FOLLOW_NEXT_FRAME.to_string(), "F".to_string()),
+                (input::FREE_CAMERA.to_string(), "v".to_string()),
+                (input::SAVE_BOOKMARK.to_string(), "m".to_string()),
+                (input::NEXT_BOOKMARK.to_string(), "'".to_string()),
+                (input::ROBOT_UP.to_string(), "u".to_string()),
+                (input::SHOW_LEGEND.to_string(), "l".to_string()),
+                (input::SHOW_LAYERS.to_string(), "L".to_string()),
+                (input::ROTATE_VIEW_LEFT.to_string(), "[".to_string()),
+                (input::ROTATE_VIEW_RIGHT.to_string(), "]".to_string()),
+                (input::CLEAR_TRAIL.to_string(), "T".to_string()),
+                (input::CLEAR_MEMORY.to_string(), "C".to_string()),
+                (input::SAVE_MAP.to_string(), "X".to_string()),
+                (input::NEXT_CANVAS_MARKER.to_string(), "M".to_string()),
+                (input::RECORD.to_string(), "R".to_string()),
+                (input::RECORD_SEQUENCE.to_string(), "r".to_string()),
+                (input::REPLAY_SEQUENCE.to_string(), "y".to_string()),
+                (input::SWITCH_FOCUS.to_string(), "o".to_string()),
+                (input::SHOW_MINIMAP.to_string(), "N".to_string()),
+                (input::NEXT_PROJECTION.to_string(), "P".to_string()),
+                (input::NEXT_MODE.to_string(), "Tab".to_string()),
+                (input::PREVIOUS_MODE.to_string(), "BackTab".to_string()),
                 (input::MODE_2.to_string(), "t".to_string()),
                 (input::MODE_3.to_string(), "i".to_string()),
             ]),
             teleop: TeleopConfig::default(),
+            diagnostics: DiagnosticsConfig::default(),
+            joint_teleop: JointTeleopConfig::default(),
+            logs: LogsConfig::default(),
+            topic_monitor: TopicMonitorConfig::default(),
+            bag_playback: BagPlaybackConfig::default(),
+            waypoint_editor: WaypointEditorConfig::default(),
+            polygon_editor: PolygonEditorConfig::default(),
+            split_view: SplitViewConfig::default(),
+            multi_view: MultiViewConfig::default(),
+            dashboard: DashboardConfig::default(),
+            map_editor: MapEditorConfig::default(),
         }
     }
 }
@@ -253,6 +1411,13 @@ pub fn get_config(config_path: Option<&String>) -> Result<TermvizConfig, confy::
     if load_config_path.exists() {
         println!("Loading config from: {:?}", load_config_path);
         cfg = confy::load_path(load_config_path)?;
+        // Actions added since the config was written get their default key,
+        // unless the user already mapped that key to another action
+        for (action, key) in TermvizConfig::default().key_mapping {
+            if !cfg.key_mapping.values().any(|mapped| *mapped == key) {
+                cfg.key_mapping.entry(action).or_insert(key);
+            }
+        }
     } else {
         // no config found, generate default
         println!("No config found, using default");
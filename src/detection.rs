@@ -0,0 +1,83 @@
+use crate::config::DetectionListenerConfig;
+use crate::marker::parse_cube;
+use crate::source;
+use crate::transformation::{ros_pose_to_isometry, ros_transform_to_isometry};
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+pub struct Detection3DListener {
+    pub config: DetectionListenerConfig,
+    lines: Arc<RwLock<Vec<Line>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl Detection3DListener {
+    pub fn new(
+        config: DetectionListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> Detection3DListener {
+        let lines = Arc::new(RwLock::new(Vec::<Line>::new()));
+        let cb_lines = lines.clone();
+        let str_ = static_frame.clone();
+        let mut colors: Vec<tui::style::Color> = config.colors.iter().map(|c| c.to_tui()).collect();
+        if colors.is_empty() {
+            colors.push(tui::style::Color::White);
+        }
+
+        let local_listener = tf_listener.clone();
+        let detections_sub = source::subscribe(
+            &config.topic,
+            1,
+            move |msg: rosrust_msg::vision_msgs::Detection3DArray| {
+                let res =
+                    local_listener.lookup_transform(&str_, &msg.header.frame_id, msg.header.stamp);
+                let transform = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                let mut detection_lines: Vec<Line> = Vec::new();
+                for detection in msg.detections.iter() {
+                    // Use the most likely class to pick the color
+                    let class_id = detection
+                        .results
+                        .iter()
+                        .max_by(|a, b| {
+                            a.score
+                                .partial_cmp(&b.score)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                        })
+                        .map_or(0, |r| r.id);
+                    let color = colors[class_id.rem_euclid(colors.len() as i64) as usize];
+                    let iso = transform * ros_pose_to_isometry(&detection.bbox.center);
+                    detection_lines.extend(parse_cube(
+                        &detection.bbox.size,
+                        &rosrust_msg::geometry_msgs::Point {
+                            x: 0.0,
+                            y: 0.0,
+                            z: 0.0,
+                        },
+                        &color,
+                        &iso,
+                    ));
+                }
+                *cb_lines.write().unwrap() = detection_lines;
+            },
+        )
+        .unwrap();
+
+        Detection3DListener {
+            config,
+            lines,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: detections_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        self.lines.read().unwrap().to_vec()
+    }
+}
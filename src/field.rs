@@ -0,0 +1,157 @@
+//! Module dealing with the extraction of single numeric fields from messages.
+//!
+//! A field is given as a path of field names and array indices separated by dots,
+//! e.g. `twist.twist.linear.x` or `position.0`. The messages are converted to a
+//! generic value through serde, so the path follows the field names of the ROS message.
+use crate::config::FieldListenerConfig;
+use crate::source;
+use rosrust;
+use rosrust_msg::{geometry_msgs, nav_msgs, sensor_msgs, std_msgs};
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+use tui::style::{Color, Style};
+use tui::text::Span;
+
+/// Resolves the path in the value, returning the number found there, if any.
+/// Booleans are converted to 0 or 1.
+pub fn resolve(value: &Value, path: &str) -> Option<f64> {
+    let mut current = value;
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        current = match current {
+            Value::Object(map) => map.get(key)?,
+            Value::Array(array) => array.get(key.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    match current {
+        Value::Number(n) => n.as_f64(),
+        Value::Bool(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+/// Subscribes to a topic of type T, storing the value of the field of each message.
+fn subscribe<T: rosrust::Message + Serialize>(
+    topic: &str,
+    field: String,
+    data: Arc<RwLock<Option<f64>>>,
+) -> Option<source::Subscriber> {
+    source::subscribe(topic, 1, move |msg: T| {
+        let value = serde_json::to_value(&msg).ok();
+        *data.write().unwrap() = value.and_then(|value| resolve(&value, &field));
+    })
+    .ok()
+}
+
+pub struct FieldListener {
+    pub config: FieldListenerConfig,
+    pub value: Arc<RwLock<Option<f64>>>,
+    _subscriber: Option<source::Subscriber>,
+}
+
+impl FieldListener {
+    pub fn new(config: FieldListenerConfig) -> FieldListener {
+        let value = Arc::new(RwLock::new(None));
+        // Without a configured type, the type of the topic is taken from the master or the bags,
+        // in which case the topic has to be advertised when starting
+        let msg_type = config
+            .msg_type
+            .clone()
+            .or_else(|| source::topic_type(&config.topic))
+            .unwrap_or_default();
+        let topic = config.topic.as_str();
+        let field = config.field.clone();
+        let data = value.clone();
+        let sub = match msg_type.as_str() {
+            "geometry_msgs/Point" => subscribe::<geometry_msgs::Point>(topic, field, data),
+            "geometry_msgs/PointStamped" => {
+                subscribe::<geometry_msgs::PointStamped>(topic, field, data)
+            }
+            "geometry_msgs/Pose" => subscribe::<geometry_msgs::Pose>(topic, field, data),
+            "geometry_msgs/PoseStamped" => {
+                subscribe::<geometry_msgs::PoseStamped>(topic, field, data)
+            }
+            "geometry_msgs/PoseWithCovarianceStamped" => {
+                subscribe::<geometry_msgs::PoseWithCovarianceStamped>(topic, field, data)
+            }
+            "geometry_msgs/Twist" => subscribe::<geometry_msgs::Twist>(topic, field, data),
+            "geometry_msgs/TwistStamped" => {
+                subscribe::<geometry_msgs::TwistStamped>(topic, field, data)
+            }
+            "geometry_msgs/WrenchStamped" => {
+                subscribe::<geometry_msgs::WrenchStamped>(topic, field, data)
+            }
+            "nav_msgs/Odometry" => subscribe::<nav_msgs::Odometry>(topic, field, data),
+            "sensor_msgs/BatteryState" => {
+                subscribe::<sensor_msgs::BatteryState>(topic, field, data)
+            }
+            "sensor_msgs/Imu" => subscribe::<sensor_msgs::Imu>(topic, field, data),
+            "sensor_msgs/JointState" => subscribe::<sensor_msgs::JointState>(topic, field, data),
+            "sensor_msgs/NavSatFix" => subscribe::<sensor_msgs::NavSatFix>(topic, field, data),
+            "sensor_msgs/Range" => subscribe::<sensor_msgs::Range>(topic, field, data),
+            "sensor_msgs/Temperature" => subscribe::<sensor_msgs::Temperature>(topic, field, data),
+            "std_msgs/Bool" => subscribe::<std_msgs::Bool>(topic, field, data),
+            "std_msgs/Float32" => subscribe::<std_msgs::Float32>(topic, field, data),
+            "std_msgs/Float64" => subscribe::<std_msgs::Float64>(topic, field, data),
+            "std_msgs/Int32" => subscribe::<std_msgs::Int32>(topic, field, data),
+            "std_msgs/Int64" => subscribe::<std_msgs::Int64>(topic, field, data),
+            "std_msgs/UInt8" => subscribe::<std_msgs::UInt8>(topic, field, data),
+            _ => None,
+        };
+        FieldListener {
+            config,
+            value,
+            _subscriber: sub,
+        }
+    }
+
+    /// Returns the label of the field, by default the topic and the field path.
+    pub fn label(&self) -> String {
+        self.config
+            .label
+            .clone()
+            .unwrap_or(format!("{}/{}", self.config.topic, self.config.field))
+    }
+
+    /// Returns a description of the violated threshold, if the last value is out of
+    /// the configured range.
+    pub fn alert(&self) -> Option<String> {
+        let value = (*self.value.read().unwrap())?;
+        match (self.config.min, self.config.max) {
+            (Some(min), _) if value < min => Some(format!(
+                "{} is {:.*}, below {}",
+                self.label(),
+                self.config.precision,
+                value,
+                min
+            )),
+            (_, Some(max)) if value > max => Some(format!(
+                "{} is {:.*}, above {}",
+                self.label(),
+                self.config.precision,
+                value,
+                max
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns the last value of the field, in red if out of the configured range.
+    /// Shows a placeholder if the type is unsupported or the field could not be extracted.
+    pub fn status(&self) -> Span<'static> {
+        let text = if self._subscriber.is_none() {
+            format!("{}: unsupported type", self.label())
+        } else {
+            match *self.value.read().unwrap() {
+                Some(value) => format!("{}: {:.*}", self.label(), self.config.precision, value),
+                None => format!("{}: -", self.label()),
+            }
+        };
+        if self.alert().is_some() {
+            Span::styled(text, Style::default().fg(Color::Red))
+        } else {
+            Span::raw(text)
+        }
+    }
+}
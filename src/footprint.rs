@@ -1,3 +1,4 @@
+use crate::source;
 use crate::transformation;
 
 use rosrust;
@@ -15,6 +16,10 @@ pub fn get_default_footprint() -> Vec<(f64, f64)> {
 }
 
 pub fn get_footprint() -> Vec<(f64, f64)> {
+    // The parameters are only available on the master
+    if source::reading_bags() {
+        return get_default_footprint();
+    }
     let param = rosrust::param("/footprint");
     let mut result = Vec::<(f64, f64)>::new();
     match param {
@@ -1,10 +1,84 @@
-use crate::config::ImageListenerConfig;
+use crate::config::{Color, ImageListenerConfig};
+use crate::source;
 use byteorder::{ByteOrder, LittleEndian};
-use image::{imageops, DynamicImage, ImageBuffer, Rgb, RgbImage, RgbaImage};
-use rosrust;
+use image::{
+    imageops, load_from_memory, load_from_memory_with_format, DynamicImage, ImageBuffer,
+    ImageFormat, Rgb, RgbImage, Rgba, RgbaImage,
+};
 use rosrust_msg;
 use std::sync::{Arc, RwLock};
 
+/// Thickness of the bounding boxes, in pixels.
+const BOX_THICKNESS: u32 = 2;
+
+/// A 2D detection, with its bounding box in pixels.
+#[derive(Clone)]
+pub struct Detection {
+    pub class_id: i64,
+    pub score: f64,
+    /// Color of the bounding box, picked depending on the class.
+    pub color: Color,
+    x_min: f64,
+    y_min: f64,
+    x_max: f64,
+    y_max: f64,
+}
+
+fn read_detections_msg(
+    msg: rosrust_msg::vision_msgs::Detection2DArray,
+    colors: &Vec<Color>,
+) -> Vec<Detection> {
+    msg.detections
+        .iter()
+        .map(|detection| {
+            // Use the most likely class
+            let best = detection.results.iter().max_by(|a, b| {
+                a.score
+                    .partial_cmp(&b.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            let bbox = &detection.bbox;
+            let class_id = best.map_or(-1, |r| r.id);
+            Detection {
+                class_id,
+                color: colors[class_id.rem_euclid(colors.len() as i64) as usize].clone(),
+                score: best.map_or(0.0, |r| r.score),
+                x_min: bbox.center.x - bbox.size_x / 2.0,
+                y_min: bbox.center.y - bbox.size_y / 2.0,
+                x_max: bbox.center.x + bbox.size_x / 2.0,
+                y_max: bbox.center.y + bbox.size_y / 2.0,
+            }
+        })
+        .collect()
+}
+
+fn draw_detections(img: &mut RgbaImage, detections: &Vec<Detection>) {
+    let (width, height) = img.dimensions();
+    if width == 0 || height == 0 {
+        return;
+    }
+    let clamp_x = |x: f64| x.max(0.0).min((width - 1) as f64) as u32;
+    let clamp_y = |y: f64| y.max(0.0).min((height - 1) as f64) as u32;
+    for detection in detections {
+        let c = &detection.color;
+        let color = Rgba([c.r, c.g, c.b, 255]);
+        let (x_min, x_max) = (clamp_x(detection.x_min), clamp_x(detection.x_max));
+        let (y_min, y_max) = (clamp_y(detection.y_min), clamp_y(detection.y_max));
+        for x in x_min..x_max + 1 {
+            for t in 0..BOX_THICKNESS {
+                img.put_pixel(x, (y_min + t).min(y_max), color);
+                img.put_pixel(x, y_max.saturating_sub(t).max(y_min), color);
+            }
+        }
+        for y in y_min..y_max + 1 {
+            for t in 0..BOX_THICKNESS {
+                img.put_pixel((x_min + t).min(x_max), y, color);
+                img.put_pixel(x_max.saturating_sub(t).max(x_min), y, color);
+            }
+        }
+    }
+}
+
 // remap a value from range min_val - max_val to 0 - 255
 fn remap_u8(val: f64, min_val: f64, max_val: f64) -> u8 {
     ((val - min_val) * (u8::MAX as f64 / (max_val - min_val))) as u8
@@ -40,6 +114,74 @@ fn read_img_msg(img_msg: rosrust_msg::sensor_msgs::Image) -> DynamicImage {
     }
 }
 
+/// Size of the header preceding the PNG data in the compressedDepth transport.
+const COMPRESSED_DEPTH_HEADER_SIZE: usize = 12;
+
+/// Decodes a compressedDepth image into a raw depth image.
+///
+/// The payload starts with a header containing the compression format (int32) and
+/// the two depth quantization parameters (float32) used for 32FC1 images. The rest
+/// is a 16 bit PNG, containing either the depth (16UC1) or the quantized inverse depth (32FC1).
+fn read_compressed_depth_msg(
+    img_msg: &rosrust_msg::sensor_msgs::CompressedImage,
+) -> Option<rosrust_msg::sensor_msgs::Image> {
+    if img_msg.data.len() <= COMPRESSED_DEPTH_HEADER_SIZE {
+        return None;
+    }
+    let depth_quant_a = LittleEndian::read_f32(&img_msg.data[4..8]);
+    let depth_quant_b = LittleEndian::read_f32(&img_msg.data[8..12]);
+    let png = load_from_memory_with_format(
+        &img_msg.data[COMPRESSED_DEPTH_HEADER_SIZE..],
+        ImageFormat::Png,
+    )
+    .ok()?
+    .into_luma16();
+
+    let mut img = rosrust_msg::sensor_msgs::Image::default();
+    img.header = img_msg.header.clone();
+    img.width = png.width();
+    img.height = png.height();
+    if img_msg.format.starts_with("32FC1") {
+        img.encoding = "32FC1".to_string();
+        img.step = img.width * 4;
+        img.data = vec![0; (img.step * img.height) as usize];
+        for (i, val) in png.into_raw().iter().enumerate() {
+            let depth = if *val == 0 {
+                f32::NAN
+            } else {
+                depth_quant_a / (*val as f32 - depth_quant_b)
+            };
+            LittleEndian::write_f32(&mut img.data[i * 4..i * 4 + 4], depth);
+        }
+    } else {
+        img.encoding = "16UC1".to_string();
+        img.step = img.width * 2;
+        img.data = vec![0; (img.step * img.height) as usize];
+        LittleEndian::write_u16_into(&png.into_raw(), &mut img.data);
+    }
+    Some(img)
+}
+
+/// Draws the detections on the image, rotates it and stores it for display.
+fn store_img(
+    img: DynamicImage,
+    detections: &Arc<RwLock<Vec<Detection>>>,
+    rotation: &Arc<RwLock<i64>>,
+    target: &Arc<RwLock<RgbaImage>>,
+) {
+    let mut img = img.to_rgba8();
+    draw_detections(&mut img, &detections.read().unwrap());
+    let rot = rotation.read().unwrap();
+    match *rot {
+        90 => img = imageops::rotate90(&img),
+        180 => img = imageops::rotate180(&img),
+        270 => img = imageops::rotate270(&img),
+        _ => (),
+    }
+    let mut target = target.write().unwrap();
+    *target = img;
+}
+
 fn read_f32(vec: &Vec<u8>) -> Vec<u8> {
     let mut vals: Vec<f32> = Vec::with_capacity(vec.len() / 4);
     let mut max_val = f32::MIN;
@@ -85,7 +227,9 @@ fn read_u16(vec: &Vec<u8>) -> Vec<u8> {
 pub struct ImageListener {
     pub config: ImageListenerConfig,
     pub img: Arc<RwLock<RgbaImage>>,
-    _subscriber: Option<rosrust::Subscriber>,
+    pub detections: Arc<RwLock<Vec<Detection>>>,
+    _subscriber: Option<source::Subscriber>,
+    _detections_subscriber: Option<source::Subscriber>,
     _rotation: Arc<RwLock<i64>>,
 }
 
@@ -96,7 +240,9 @@ impl ImageListener {
         ImageListener {
             config,
             img,
+            detections: Arc::new(RwLock::new(Vec::new())),
             _subscriber: None,
+            _detections_subscriber: None,
             _rotation: Arc::new(RwLock::new(default_rotation)),
         }
     }
@@ -104,24 +250,63 @@ impl ImageListener {
     pub fn setup_sub(&mut self) {
         let cb_img = self.img.clone();
         let cb_rotation = self._rotation.clone();
-        let sub = rosrust::subscribe(
-            &self.config.topic,
-            1,
-            move |img_msg: rosrust_msg::sensor_msgs::Image| {
-                let mut img = read_img_msg(img_msg).to_rgba8();
-                let rot = cb_rotation.read().unwrap();
-                match *rot {
-                    90 => img = imageops::rotate90(&img),
-                    180 => img = imageops::rotate180(&img),
-                    270 => img = imageops::rotate270(&img),
-                    _ => (),
-                }
-                let mut cb_img = cb_img.write().unwrap();
-                *cb_img = img;
-            },
-        )
+        let cb_detections = self.detections.clone();
+        let sub = if self.config.compressed {
+            source::subscribe(
+                &self.config.topic,
+                1,
+                move |img_msg: rosrust_msg::sensor_msgs::CompressedImage| {
+                    if img_msg.format.contains("compressedDepth") {
+                        match read_compressed_depth_msg(&img_msg) {
+                            Some(depth_msg) => store_img(
+                                read_img_msg(depth_msg),
+                                &cb_detections,
+                                &cb_rotation,
+                                &cb_img,
+                            ),
+                            None => return,
+                        }
+                        return;
+                    }
+                    // The format (JPEG or PNG) is guessed from the payload
+                    match load_from_memory(&img_msg.data) {
+                        Ok(img) => store_img(img, &cb_detections, &cb_rotation, &cb_img),
+                        Err(_e) => return,
+                    }
+                },
+            )
+        } else {
+            source::subscribe(
+                &self.config.topic,
+                1,
+                move |img_msg: rosrust_msg::sensor_msgs::Image| {
+                    store_img(read_img_msg(img_msg), &cb_detections, &cb_rotation, &cb_img);
+                },
+            )
+        }
         .unwrap();
-        self._subscriber = Some(sub)
+        self._subscriber = Some(sub);
+
+        if let Some(detections_topic) = &self.config.detections_topic {
+            let cb_detections = self.detections.clone();
+            let mut colors = self.config.detection_colors.clone();
+            if colors.is_empty() {
+                colors.push(Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                });
+            }
+            let detections_sub = source::subscribe(
+                detections_topic,
+                1,
+                move |msg: rosrust_msg::vision_msgs::Detection2DArray| {
+                    *cb_detections.write().unwrap() = read_detections_msg(msg, &colors);
+                },
+            )
+            .unwrap();
+            self._detections_subscriber = Some(detections_sub);
+        }
     }
 
     pub fn is_active(&self) -> bool {
@@ -134,6 +319,8 @@ impl ImageListener {
 
     pub fn deactivate(&mut self) {
         self._subscriber = None;
+        self._detections_subscriber = None;
+        self.detections.write().unwrap().clear();
     }
 
     pub fn rotate(&mut self, angle: i64) {
@@ -0,0 +1,117 @@
+use crate::config::ImuListenerConfig;
+use crate::pose::{pose_to_axes, vector_to_arrow};
+use crate::source;
+use crate::transformation::ros_transform_to_isometry;
+use nalgebra::geometry::{Isometry3, Point3, Quaternion, UnitQuaternion};
+use nalgebra::Vector3;
+use std::option::Option;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+/// Orientation and acceleration of the IMU, expressed in the static frame.
+struct ImuData {
+    pose: Isometry3<f64>,
+    acceleration_end: Point3<f64>,
+    roll_pitch_yaw: (f64, f64, f64),
+}
+
+pub struct ImuListener {
+    pub config: ImuListenerConfig,
+    data: Arc<RwLock<Option<ImuData>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl ImuListener {
+    pub fn new(
+        config: ImuListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> ImuListener {
+        let data = Arc::new(RwLock::new(None));
+        let cb_data = data.clone();
+        let str_ = static_frame.clone();
+        let acceleration_scale = config.acceleration_scale;
+
+        let local_listener = tf_listener.clone();
+        let imu_sub = source::subscribe(
+            &config.topic,
+            2,
+            move |imu_msg: rosrust_msg::sensor_msgs::Imu| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &imu_msg.header.frame_id,
+                    imu_msg.header.stamp,
+                );
+                let transform = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                let orientation = UnitQuaternion::new_normalize(Quaternion::new(
+                    imu_msg.orientation.w,
+                    imu_msg.orientation.x,
+                    imu_msg.orientation.y,
+                    imu_msg.orientation.z,
+                ));
+                // The orientation is absolute, so it is only attached to the position of the sensor
+                let pose = Isometry3::from_parts(transform.translation, orientation);
+                // The acceleration is expressed in the sensor frame
+                let acceleration = transform.rotation
+                    * Vector3::new(
+                        imu_msg.linear_acceleration.x,
+                        imu_msg.linear_acceleration.y,
+                        imu_msg.linear_acceleration.z,
+                    )
+                    * acceleration_scale;
+                let origin = Point3::from(transform.translation.vector);
+                *cb_data.write().unwrap() = Some(ImuData {
+                    pose,
+                    acceleration_end: origin + acceleration,
+                    roll_pitch_yaw: orientation.euler_angles(),
+                });
+            },
+        )
+        .unwrap();
+
+        ImuListener {
+            config,
+            data,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: imu_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match &*self.data.read().unwrap() {
+            Some(data) => {
+                let mut lines = pose_to_axes(&data.pose, self.config.length);
+                if self.config.show_acceleration {
+                    let origin = data.pose.transform_point(&Point3::new(0.0, 0.0, 0.0));
+                    lines.extend(vector_to_arrow(
+                        &origin,
+                        &data.acceleration_end,
+                        self.config.color.to_tui(),
+                    ));
+                }
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns the current roll, pitch and yaw in degrees, if any was received.
+    pub fn info(&self) -> Option<String> {
+        match &*self.data.read().unwrap() {
+            Some(data) => Some(format!(
+                "/{} r: {:.1}° p: {:.1}° y: {:.1}°",
+                self.config.topic,
+                data.roll_pitch_yaw.0.to_degrees(),
+                data.roll_pitch_yaw.1.to_degrees(),
+                data.roll_pitch_yaw.2.to_degrees(),
+            )),
+            None => None,
+        }
+    }
+}
@@ -1,30 +1,52 @@
-use crate::config::ListenerConfigColor;
+use crate::config::{Color, LaserListenerConfig};
+use crate::source;
 use crate::transformation;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
-use rosrust;
-use rustros_tf;
+use tui::style::Color as TuiColor;
+
+/// Points of a scan in the static frame, with the time at which it was received.
+pub struct ScanPoints {
+    pub received: Instant,
+    pub points: Vec<(f64, f64)>,
+}
 
 pub struct LaserListener {
-    pub config: ListenerConfigColor,
-    pub points: Arc<RwLock<Vec<(f64, f64)>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    pub config: LaserListenerConfig,
+    /// Scans received within the decay time, from the oldest to the latest.
+    pub scans: Arc<RwLock<VecDeque<ScanPoints>>>,
+    /// Cells of the local obstacle memory in the static frame, with the time at which they
+    /// were last seen.
+    pub memory: Arc<RwLock<HashMap<(i64, i64), Instant>>>,
+    background_color: Color,
+    _tf_listener: Arc<source::TfListener>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 impl LaserListener {
     pub fn new(
-        config: ListenerConfigColor,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        config: LaserListenerConfig,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
+        background_color: Color,
     ) -> LaserListener {
-        let scan_points = Arc::new(RwLock::new(Vec::<(f64, f64)>::new()));
-        let cb_scan_points = scan_points.clone();
+        let scans = Arc::new(RwLock::new(VecDeque::<ScanPoints>::new()));
+        let cb_scans = scans.clone();
         let str_ = static_frame.clone();
+        let decay_time = Duration::from_secs_f64(config.decay_time.max(0.0));
+        let range_max_override = config.range_max_override;
+        let memory = Arc::new(RwLock::new(HashMap::<(i64, i64), Instant>::new()));
+        let cb_memory = memory.clone();
+        let memory_time = Duration::from_secs_f64(config.memory_time.max(0.0));
+        let memory_resolution = config.memory_resolution;
+        let memory_radius = config.memory_radius;
+        let stride = config.stride.max(1);
 
         let local_listener = tf_listener.clone();
-        let laser_sub = rosrust::subscribe(
+        let laser_sub = source::subscribe(
             &config.topic,
             2,
             move |scan: rosrust_msg::sensor_msgs::LaserScan| {
@@ -38,7 +60,7 @@ impl LaserListener {
                     Ok(res) => res,
                     Err(_e) => return,
                 };
-                for (i, range) in scan.ranges.iter().enumerate() {
+                for (i, range) in scan.ranges.iter().enumerate().step_by(stride) {
                     let angle = scan.angle_min + i as f32 * scan.angle_increment;
                     let pt = transformation::transform_relative_pt(
                         &res.as_ref().unwrap().transform,
@@ -47,22 +69,118 @@ impl LaserListener {
                             *range as f64 * angle.sin() as f64,
                         ),
                     );
-                    if range > &scan.range_min {
+                    if range > &scan.range_min
+                        && range_max_override.map_or(true, |max| *range as f64 <= max)
+                    {
                         points.push(pt);
                     }
                 }
-                let mut cb_scan_points = cb_scan_points.write().unwrap();
-                *cb_scan_points = points;
+                let now = Instant::now();
+                if !memory_time.is_zero() && memory_resolution > 0.0 {
+                    let origin = &res.as_ref().unwrap().transform.translation;
+                    let mut cb_memory = cb_memory.write().unwrap();
+                    for (x, y) in points.iter() {
+                        cb_memory.insert(
+                            (
+                                (x / memory_resolution).floor() as i64,
+                                (y / memory_resolution).floor() as i64,
+                            ),
+                            now,
+                        );
+                    }
+                    // Forgets the cells not seen for a while and the ones left behind the robot
+                    cb_memory.retain(|cell, seen| {
+                        let dx = (cell.0 as f64 + 0.5) * memory_resolution - origin.x;
+                        let dy = (cell.1 as f64 + 0.5) * memory_resolution - origin.y;
+                        now.duration_since(*seen) < memory_time && dx.hypot(dy) <= memory_radius
+                    });
+                }
+                let mut cb_scans = cb_scans.write().unwrap();
+                while cb_scans.front().map_or(false, |oldest| {
+                    now.duration_since(oldest.received) >= decay_time
+                }) {
+                    cb_scans.pop_front();
+                }
+                cb_scans.push_back(ScanPoints {
+                    received: now,
+                    points,
+                });
             },
         )
         .unwrap();
 
         LaserListener {
             config,
-            points: scan_points,
+            scans,
+            memory,
+            background_color,
             _tf_listener: tf_listener.clone(),
             _static_frame: static_frame.to_string(),
             _subscriber: laser_sub,
         }
     }
+
+    /// Returns the points of the scans to draw, with their color dimmed according to their age.
+    /// Only the latest scan is returned if there is no decay time.
+    pub fn get_points(&self) -> Vec<(Vec<(f64, f64)>, TuiColor)> {
+        let scans = self.scans.read().unwrap();
+        if self.config.decay_time <= 0.0 {
+            return scans
+                .back()
+                .map(|scan| vec![(scan.points.clone(), self.config.color.to_tui())])
+                .unwrap_or_default();
+        }
+        let now = Instant::now();
+        scans
+            .iter()
+            .filter_map(|scan| {
+                let age = now.duration_since(scan.received).as_secs_f64() / self.config.decay_time;
+                if age >= 1.0 {
+                    return None;
+                }
+                let color = self.config.color.fade(&self.background_color, age);
+                Some((scan.points.clone(), color))
+            })
+            .collect()
+    }
+
+    /// Returns the points of the latest scan.
+    pub fn latest_points(&self) -> Vec<(f64, f64)> {
+        self.scans
+            .read()
+            .unwrap()
+            .back()
+            .map(|scan| scan.points.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the centers of the cells of the local obstacle memory seen within the memory
+    /// time.
+    pub fn get_memory_points(&self) -> Vec<(f64, f64)> {
+        let resolution = self.config.memory_resolution;
+        let now = Instant::now();
+        self.memory
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen).as_secs_f64() < self.config.memory_time)
+            .map(|(cell, _)| {
+                (
+                    (cell.0 as f64 + 0.5) * resolution,
+                    (cell.1 as f64 + 0.5) * resolution,
+                )
+            })
+            .collect()
+    }
+
+    /// Forgets the cells of the local obstacle memory.
+    pub fn clear_memory(&self) {
+        self.memory.write().unwrap().clear();
+    }
+
+    /// Returns the color of the cells of the local obstacle memory, half way to the background
+    /// color.
+    pub fn memory_color(&self) -> TuiColor {
+        self.config.color.fade(&self.background_color, 0.5)
+    }
 }
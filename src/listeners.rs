@@ -1,51 +1,214 @@
+use crate::battery;
+use crate::camera_info;
 use crate::config::{
-    ListenerConfig, ListenerConfigColor, MapListenerConfig, PointCloud2ListenerConfig,
-    PoseListenerConfig,
+    BatteryListenerConfig, CameraInfoListenerConfig, Color, DetectionListenerConfig,
+    FieldListenerConfig, ImuListenerConfig, LaserListenerConfig, ListenerConfig,
+    ListenerConfigColor, MapListenerConfig, MoveBaseStatusListenerConfig, NavSatFixListenerConfig,
+    OdometryListenerConfig, PointCloud2ListenerConfig, PointListenerConfig, PoseListenerConfig,
+    VectorListenerConfig,
 };
+use crate::detection;
+use crate::field;
+use crate::imu;
 use crate::laser;
 use crate::map;
 use crate::marker;
+use crate::move_base;
+use crate::navsat;
+use crate::point;
 use crate::pointcloud;
 use crate::polygon;
 use crate::pose;
+use crate::range;
+use crate::source;
+use crate::twist;
+use crate::wrench;
 
 use std::sync::Arc;
 
+/// Name of the layer of all the markers, which share one container and are hidden together.
+pub const MARKERS_LAYER: &str = "Markers";
+
+/// Describes how the messages of a topic are drawn, to be shown in the legend.
+pub struct LegendEntry {
+    pub topic: String,
+    pub kind: &'static str,
+    /// How the messages are drawn, if not only with the color.
+    pub style: Option<String>,
+    /// Color of the messages, or None if it is taken from the messages themselves.
+    pub color: Option<Color>,
+}
+
+impl LegendEntry {
+    fn new(topic: &String, kind: &'static str, style: Option<&str>, color: Option<&Color>) -> Self {
+        LegendEntry {
+            topic: topic.clone(),
+            kind,
+            style: style.map(|style| style.to_string()),
+            color: color.cloned(),
+        }
+    }
+
+    fn colored(topic: &String, kind: &'static str, color: &Color) -> Self {
+        LegendEntry::new(topic, kind, None, Some(color))
+    }
+}
+
 pub struct Listeners {
     pub lasers: Vec<laser::LaserListener>,
     pub markers: marker::MarkersListener,
     pub maps: Vec<map::MapListener>,
+    pub grid_cells: Vec<map::GridCellsListener>,
     pub pose_stamped: Vec<pose::PoseStampedListener>,
     pub pose_array: Vec<pose::PoseArrayListener>,
+    pub pose_with_covariance: Vec<pose::PoseWithCovarianceListener>,
     pub pointclouds: Vec<pointcloud::PointCloud2Listener>,
     pub polygons: Vec<polygon::PolygonListener>,
     pub paths: Vec<pose::PathListener>,
+    pub odometries: Vec<pose::OdometryListener>,
+    pub ranges: Vec<range::RangeListener>,
+    pub points: Vec<point::PointStampedListener>,
+    pub imus: Vec<imu::ImuListener>,
+    pub twists: Vec<twist::TwistStampedListener>,
+    pub wrenches: Vec<wrench::WrenchStampedListener>,
+    pub navsat_fixes: Vec<navsat::NavSatFixListener>,
+    pub detections: Vec<detection::Detection3DListener>,
+    pub camera_infos: Vec<camera_info::CameraInfoListener>,
+    pub batteries: Vec<battery::BatteryListener>,
+    pub move_base_statuses: Vec<move_base::MoveBaseStatusListener>,
+    pub fields: Vec<field::FieldListener>,
+    /// Drawn topics, in the default draw order.
+    pub legend: Vec<LegendEntry>,
 }
 
 impl Listeners {
     pub fn new(
-        tf_listener: Arc<rustros_tf::TfListener>,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
-        laser_topics: Vec<ListenerConfigColor>,
+        background_color: Color,
+        laser_topics: Vec<LaserListenerConfig>,
         marker_topics: Vec<ListenerConfig>,
         marker_array_topics: Vec<ListenerConfig>,
-        map_topics: Vec<MapListenerConfig>,
+        mut map_topics: Vec<MapListenerConfig>,
         pose_stamped_topics: Vec<PoseListenerConfig>,
         pose_array_topics: Vec<PoseListenerConfig>,
         pointcloud2_topics: Vec<PointCloud2ListenerConfig>,
         polygon_stamped_topics: Vec<ListenerConfigColor>,
         path_topics: Vec<PoseListenerConfig>,
+        odometry_topics: Vec<OdometryListenerConfig>,
+        range_topics: Vec<ListenerConfigColor>,
+        grid_cells_topics: Vec<ListenerConfigColor>,
+        point_stamped_topics: Vec<PointListenerConfig>,
+        imu_topics: Vec<ImuListenerConfig>,
+        pose_with_covariance_topics: Vec<PoseListenerConfig>,
+        twist_stamped_topics: Vec<VectorListenerConfig>,
+        wrench_stamped_topics: Vec<VectorListenerConfig>,
+        navsat_fix_topics: Vec<NavSatFixListenerConfig>,
+        detection3d_topics: Vec<DetectionListenerConfig>,
+        camera_info_topics: Vec<CameraInfoListenerConfig>,
+        battery_topics: Vec<BatteryListenerConfig>,
+        move_base_status_topics: Vec<MoveBaseStatusListenerConfig>,
+        field_topics: Vec<FieldListenerConfig>,
     ) -> Listeners {
+        // In the default draw order: maps at the bottom, then clouds, markers, scans and poses
+        map_topics.sort_by_key(|c| c.z_order);
+        let mut legend: Vec<LegendEntry> = Vec::new();
+        for c in &map_topics {
+            legend.push(LegendEntry::colored(&c.topic, "OccupancyGrid", &c.color));
+        }
+        for c in &grid_cells_topics {
+            legend.push(LegendEntry::colored(&c.topic, "GridCells", &c.color));
+        }
+        for c in &pointcloud2_topics {
+            let style = if c.use_rgb {
+                "colored by rgb"
+            } else {
+                "colored by height"
+            };
+            legend.push(LegendEntry::new(&c.topic, "PointCloud2", Some(style), None));
+        }
+        for c in &navsat_fix_topics {
+            let style = Some("colored by accuracy");
+            legend.push(LegendEntry::new(&c.topic, "NavSatFix", style, None));
+        }
+        for c in &marker_topics {
+            legend.push(LegendEntry::new(&c.topic, "Marker", None, None));
+        }
+        for c in &marker_array_topics {
+            legend.push(LegendEntry::new(&c.topic, "MarkerArray", None, None));
+        }
+        for c in &detection3d_topics {
+            let style = Some("colored by class");
+            legend.push(LegendEntry::new(&c.topic, "Detection3DArray", style, None));
+        }
+        for c in &laser_topics {
+            legend.push(LegendEntry::colored(&c.topic, "LaserScan", &c.color));
+        }
+        for c in &range_topics {
+            legend.push(LegendEntry::colored(&c.topic, "Range", &c.color));
+        }
+        for c in &camera_info_topics {
+            legend.push(LegendEntry::colored(&c.topic, "CameraInfo", &c.color));
+        }
+        for c in &pose_stamped_topics {
+            let style = Some(c.style.as_str());
+            legend.push(LegendEntry::new(
+                &c.topic,
+                "PoseStamped",
+                style,
+                Some(&c.color),
+            ));
+        }
+        for c in &polygon_stamped_topics {
+            legend.push(LegendEntry::colored(&c.topic, "PolygonStamped", &c.color));
+        }
+        for (topics, kind) in [
+            (&path_topics, "Path"),
+            (&pose_array_topics, "PoseArray"),
+            (&pose_with_covariance_topics, "PoseWithCovarianceStamped"),
+        ] {
+            for c in topics {
+                legend.push(LegendEntry::new(
+                    &c.topic,
+                    kind,
+                    Some(&c.style),
+                    Some(&c.color),
+                ));
+            }
+        }
+        for c in &odometry_topics {
+            legend.push(LegendEntry::colored(&c.topic, "Odometry", &c.color));
+        }
+        for c in &point_stamped_topics {
+            legend.push(LegendEntry::colored(&c.topic, "PointStamped", &c.color));
+        }
+        for c in &imu_topics {
+            legend.push(LegendEntry::colored(&c.topic, "Imu", &c.color));
+        }
+        for (topics, kind) in [
+            (&twist_stamped_topics, "TwistStamped"),
+            (&wrench_stamped_topics, "WrenchStamped"),
+        ] {
+            for c in topics {
+                legend.push(LegendEntry::colored(&c.topic, kind, &c.color));
+            }
+        }
+
         let mut lasers: Vec<laser::LaserListener> = Vec::new();
         for laser_config in laser_topics {
             lasers.push(laser::LaserListener::new(
                 laser_config,
                 tf_listener.clone(),
                 static_frame.clone(),
+                background_color.clone(),
             ));
         }
 
-        let mut markers = marker::MarkersListener::new(tf_listener.clone(), static_frame.clone());
+        let mut markers = marker::MarkersListener::new(
+            tf_listener.clone(),
+            static_frame.clone(),
+            background_color,
+        );
         for marker_config in marker_topics {
             markers.add_marker_listener(&marker_config);
         }
@@ -63,6 +226,15 @@ impl Listeners {
             ));
         }
 
+        let mut grid_cells: Vec<map::GridCellsListener> = Vec::new();
+        for grid_cells_config in grid_cells_topics {
+            grid_cells.push(map::GridCellsListener::new(
+                grid_cells_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
         let mut pointclouds: Vec<pointcloud::PointCloud2Listener> = Vec::new();
         for pc_config in pointcloud2_topics {
             pointclouds.push(pointcloud::PointCloud2Listener::new(
@@ -81,6 +253,69 @@ impl Listeners {
             ));
         }
 
+        let mut ranges: Vec<range::RangeListener> = Vec::new();
+        for range_config in range_topics {
+            ranges.push(range::RangeListener::new(
+                range_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut points: Vec<point::PointStampedListener> = Vec::new();
+        for point_config in point_stamped_topics {
+            points.push(point::PointStampedListener::new(
+                point_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut imus: Vec<imu::ImuListener> = Vec::new();
+        for imu_config in imu_topics {
+            imus.push(imu::ImuListener::new(
+                imu_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut twists: Vec<twist::TwistStampedListener> = Vec::new();
+        for twist_config in twist_stamped_topics {
+            twists.push(twist::TwistStampedListener::new(
+                twist_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut wrenches: Vec<wrench::WrenchStampedListener> = Vec::new();
+        for wrench_config in wrench_stamped_topics {
+            wrenches.push(wrench::WrenchStampedListener::new(
+                wrench_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut detections: Vec<detection::Detection3DListener> = Vec::new();
+        for detection_config in detection3d_topics {
+            detections.push(detection::Detection3DListener::new(
+                detection_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
+        let mut camera_infos: Vec<camera_info::CameraInfoListener> = Vec::new();
+        for camera_info_config in camera_info_topics {
+            camera_infos.push(camera_info::CameraInfoListener::new(
+                camera_info_config,
+                tf_listener.clone(),
+                static_frame.clone(),
+            ));
+        }
+
         let pose_stamped = pose_stamped_topics
             .into_iter()
             .map(|topic| pose::PoseStampedListener::new(topic))
@@ -89,19 +324,82 @@ impl Listeners {
             .into_iter()
             .map(|topic| pose::PoseArrayListener::new(topic))
             .collect();
+        let pose_with_covariance = pose_with_covariance_topics
+            .into_iter()
+            .map(|topic| {
+                pose::PoseWithCovarianceListener::new(
+                    topic,
+                    tf_listener.clone(),
+                    static_frame.clone(),
+                )
+            })
+            .collect();
+        let navsat_fixes = navsat_fix_topics
+            .into_iter()
+            .map(|topic| navsat::NavSatFixListener::new(topic))
+            .collect();
+        let batteries = battery_topics
+            .into_iter()
+            .map(|topic| battery::BatteryListener::new(topic))
+            .collect();
+        let move_base_statuses = move_base_status_topics
+            .into_iter()
+            .map(|topic| move_base::MoveBaseStatusListener::new(topic))
+            .collect();
+        let fields = field_topics
+            .into_iter()
+            .map(|topic| field::FieldListener::new(topic))
+            .collect();
         let paths = path_topics
             .into_iter()
             .map(|topic| pose::PathListener::new(topic))
             .collect();
+        let odometries = odometry_topics
+            .into_iter()
+            .map(|topic| {
+                pose::OdometryListener::new(topic, tf_listener.clone(), static_frame.clone())
+            })
+            .collect();
         Listeners {
             lasers,
             markers,
             maps,
+            grid_cells,
             pose_stamped,
             pose_array,
+            pose_with_covariance,
             pointclouds,
             polygons,
             paths,
+            odometries,
+            ranges,
+            points,
+            imus,
+            twists,
+            wrenches,
+            navsat_fixes,
+            detections,
+            camera_infos,
+            batteries,
+            move_base_statuses,
+            fields,
+            legend,
+        }
+    }
+
+    /// Returns the names of the layers that can be hidden: the drawn topics, with all the markers
+    /// as one layer.
+    pub fn layers(&self) -> Vec<String> {
+        let mut layers: Vec<String> = Vec::new();
+        for entry in &self.legend {
+            let layer = match entry.kind {
+                "Marker" | "MarkerArray" => MARKERS_LAYER.to_string(),
+                _ => entry.topic.clone(),
+            };
+            if !layers.contains(&layer) {
+                layers.push(layer);
+            }
         }
+        layers
     }
 }
@@ -1,16 +1,31 @@
 mod app;
 mod app_modes;
+mod battery;
+mod camera_info;
 mod config;
+mod detection;
+mod field;
 mod footprint;
 mod image;
+mod imu;
 mod laser;
 mod listeners;
 mod map;
 mod marker;
+mod mesh;
+mod move_base;
+mod navsat;
+mod point;
 mod pointcloud;
 mod polygon;
 mod pose;
+mod range;
+mod rosout;
+mod source;
+mod theme;
 mod transformation;
+mod twist;
+mod wrench;
 use futures::{future::FutureExt, select, StreamExt};
 use futures_timer::Delay;
 use std::collections::HashMap;
@@ -26,7 +41,6 @@ use crossterm::{
 };
 use dialoguer::Confirm;
 use rosrust;
-use rustros_tf::TfListener;
 use std::error::Error;
 
 #[tokio::main]
@@ -47,13 +61,30 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .long_help("How long to wait for the robot pose TF on startup, in seconds.")
                 .value_parser(value_parser!(u64)),
         )
+        .arg(
+            Arg::new("bag")
+                .long("bag")
+                .short('b')
+                .action(ArgAction::Append)
+                .long_help("Bag file to read instead of connecting to a ROS master. Can be given multiple times, in which case the bags are played one after the other."),
+        )
         .after_help("More documentation can be found at: https://github.com/carzum/termviz")
         .get_matches();
 
     let conf = config::get_config(matches.get_one("config"))?;
 
-    println!("Connecting to ROS...");
-    rosrust::init("termviz");
+    let bags: Vec<String> = matches
+        .get_many::<String>("bag")
+        .map(|bags| bags.cloned().collect())
+        .unwrap_or_default();
+    let bag_reader = if bags.is_empty() {
+        println!("Connecting to ROS...");
+        rosrust::init("termviz");
+        None
+    } else {
+        println!("Opening bags...");
+        Some(source::open_bags(&bags)?)
+    };
 
     let mut key_to_input: HashMap<KeyCode, String> = conf
         .key_mapping
@@ -61,51 +92,59 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .map(|(v, k)| match k.as_str() {
             "Enter" => (KeyCode::Enter, v.clone()),
             "Esc" => (KeyCode::Esc, v.clone()),
+            "Tab" => (KeyCode::Tab, v.clone()),
+            "BackTab" => (KeyCode::BackTab, v.clone()),
+            "Backspace" => (KeyCode::Backspace, v.clone()),
             _ => (KeyCode::Char(k.chars().next().unwrap()), v.clone()),
         })
         .collect();
-    for i in 0..9 {
+    for i in 1..10 {
         key_to_input.insert(
             KeyCode::Char(std::char::from_digit(i, 10).unwrap()),
             i.to_string(),
         );
     }
+    key_to_input.insert(KeyCode::Char('0'), "10".to_string());
 
     println!("Starting TF listener");
-    let listener = Arc::new(TfListener::new());
-
-    // rustros_tf has no option for a timeout, so we have to do it manually.
-    let mut passed_time = std::time::Duration::ZERO;
-    let max_time = std::time::Duration::from_secs(*matches.get_one::<u64>("tf-wait-time").unwrap());
-    let sleep_time = std::time::Duration::from_millis(100);
-
-    println!("Waiting up to {}s for robot pose...", max_time.as_secs());
-    let robot_pose_available = loop {
-        if listener
-            .lookup_transform(&conf.fixed_frame, &conf.robot_frame, rosrust::Time::new())
-            .is_ok()
-        {
-            break true;
-        }
-        std::thread::sleep(sleep_time);
-        passed_time += sleep_time;
-        if passed_time > max_time {
-            break false;
-        }
-    };
+    let listener = Arc::new(source::TfListener::new());
+
+    // When reading bags, the TF is only available once the playback is started
+    if bag_reader.is_none() {
+        // rustros_tf has no option for a timeout, so we have to do it manually.
+        let mut passed_time = std::time::Duration::ZERO;
+        let max_time =
+            std::time::Duration::from_secs(*matches.get_one::<u64>("tf-wait-time").unwrap());
+        let sleep_time = std::time::Duration::from_millis(100);
+
+        println!("Waiting up to {}s for robot pose...", max_time.as_secs());
+        let robot_pose_available = loop {
+            if listener
+                .lookup_transform(&conf.fixed_frame, &conf.robot_frame, rosrust::Time::new())
+                .is_ok()
+            {
+                break true;
+            }
+            std::thread::sleep(sleep_time);
+            passed_time += sleep_time;
+            if passed_time > max_time {
+                break false;
+            }
+        };
 
-    if !robot_pose_available {
-        println!(
-            "\n{}\n{}",
-            "Robot pose is not being published on TF!".bold().red(),
-            "termviz will display the robot at the origin of the map and you can set the pose from there."
-        );
-        if !Confirm::new()
-            .with_prompt("\nContinue?")
-            .interact()
-            .unwrap()
-        {
-            Err("Aborting.")?;
+        if !robot_pose_available {
+            println!(
+                "\n{}\n{}",
+                "Robot pose is not being published on TF!".bold().red(),
+                "termviz will display the robot at the origin of the map and you can set the pose from there."
+            );
+            if !Confirm::new()
+                .with_prompt("\nContinue?")
+                .interact()
+                .unwrap()
+            {
+                Err("Aborting.")?;
+            }
         }
     }
 
@@ -115,6 +154,11 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
     let default_app_config = Arc::new(Mutex::new(app::App::new(listener.clone(), conf)));
 
+    // The listeners are subscribed, so the messages of the bags can be passed to them
+    if let Some(bag_reader) = bag_reader {
+        bag_reader.play();
+    }
+
     let mut running_app = default_app_config.lock().unwrap();
 
     let mut terminal = running_app.init_terminal().unwrap();
@@ -136,12 +180,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
                         }
                         if let Event::Key(input) = event {
 
-                            if key_to_input.contains_key(&input.code) {
+                            if running_app.is_capturing_text() {
+                                running_app.handle_text_input(input.code);
+                            } else if key_to_input.contains_key(&input.code) {
                                 running_app.handle_input(&key_to_input[&input.code]);
                             } else {
                                 running_app.handle_input(&app_modes::input::UNMAPPED.to_string());
                             }
                         }
+                        if let Event::Mouse(mouse) = event {
+                            running_app.handle_mouse(mouse);
+                        }
 
                     }
                     Some(Err(e)) => println!("Error: {:?}\r", e),
@@ -1,87 +1,808 @@
-use crate::config::MapListenerConfig;
-use crate::transformation;
+use crate::config::{ListenerConfigColor, MapListenerConfig};
+use crate::pointcloud;
+use crate::source;
+use crate::transformation::{self, ros_transform_to_isometry};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::sync::{Arc, RwLock};
 
 use nalgebra::geometry::{Isometry3, Point3, Quaternion, Translation3, UnitQuaternion};
 
 use rosrust;
-use rustros_tf;
+use tui::style::Color;
+
+/// Highest binning level of the points, at which bins are made of 2^n cells in each direction.
+const MAX_BINNING_LEVEL: u32 = 16;
+
+/// Number of bins along each side of the tiles by which the points are culled.
+const TILE_BINS: u32 = 64;
+
+/// Returns the pose of the cell (0, 0) of the map in the frame of the map.
+fn origin_isometry(map: &rosrust_msg::nav_msgs::OccupancyGrid) -> Isometry3<f64> {
+    let tra = Translation3::new(
+        map.info.origin.position.x,
+        map.info.origin.position.y,
+        map.info.origin.position.z,
+    );
+    let rot = UnitQuaternion::new_normalize(Quaternion::new(
+        map.info.origin.orientation.w,
+        map.info.origin.orientation.x,
+        map.info.origin.orientation.y,
+        map.info.origin.orientation.z,
+    ));
+    Isometry3::from_parts(tra, rot)
+}
+
+/// Returns the occupied cells of the map, with a value between min and max, by column and line.
+fn occupied_cells(map: &rosrust_msg::nav_msgs::OccupancyGrid, min: i8, max: i8) -> Vec<(u32, u32)> {
+    let width = map.info.width as usize;
+    map.data
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| min <= **value && **value <= max)
+        .map(|(i, _)| ((i % width) as u32, (i / width) as u32))
+        .collect()
+}
+
+/// Occupancy probabilities above which a cell is saved as occupied, and below which it is saved
+/// as free, as map_server reads them back.
+const OCCUPIED_THRESHOLD: f64 = 0.65;
+const FREE_THRESHOLD: f64 = 0.196;
+
+/// Writes the map to `<basename>.pgm` and `<basename>.yaml` in the format of map_server.
+fn write_map(map: &rosrust_msg::nav_msgs::OccupancyGrid, basename: &str) -> io::Result<()> {
+    let (width, height) = (map.info.width as usize, map.info.height as usize);
+    let mut pgm = BufWriter::new(File::create(format!("{}.pgm", basename))?);
+    write!(
+        pgm,
+        "P5\n# CREATOR: termviz {:.3} m/pix\n{} {}\n255\n",
+        map.info.resolution, width, height
+    )?;
+    // The image starts with the top line, the last of the grid
+    for line in (0..height).rev() {
+        let pixels: Vec<u8> = map.data[line * width..(line + 1) * width]
+            .iter()
+            .map(|&value| {
+                if value < 0 {
+                    205
+                } else if value as f64 >= OCCUPIED_THRESHOLD * 100.0 {
+                    0
+                } else if value as f64 <= FREE_THRESHOLD * 100.0 {
+                    254
+                } else {
+                    205
+                }
+            })
+            .collect();
+        pgm.write_all(&pixels)?;
+    }
+    pgm.flush()?;
+
+    let origin = &map.info.origin.position;
+    let (_, _, yaw) = origin_isometry(map).rotation.euler_angles();
+    let mut yaml = File::create(format!("{}.yaml", basename))?;
+    write!(
+        yaml,
+        "image: {}.pgm\nresolution: {:.6}\norigin: [{:.6}, {:.6}, {:.6}]\nnegate: 0\noccupied_thresh: {}\nfree_thresh: {}\n",
+        basename,
+        map.info.resolution,
+        origin.x,
+        origin.y,
+        yaw,
+        OCCUPIED_THRESHOLD,
+        FREE_THRESHOLD
+    )
+}
+
+/// Returns true if the transforms are the same, within rounding errors.
+fn same_transform(
+    a: &rosrust_msg::geometry_msgs::Transform,
+    b: &rosrust_msg::geometry_msgs::Transform,
+) -> bool {
+    const EPSILON: f64 = 1e-6;
+    [
+        a.translation.x - b.translation.x,
+        a.translation.y - b.translation.y,
+        a.translation.z - b.translation.z,
+        a.rotation.x - b.rotation.x,
+        a.rotation.y - b.rotation.y,
+        a.rotation.z - b.rotation.z,
+        a.rotation.w - b.rotation.w,
+    ]
+    .iter()
+    .all(|d| d.abs() < EPSILON)
+}
+
+/// Copies the patch of an update into the map. Returns false if the patch doesn't fit the map.
+fn apply_update(
+    map: &mut rosrust_msg::nav_msgs::OccupancyGrid,
+    update: &rosrust_msg::map_msgs::OccupancyGridUpdate,
+) -> bool {
+    // The sums and product are computed in u64 so that malformed updates can't overflow
+    if update.x < 0
+        || update.y < 0
+        || update.x as u64 + update.width as u64 > map.info.width as u64
+        || update.y as u64 + update.height as u64 > map.info.height as u64
+        || update.data.len() as u64 != update.width as u64 * update.height as u64
+        || map.data.len() as u64 != map.info.width as u64 * map.info.height as u64
+    {
+        return false;
+    }
+    for row in 0..update.height as usize {
+        let map_start = (update.y as usize + row) * map.info.width as usize + update.x as usize;
+        let update_start = row * update.width as usize;
+        map.data[map_start..map_start + update.width as usize]
+            .copy_from_slice(&update.data[update_start..update_start + update.width as usize]);
+    }
+    true
+}
+
+/// Returns the lowest and highest column and line of the cells, if any.
+fn cells_bounds(cells: &[(u32, u32)]) -> Option<((u32, u32), (u32, u32))> {
+    cells.iter().fold(None, |bounds, &(column, line)| {
+        let ((column_min, line_min), (column_max, line_max)) =
+            bounds.unwrap_or(((column, line), (column, line)));
+        Some((
+            (column_min.min(column), line_min.min(line)),
+            (column_max.max(column), line_max.max(line)),
+        ))
+    })
+}
+
+/// Returns the highest value between min and max of the cells of a bin of 2^level cells in
+/// each direction, if any of them is occupied.
+fn bin_value(
+    map: &rosrust_msg::nav_msgs::OccupancyGrid,
+    min: i8,
+    max: i8,
+    level: u32,
+    (column, line): (u32, u32),
+) -> Option<i8> {
+    let (width, height) = (map.info.width, map.info.height);
+    let mut highest: Option<i8> = None;
+    for l in (line << level)..((line + 1) << level).min(height) {
+        for c in (column << level)..((column + 1) << level).min(width) {
+            let value = map.data[(l * width + c) as usize];
+            if min <= value && value <= max {
+                highest = Some(highest.map_or(value, |v| v.max(value)));
+            }
+        }
+    }
+    highest
+}
+
+/// Returns the position in the static frame of a bin of 2^level cells in each direction, at the
+/// middle of the corners of its cells.
+fn bin_point(
+    isometry: &Isometry3<f64>,
+    resolution: f64,
+    level: u32,
+    (column, line): (u32, u32),
+) -> (f64, f64) {
+    let middle = ((1u32 << level) - 1) as f64 / 2.0;
+    let pt = isometry.transform_point(&Point3::new(
+        ((column << level) as f64 + middle) * resolution,
+        ((line << level) as f64 + middle) * resolution,
+        0.,
+    ));
+    (pt.x, pt.y)
+}
+
+/// Occupied points of a binning level in the static frame with their value, by bin, grouped by
+/// tiles of TILE_BINS bins along each side, so that only the visible ones are drawn.
+#[derive(Clone)]
+struct BinnedPoints {
+    tiles: HashMap<(u32, u32), HashMap<(u32, u32), ((f64, f64), i8)>>,
+}
+
+/// State shared between the map and the map updates callbacks.
+struct MapData {
+    grid: Option<rosrust_msg::nav_msgs::OccupancyGrid>,
+    /// Occupied cells of the grid, by column and line.
+    occupied: Vec<(u32, u32)>,
+    /// Lowest and highest column and line of the occupied cells, if any.
+    bounds: Option<((u32, u32), (u32, u32))>,
+    /// Transform from the frame of the map to the static frame with which the points are
+    /// projected, if it is known.
+    transform: Option<rosrust_msg::geometry_msgs::Transform>,
+    /// Occupied points in the static frame with their value, by binning level. At level n, the
+    /// cells are merged into bins of 2^n cells in each direction, of which only the occupied
+    /// ones are kept with the highest value of their cells.
+    points: HashMap<u32, Arc<BinnedPoints>>,
+    tf_listener: Arc<source::TfListener>,
+    static_frame: String,
+    min: i8,
+    max: i8,
+}
+
+impl MapData {
+    /// Recomputes the occupied cells after the grid was received or updated.
+    fn update_cells(&mut self) {
+        let map = match self.grid.as_ref() {
+            Some(map) => map,
+            None => return,
+        };
+        self.occupied = occupied_cells(map, self.min, self.max);
+        self.bounds = cells_bounds(&self.occupied);
+        self.points.clear();
+    }
+
+    /// Recomputes the occupied cells and the projected bins inside the given window of the grid,
+    /// after an update was copied into it.
+    fn update_window(&mut self, column: u32, line: u32, width: u32, height: u32) {
+        let map = match self.grid.as_ref() {
+            Some(map) => map,
+            None => return,
+        };
+        if width == 0 || height == 0 {
+            return;
+        }
+        let (min, max) = (self.min, self.max);
+        let map_width = map.info.width;
+        self.occupied
+            .retain(|&(c, l)| c < column || c >= column + width || l < line || l >= line + height);
+        for l in line..line + height {
+            for c in column..column + width {
+                let value = map.data[(l * map_width + c) as usize];
+                if min <= value && value <= max {
+                    self.occupied.push((c, l));
+                }
+            }
+        }
+        self.bounds = cells_bounds(&self.occupied);
+        // Bins are only projected once the transform is known
+        let isometry = match self.cells_isometry() {
+            Some(isometry) => isometry,
+            None => return,
+        };
+        let resolution = map.info.resolution as f64;
+        for (&level, points) in self.points.iter_mut() {
+            let points = Arc::make_mut(points);
+            for bin_line in (line >> level)..=((line + height - 1) >> level) {
+                for bin_column in (column >> level)..=((column + width - 1) >> level) {
+                    let bin = (bin_column, bin_line);
+                    let tile = (bin_column / TILE_BINS, bin_line / TILE_BINS);
+                    match bin_value(map, min, max, level, bin) {
+                        Some(value) => {
+                            let pt = bin_point(&isometry, resolution, level, bin);
+                            points
+                                .tiles
+                                .entry(tile)
+                                .or_default()
+                                .insert(bin, (pt, value));
+                        }
+                        None => {
+                            if let Some(tile) = points.tiles.get_mut(&tile) {
+                                tile.remove(&bin);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Looks up the latest transform of the map, and drops the projected points if it moved.
+    fn refresh(&mut self) {
+        let map = match self.grid.as_ref() {
+            Some(map) => map,
+            None => return,
+        };
+        let transform = match self.tf_listener.lookup_transform(
+            &self.static_frame,
+            &map.header.frame_id,
+            rosrust::Time::new(),
+        ) {
+            Ok(res) => res.transform,
+            Err(_e) => return,
+        };
+        let moved = self
+            .transform
+            .as_ref()
+            .map_or(true, |previous| !same_transform(previous, &transform));
+        if moved {
+            self.transform = Some(transform);
+            self.points.clear();
+        }
+    }
+
+    /// Returns the transform from the cells of the map, scaled by the resolution, to the static
+    /// frame.
+    fn cells_isometry(&self) -> Option<Isometry3<f64>> {
+        let map = self.grid.as_ref()?;
+        let transform = self.transform.as_ref()?;
+        Some(ros_transform_to_isometry(transform) * origin_isometry(map))
+    }
+
+    /// Returns the occupied points at the given binning level, projecting them the first time.
+    fn get_binned_points(&mut self, level: u32) -> Option<Arc<BinnedPoints>> {
+        if let Some(points) = self.points.get(&level) {
+            return Some(points.clone());
+        }
+        let map = self.grid.as_ref()?;
+        let isometry = self.cells_isometry()?;
+        let resolution = map.info.resolution as f64;
+        let width = map.info.width as usize;
+        let cell_value =
+            |&(column, line): &(u32, u32)| map.data[line as usize * width + column as usize];
+        let bins: Vec<((u32, u32), i8)> = if level == 0 {
+            self.occupied
+                .iter()
+                .map(|cell| (*cell, cell_value(cell)))
+                .collect()
+        } else {
+            let mut bins: HashMap<(u32, u32), i8> = HashMap::new();
+            for cell in self.occupied.iter() {
+                let bin = bins
+                    .entry((cell.0 >> level, cell.1 >> level))
+                    .or_insert(i8::MIN);
+                *bin = (*bin).max(cell_value(cell));
+            }
+            bins.into_iter().collect()
+        };
+        let mut tiles: HashMap<(u32, u32), HashMap<(u32, u32), ((f64, f64), i8)>> = HashMap::new();
+        for (bin, value) in bins {
+            let pt = bin_point(&isometry, resolution, level, bin);
+            tiles
+                .entry((bin.0 / TILE_BINS, bin.1 / TILE_BINS))
+                .or_default()
+                .insert(bin, (pt, value));
+        }
+        let points = Arc::new(BinnedPoints { tiles });
+        self.points.insert(level, points.clone());
+        Some(points)
+    }
+
+    /// Returns the occupied points at the given binning level with their value, only the ones
+    /// of the tiles overlapping the given area of the static frame if any.
+    fn get_points(
+        &mut self,
+        level: u32,
+        area: Option<([f64; 2], [f64; 2])>,
+    ) -> Vec<((f64, f64), i8)> {
+        let points = match self.get_binned_points(level) {
+            Some(points) => points,
+            None => return Vec::new(),
+        };
+        let range = area.and_then(|area| self.tile_range(level, area));
+        points
+            .tiles
+            .iter()
+            .filter(|(tile, _)| {
+                range.map_or(true, |((column_min, line_min), (column_max, line_max))| {
+                    column_min <= tile.0
+                        && tile.0 <= column_max
+                        && line_min <= tile.1
+                        && tile.1 <= line_max
+                })
+            })
+            .flat_map(|(_, points)| points.values().cloned())
+            .collect()
+    }
+
+    /// Returns the lowest and highest tiles of the binning level overlapping the given area of
+    /// the static frame, by column and line.
+    fn tile_range(
+        &self,
+        level: u32,
+        ([x_min, x_max], [y_min, y_max]): ([f64; 2], [f64; 2]),
+    ) -> Option<((u32, u32), (u32, u32))> {
+        let map = self.grid.as_ref()?;
+        let isometry = self.cells_isometry()?;
+        let tile_size = map.info.resolution as f64 * (TILE_BINS << level) as f64;
+        let (mut column_min, mut column_max) = (f64::MAX, f64::MIN);
+        let (mut line_min, mut line_max) = (f64::MAX, f64::MIN);
+        for &(x, y) in [
+            (x_min, y_min),
+            (x_max, y_min),
+            (x_min, y_max),
+            (x_max, y_max),
+        ]
+        .iter()
+        {
+            let pt = isometry.inverse_transform_point(&Point3::new(x, y, 0.));
+            column_min = column_min.min(pt.x / tile_size);
+            column_max = column_max.max(pt.x / tile_size);
+            line_min = line_min.min(pt.y / tile_size);
+            line_max = line_max.max(pt.y / tile_size);
+        }
+        // Points are drawn at the middle of their bin, which stays in its tile
+        Some((
+            (
+                column_min.floor().max(0.0) as u32,
+                line_min.floor().max(0.0) as u32,
+            ),
+            (
+                column_max.floor().max(0.0) as u32,
+                line_max.floor().max(0.0) as u32,
+            ),
+        ))
+    }
+
+    /// Returns the horizontal and vertical bounds of the occupied cells in the static frame.
+    fn get_extent(&self) -> Option<([f64; 2], [f64; 2])> {
+        let map = self.grid.as_ref()?;
+        let isometry = self.cells_isometry()?;
+        let resolution = map.info.resolution as f64;
+        let ((column_min, line_min), (column_max, line_max)) = self.bounds?;
+        let mut extent: Option<([f64; 2], [f64; 2])> = None;
+        for &(column, line) in [
+            (column_min, line_min),
+            (column_max, line_min),
+            (column_min, line_max),
+            (column_max, line_max),
+        ]
+        .iter()
+        {
+            let pt = isometry.transform_point(&Point3::new(
+                column as f64 * resolution,
+                line as f64 * resolution,
+                0.,
+            ));
+            let ([x_min, x_max], [y_min, y_max]) = extent.unwrap_or(([pt.x, pt.x], [pt.y, pt.y]));
+            extent = Some((
+                [x_min.min(pt.x), x_max.max(pt.x)],
+                [y_min.min(pt.y), y_max.max(pt.y)],
+            ));
+        }
+        extent
+    }
+
+    /// Sets the cells whose center is within the radius of the given point of the static frame,
+    /// and the cell containing it, to the value. Returns false if the point is not on the map.
+    fn paint(&mut self, point: (f64, f64), radius: f64, value: i8) -> bool {
+        let isometry = match self.cells_isometry() {
+            Some(isometry) => isometry,
+            None => return false,
+        };
+        let map = self.grid.as_mut().unwrap();
+        let cell = isometry.inverse_transform_point(&Point3::new(point.0, point.1, 0.));
+        let resolution = map.info.resolution as f64;
+        let (column, line) = (cell[0] / resolution, cell[1] / resolution);
+        let (width, height) = (map.info.width as i64, map.info.height as i64);
+        let (center_column, center_line) = (column.floor() as i64, line.floor() as i64);
+        if center_column < 0 || center_line < 0 || center_column >= width || center_line >= height {
+            return false;
+        }
+        let reach = (radius / resolution).ceil() as i64;
+        for l in (center_line - reach).max(0)..=(center_line + reach).min(height - 1) {
+            for c in (center_column - reach).max(0)..=(center_column + reach).min(width - 1) {
+                let distance = (c as f64 + 0.5 - column).hypot(l as f64 + 0.5 - line) * resolution;
+                if (c == center_column && l == center_line) || distance <= radius {
+                    map.data[(l * width + c) as usize] = value;
+                }
+            }
+        }
+        self.update_cells();
+        true
+    }
+
+    /// Returns the value of the cell containing the given point of the static frame.
+    fn get_value(&self, x: f64, y: f64) -> Option<i8> {
+        self.get_values(&[(x, y)])?.pop()?
+    }
+
+    /// Returns the values of the cells containing the given points of the static frame, or
+    /// nothing for the points outside the map. The cells are found with the transform with
+    /// which the points are drawn.
+    fn get_values(&self, points: &[(f64, f64)]) -> Option<Vec<Option<i8>>> {
+        let map = self.grid.as_ref()?;
+        let isometry = self.cells_isometry()?;
+        let values = points
+            .iter()
+            .map(|&(x, y)| {
+                let cell = isometry.inverse_transform_point(&Point3::new(x, y, 0.));
+                let column = (cell[0] / map.info.resolution as f64).floor();
+                let line = (cell[1] / map.info.resolution as f64).floor();
+                if column < 0. || line < 0. {
+                    return None;
+                }
+                let (column, line) = (column as usize, line as usize);
+                if column >= map.info.width as usize || line >= map.info.height as usize {
+                    return None;
+                }
+                map.data
+                    .get(line * map.info.width as usize + column)
+                    .copied()
+            })
+            .collect();
+        Some(values)
+    }
+
+    /// Returns the distance from each of the given points of the static frame to the center of
+    /// the closest occupied cell, up to the max distance.
+    fn get_distances(&self, points: &[(f64, f64)], max_distance: f64) -> Option<Vec<f64>> {
+        let map = self.grid.as_ref()?;
+        let isometry = self.cells_isometry()?;
+        let resolution = map.info.resolution as f64;
+        let (width, height) = (map.info.width as i64, map.info.height as i64);
+        // Cells farther than the max distance in any direction are not searched
+        let reach = (max_distance / resolution).ceil() as i64;
+        let distances = points
+            .iter()
+            .map(|&(x, y)| {
+                let cell = isometry.inverse_transform_point(&Point3::new(x, y, 0.));
+                let (column, line) = (cell[0] / resolution, cell[1] / resolution);
+                let mut closest = max_distance;
+                for l in (line.floor() as i64 - reach)..=(line.floor() as i64 + reach) {
+                    for c in (column.floor() as i64 - reach)..=(column.floor() as i64 + reach) {
+                        if l < 0 || c < 0 || l >= height || c >= width {
+                            continue;
+                        }
+                        let value = map.data[(l * width + c) as usize];
+                        if self.min <= value && value <= self.max {
+                            let distance =
+                                (c as f64 + 0.5 - column).hypot(l as f64 + 0.5 - line) * resolution;
+                            closest = closest.min(distance);
+                        }
+                    }
+                }
+                closest
+            })
+            .collect();
+        Some(distances)
+    }
+}
 
 pub struct MapListener {
     pub config: MapListenerConfig,
-    pub points: Arc<RwLock<Vec<(f64, f64)>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    data: Arc<RwLock<MapData>>,
+    /// Colormap of the values of the cells, if they are drawn with a gradient.
+    gradient: Option<colorgrad::Gradient>,
+    _tf_listener: Arc<source::TfListener>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
+    _updates_subscriber: Option<source::Subscriber>,
 }
 
 impl MapListener {
     pub fn new(
         config: MapListenerConfig,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
     ) -> MapListener {
-        let occ_points = Arc::new(RwLock::new(Vec::<(f64, f64)>::new()));
-        let cb_occ_points = occ_points.clone();
+        let data = Arc::new(RwLock::new(MapData {
+            grid: None,
+            occupied: Vec::new(),
+            bounds: None,
+            transform: None,
+            points: HashMap::new(),
+            tf_listener: tf_listener.clone(),
+            static_frame: static_frame.clone(),
+            min: config.min,
+            max: config.max,
+        }));
+
+        let cb_data = data.clone();
+        let _map_sub = source::subscribe(
+            &config.topic,
+            1,
+            move |map: rosrust_msg::nav_msgs::OccupancyGrid| {
+                let mut data = cb_data.write().unwrap();
+                data.grid = Some(map);
+                data.update_cells();
+            },
+        )
+        .unwrap();
+
+        let _updates_sub = if config.subscribe_to_updates {
+            let cb_data = data.clone();
+            Some(
+                source::subscribe(
+                    &(config.topic.clone() + "_updates"),
+                    10,
+                    move |update: rosrust_msg::map_msgs::OccupancyGridUpdate| {
+                        let mut data = cb_data.write().unwrap();
+                        let applied = match data.grid.as_mut() {
+                            Some(map) => apply_update(map, &update),
+                            None => false,
+                        };
+                        // Only the cells and bins inside the patch are recomputed
+                        if applied {
+                            data.update_window(
+                                update.x as u32,
+                                update.y as u32,
+                                update.width,
+                                update.height,
+                            );
+                        }
+                    },
+                )
+                .unwrap(),
+            )
+        } else {
+            None
+        };
+
+        let gradient = if config.gradient {
+            Some(pointcloud::gradient(&config.colormap))
+        } else {
+            None
+        };
+        MapListener {
+            config,
+            data,
+            gradient,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: _map_sub,
+            _updates_subscriber: _updates_sub,
+        }
+    }
+
+    /// Re-projects the map if its transform to the static frame changed. Called on every tick.
+    pub fn refresh(&self) {
+        self.data.write().unwrap().refresh();
+    }
+
+    /// Returns the occupied points of the map in the static frame, merged into bins no larger
+    /// than the given size, so that no more points are drawn than the canvas can show. Given
+    /// the visible area of the static frame, mostly the points inside it are returned.
+    /// The points are grouped by color: the one of the config, or the one of their value
+    /// through the colormap from min to max if the map is drawn with a gradient.
+    pub fn get_points(
+        &self,
+        bin_size: f64,
+        area: Option<([f64; 2], [f64; 2])>,
+    ) -> Vec<(Vec<(f64, f64)>, Color)> {
+        let points = self.get_valued_points(bin_size, area);
+        let gradient = match &self.gradient {
+            Some(gradient) => gradient,
+            None => {
+                let points = points.into_iter().map(|(pt, _)| pt).collect();
+                return vec![(points, self.config.color.to_tui())];
+            }
+        };
+        let mut by_value: HashMap<i8, Vec<(f64, f64)>> = HashMap::new();
+        for (pt, value) in points {
+            by_value.entry(value).or_default().push(pt);
+        }
+        let (min, max) = (self.config.min as f64, self.config.max as f64);
+        let mut groups: Vec<(i8, Vec<(f64, f64)>)> = by_value.into_iter().collect();
+        // Higher values are drawn over the lower ones
+        groups.sort_by_key(|(value, _)| *value);
+        groups
+            .into_iter()
+            .map(|(value, points)| {
+                let t = if max > min {
+                    ((value as f64 - min) / (max - min)).max(0.0).min(1.0)
+                } else {
+                    0.5
+                };
+                let c = gradient.at(t).to_rgba8();
+                (points, Color::Rgb(c[0], c[1], c[2]))
+            })
+            .collect()
+    }
+
+    /// Returns the occupied points of the map with the highest value of the cells of their bin.
+    fn get_valued_points(
+        &self,
+        bin_size: f64,
+        area: Option<([f64; 2], [f64; 2])>,
+    ) -> Vec<((f64, f64), i8)> {
+        let mut data = self.data.write().unwrap();
+        let resolution = data
+            .grid
+            .as_ref()
+            .map_or(0.0, |map| map.info.resolution as f64);
+        let level = if resolution > 0.0 && bin_size > resolution {
+            ((bin_size / resolution).log2().floor() as u32).min(MAX_BINNING_LEVEL)
+        } else {
+            0
+        };
+        data.get_points(level, area)
+    }
+
+    /// Returns the horizontal and vertical bounds of the occupied cells in the static frame.
+    pub fn extent(&self) -> Option<([f64; 2], [f64; 2])> {
+        self.data.read().unwrap().get_extent()
+    }
+
+    /// Sets the cells of the grid around the given point of the static frame to the value, until
+    /// the next map is received. Returns false if the point is not on the map.
+    pub fn paint(&self, point: (f64, f64), radius: f64, value: i8) -> bool {
+        self.data.write().unwrap().paint(point, radius, value)
+    }
+
+    /// Returns a copy of the grid with the cells painted since it was received, if any.
+    pub fn grid(&self) -> Option<rosrust_msg::nav_msgs::OccupancyGrid> {
+        self.data.read().unwrap().grid.clone()
+    }
+
+    /// Writes the latest received grid to `<basename>.pgm` and `<basename>.yaml` in the format
+    /// of map_server.
+    pub fn save(&self, basename: &str) -> io::Result<()> {
+        match self.data.read().unwrap().grid.as_ref() {
+            Some(map) => write_map(map, basename),
+            None => Err(io::Error::new(io::ErrorKind::Other, "no map received")),
+        }
+    }
+
+    /// Returns the occupancy value of the map at the given point of the static frame,
+    /// if the point is inside the map.
+    pub fn value_at(&self, x: f64, y: f64) -> Option<i8> {
+        self.data.read().unwrap().get_value(x, y)
+    }
+
+    /// Returns whether the cells of the map containing the given points of the static frame
+    /// are free, i.e. known and below the drawn values, if the map was received.
+    pub fn free_at(&self, points: &[(f64, f64)]) -> Option<Vec<bool>> {
+        let data = self.data.read().unwrap();
+        let values = data.get_values(points)?;
+        Some(
+            values
+                .into_iter()
+                .map(|value| value.map_or(false, |value| 0 <= value && value < data.min))
+                .collect(),
+        )
+    }
+
+    /// Returns the distance from each of the given points of the static frame to the closest
+    /// occupied cell of the map, up to the max distance, if the map was received.
+    pub fn distances_to_occupied(
+        &self,
+        points: &[(f64, f64)],
+        max_distance: f64,
+    ) -> Option<Vec<f64>> {
+        self.data
+            .read()
+            .unwrap()
+            .get_distances(points, max_distance)
+    }
+}
+
+pub struct GridCellsListener {
+    pub config: ListenerConfigColor,
+    pub points: Arc<RwLock<Vec<(f64, f64)>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl GridCellsListener {
+    pub fn new(
+        config: ListenerConfigColor,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> GridCellsListener {
+        let cell_points = Arc::new(RwLock::new(Vec::<(f64, f64)>::new()));
+        let cb_cell_points = cell_points.clone();
         let str_ = static_frame.clone();
         let local_listener = tf_listener.clone();
-        let threshold = config.threshold.clone();
-        let _map_sub = rosrust::subscribe(
+        let _cells_sub = source::subscribe(
             &config.topic,
             1,
-            move |map: rosrust_msg::nav_msgs::OccupancyGrid| {
-                let mut points: Vec<(f64, f64)> = Vec::new();
+            move |cells: rosrust_msg::nav_msgs::GridCells| {
                 let res = local_listener.clone().lookup_transform(
                     &str_,
-                    &map.header.frame_id,
-                    map.header.stamp,
+                    &cells.header.frame_id,
+                    cells.header.stamp,
                 );
-                match &res {
-                    Ok(res) => res,
+                let transform = match &res {
+                    Ok(res) => &res.transform,
                     Err(_e) => return,
                 };
 
-                let tra = Translation3::new(
-                    map.info.origin.position.x,
-                    map.info.origin.position.y,
-                    map.info.origin.position.z,
-                );
-                let rot = UnitQuaternion::new_normalize(Quaternion::new(
-                    map.info.origin.orientation.w,
-                    map.info.origin.orientation.x,
-                    map.info.origin.orientation.y,
-                    map.info.origin.orientation.z,
-                ));
-                let isometry = Isometry3::from_parts(tra, rot);
-
-                for (i, pt) in map.data.iter().enumerate() {
-                    let line = i / map.info.width as usize;
-                    let column = i - line * map.info.width as usize;
-                    if pt >= &threshold {
-                        let trans_point = isometry.transform_point(&Point3::new(
-                            (column as f64) * map.info.resolution as f64,
-                            line as f64 * map.info.resolution as f64,
-                            0.,
-                        ));
-                        let global_point = transformation::transform_relative_pt(
-                            &res.as_ref().unwrap().transform,
-                            (trans_point[0], trans_point[1]),
-                        );
-                        points.push(global_point);
-                    }
-                }
-                let mut cb_occ_points = cb_occ_points.write().unwrap();
-                *cb_occ_points = points;
+                // Cells are given by their center
+                let points: Vec<(f64, f64)> = cells
+                    .cells
+                    .iter()
+                    .map(|cell| transformation::transform_relative_pt(transform, (cell.x, cell.y)))
+                    .collect();
+                let mut cb_cell_points = cb_cell_points.write().unwrap();
+                *cb_cell_points = points;
             },
         )
         .unwrap();
 
-        MapListener {
+        GridCellsListener {
             config,
-            points: occ_points,
+            points: cell_points,
             _tf_listener: tf_listener,
             _static_frame: static_frame.to_string(),
-            _subscriber: _map_sub,
+            _subscriber: _cells_sub,
         }
     }
 }
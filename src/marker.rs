@@ -3,23 +3,51 @@
 //! ROS has a type of message dedicated to visualization: visualization_msgs::Marker.
 //! This module allows to subsribe to topics that publish them and project them into the
 //! 2D plane. Finally, it takes care of their lifecycle: ADD, DELETE and timeout.
-use crate::config::ListenerConfig;
+//! Markers with a lifetime store the time at which they expire, and are removed
+//! when the markers are collected for rendering.
+use crate::config::{Color as ConfigColor, ListenerConfig, ZRange};
+use crate::mesh::{convex_hull, load_mesh_vertices};
+use crate::source;
 use nalgebra::base::Vector3;
 use nalgebra::geometry::Isometry3;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::f64::consts::PI;
 use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
 
-use rosrust;
 use rustros_tf::transforms::nalgebra::geometry::Point3;
 use rustros_tf::transforms::{isometry_from_pose, isometry_from_transform};
 
 use tui::style::Color;
 use tui::widgets::canvas::Line;
 
+/// A text label, positioned in the static frame.
+#[derive(Clone)]
+pub struct MarkerText {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub text: String,
+    pub color: Color,
+}
+
+/// Lines of a marker lying at a single height, e.g. one element of a list marker,
+/// so that they can be filtered by height.
+struct MarkerPart {
+    z: f64,
+    lines: Vec<Line>,
+}
+
 struct TermvizMarker {
-    pub lines: Vec<Line>,
+    pub parts: Vec<MarkerPart>,
+    pub texts: Vec<MarkerText>,
+    /// Height of the origin of the marker, used to filter its texts.
+    pub z: f64,
     pub id: i32,
+    /// Topic on which the marker was received, whose z range applies.
+    pub topic: String,
+    /// Time after which the marker is deleted, None if it never expires.
+    pub expiry: Option<Instant>,
 }
 
 /// Creates a list of lines from N line strips.
@@ -58,7 +86,7 @@ fn from_point_strips(strips: &Vec<Vec<Point3<f64>>>, color: &Color) -> Vec<Line>
 /// - `offset`: Offset of the center of the cube in the iso transformation.
 /// - `color`: Color of the cube.
 /// - `iso`: Base transformation of the cube.
-fn parse_cube(
+pub fn parse_cube(
     dimension: &rosrust_msg::geometry_msgs::Vector3,
     offset: &rosrust_msg::geometry_msgs::Point,
     color: &tui::style::Color,
@@ -300,73 +328,155 @@ fn parse_cube_list_msg(
     msg: &rosrust_msg::visualization_msgs::Marker,
     color: &tui::style::Color,
     iso: &Isometry3<f64>,
-) -> Vec<Line> {
-    let mut lines = Vec::new();
+) -> Vec<MarkerPart> {
+    let mut parts = Vec::new();
 
     for point in msg.points.iter() {
-        lines.extend(parse_cube(&msg.scale, &point, color, iso));
+        parts.push(MarkerPart {
+            z: iso
+                .transform_point(&Point3::new(point.x, point.y, point.z))
+                .z,
+            lines: parse_cube(&msg.scale, &point, color, iso),
+        });
     }
 
-    lines
+    parts
 }
 
 fn parse_points_msg(
     msg: &rosrust_msg::visualization_msgs::Marker,
     color: &tui::style::Color,
     iso: &Isometry3<f64>,
-) -> Vec<Line> {
+) -> Vec<MarkerPart> {
     return parse_cube_list_msg(msg, color, iso);
 }
 
+/// Emulates transparency by blending a color toward the background color.
+fn blend_color(r: f32, g: f32, b: f32, a: f32, background: &ConfigColor) -> Color {
+    let a = a.max(0.0).min(1.0);
+    let blend = |c: f32, bg: u8| (c.max(0.0).min(1.0) * 255.0 * a + bg as f32 * (1.0 - a)) as u8;
+    Color::Rgb(
+        blend(r, background.r),
+        blend(g, background.g),
+        blend(b, background.b),
+    )
+}
+
+fn to_tui_color(color: &rosrust_msg::std_msgs::ColorRGBA, background: &ConfigColor) -> Color {
+    blend_color(color.r, color.g, color.b, color.a, background)
+}
+
+/// Number of sub-segments used to render a color gradient along a line.
+const GRADIENT_STEPS: usize = 4;
+
+/// Creates the lines between two points, interpolating the color between the two ends.
+fn gradient_lines(
+    p1: &Point3<f64>,
+    p2: &Point3<f64>,
+    c1: &rosrust_msg::std_msgs::ColorRGBA,
+    c2: &rosrust_msg::std_msgs::ColorRGBA,
+    background: &ConfigColor,
+) -> Vec<Line> {
+    let steps = if c1 == c2 { 1 } else { GRADIENT_STEPS };
+    (0..steps)
+        .map(|i| {
+            let t1 = i as f64 / steps as f64;
+            let t2 = (i + 1) as f64 / steps as f64;
+            // Use the color in the middle of the sub-segment
+            let t = ((t1 + t2) / 2.0) as f32;
+            let color = blend_color(
+                c1.r + (c2.r - c1.r) * t,
+                c1.g + (c2.g - c1.g) * t,
+                c1.b + (c2.b - c1.b) * t,
+                c1.a + (c2.a - c1.a) * t,
+                background,
+            );
+            Line {
+                x1: p1.x + (p2.x - p1.x) * t1,
+                y1: p1.y + (p2.y - p1.y) * t1,
+                x2: p1.x + (p2.x - p1.x) * t2,
+                y2: p1.y + (p2.y - p1.y) * t2,
+                color,
+            }
+        })
+        .collect()
+}
+
+/// Creates the lines of a segment of a line strip or list, which lies at the height of its middle.
+fn segment_part(
+    p1: &Point3<f64>,
+    p2: &Point3<f64>,
+    colors: Option<(
+        &rosrust_msg::std_msgs::ColorRGBA,
+        &rosrust_msg::std_msgs::ColorRGBA,
+    )>,
+    color: &tui::style::Color,
+    background: &ConfigColor,
+) -> MarkerPart {
+    let lines = match colors {
+        Some((c1, c2)) => gradient_lines(p1, p2, c1, c2, background),
+        None => from_point_strips(&vec![vec![*p1, *p2]], color),
+    };
+    MarkerPart {
+        z: (p1.z + p2.z) / 2.0,
+        lines,
+    }
+}
+
 fn parse_line_strip_msg(
     msg: &rosrust_msg::visualization_msgs::Marker,
     color: &tui::style::Color,
     iso: &Isometry3<f64>,
-) -> Vec<Line> {
+    background: &ConfigColor,
+) -> Vec<MarkerPart> {
     let mut points: Vec<Point3<f64>> = Vec::new();
 
     for point in msg.points.iter() {
         points.push(iso.transform_point(&Point3::new(point.x, point.y, point.z)));
     }
 
-    return from_point_strips(&vec![points], color);
+    // Per-vertex colors are only used if there is one for each point
+    let use_colors = msg.colors.len() == points.len();
+
+    let mut parts: Vec<MarkerPart> = Vec::new();
+    for i in 1..points.len() {
+        let colors = if use_colors {
+            Some((&msg.colors[i - 1], &msg.colors[i]))
+        } else {
+            None
+        };
+        parts.push(segment_part(
+            &points[i - 1],
+            &points[i],
+            colors,
+            color,
+            background,
+        ));
+    }
+    parts
 }
 
 fn parse_line_list_msg(
     msg: &rosrust_msg::visualization_msgs::Marker,
     color: &tui::style::Color,
     iso: &Isometry3<f64>,
-) -> Vec<Line> {
-    let mut lines: Vec<Line> = Vec::new();
-
-    let mut point_it = msg.points.iter();
-    let mut color_it = msg.colors.iter();
-
-    while let Some(msg_p1) = point_it.next() {
-        let msg_color = color_it.next();
-        let local_color = match msg_color {
-            Some(x) => Color::Rgb(
-                (x.r * 255.0) as u8,
-                (x.g * 255.0) as u8,
-                (x.b * 255.0) as u8,
-            ),
-            None => *color,
+    background: &ConfigColor,
+) -> Vec<MarkerPart> {
+    let mut parts: Vec<MarkerPart> = Vec::new();
+    let use_colors = msg.colors.len() == msg.points.len();
+
+    // Points come in pairs, an unpaired last point is ignored
+    for (i, pair) in msg.points.chunks_exact(2).enumerate() {
+        let p1 = iso.transform_point(&Point3::new(pair[0].x, pair[0].y, pair[0].z));
+        let p2 = iso.transform_point(&Point3::new(pair[1].x, pair[1].y, pair[1].z));
+        let colors = if use_colors {
+            Some((&msg.colors[2 * i], &msg.colors[2 * i + 1]))
+        } else {
+            None
         };
-        color_it.next(); // these come in pairs, but I currently don't see the necessity to implement gradients
-
-        let p1 = iso.transform_point(&Point3::new(msg_p1.x, msg_p1.y, msg_p1.z));
-        let msg_p2 = point_it.next().expect("Malformed message.");
-        let p2 = iso.transform_point(&Point3::new(msg_p2.x, msg_p2.y, msg_p2.z));
-
-        lines.push(Line {
-            x1: p1.x,
-            y1: p1.y,
-            x2: p2.x,
-            y2: p2.y,
-            color: local_color,
-        });
+        parts.push(segment_part(&p1, &p2, colors, color, background));
     }
-    lines
+    parts
 }
 
 fn parse_sphere_msg(
@@ -477,41 +587,214 @@ fn parse_sphere_msg(
     lines
 }
 
+/// Creates a closed strip approximating an ellipse parallel to the XY plane.
+/// # Arguments:
+/// - `center`: Center of the ellipse in the iso transformation.
+/// - `radius_x`, `radius_y`: Radii of the ellipse along the X and Y axes.
+/// - `iso`: Base transformation of the ellipse.
+fn ellipse_strip(
+    center: &Point3<f64>,
+    radius_x: f64,
+    radius_y: f64,
+    iso: &Isometry3<f64>,
+) -> Vec<Point3<f64>> {
+    let segment_count = 20;
+    let step = (2.0 * PI) / (segment_count as f64);
+    (0..segment_count + 1)
+        .map(|i| {
+            let angle = i as f64 * step;
+            iso.transform_point(&Point3::new(
+                center.x + radius_x * angle.cos(),
+                center.y + radius_y * angle.sin(),
+                center.z,
+            ))
+        })
+        .collect()
+}
+
+/// Creates the visible lines for a cylinder, whose axis is the Z axis.
+///
+/// If the cylinder is perpendicular to the plane, only the top ellipse is visible.
+/// Else, we draw both ellipses and four lines joining them.
+fn parse_cylinder_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+) -> Vec<Line> {
+    let angles = iso.rotation.euler_angles();
+    let (radius_x, radius_y, half_height) =
+        (msg.scale.x / 2.0, msg.scale.y / 2.0, msg.scale.z / 2.0);
+
+    let mut points_strips: Vec<Vec<Point3<f64>>> = vec![ellipse_strip(
+        &Point3::new(0.0, 0.0, half_height),
+        radius_x,
+        radius_y,
+        iso,
+    )];
+
+    if angles.0.abs() > 0.0001 || angles.1.abs() > 0.0001 {
+        points_strips.push(ellipse_strip(
+            &Point3::new(0.0, 0.0, -half_height),
+            radius_x,
+            radius_y,
+            iso,
+        ));
+        for (x, y) in [
+            (radius_x, 0.0),
+            (-radius_x, 0.0),
+            (0.0, radius_y),
+            (0.0, -radius_y),
+        ] {
+            points_strips.push(vec![
+                iso.transform_point(&Point3::new(x, y, half_height)),
+                iso.transform_point(&Point3::new(x, y, -half_height)),
+            ]);
+        }
+    }
+
+    from_point_strips(&points_strips, color)
+}
+
+/// Creates a circle of diameter `scale.x` for each point, using the color of the point if given.
+fn parse_sphere_list_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+    background: &ConfigColor,
+) -> Vec<MarkerPart> {
+    let mut parts: Vec<MarkerPart> = Vec::new();
+    let radius = msg.scale.x / 2.0;
+
+    for (i, point) in msg.points.iter().enumerate() {
+        let local_color = match msg.colors.get(i) {
+            Some(x) => to_tui_color(x, background),
+            None => *color,
+        };
+        let center = Point3::new(point.x, point.y, point.z);
+        parts.push(MarkerPart {
+            z: iso.transform_point(&center).z,
+            lines: from_point_strips(
+                &vec![ellipse_strip(&center, radius, radius, iso)],
+                &local_color,
+            ),
+        });
+    }
+
+    parts
+}
+
+/// Creates the outline of a mesh, i.e. the 2D convex hull of its vertices.
+///
+/// Meshes that cannot be loaded are approximated by a box of size `scale`.
+/// # Arguments:
+/// - `meshes`: Cache of the vertices of the meshes, by resource URI.
+fn parse_mesh_resource_msg(
+    msg: &rosrust_msg::visualization_msgs::Marker,
+    color: &tui::style::Color,
+    iso: &Isometry3<f64>,
+    meshes: &mut HashMap<String, Option<Vec<Point3<f64>>>>,
+) -> Vec<Line> {
+    let vertices = meshes
+        .entry(msg.mesh_resource.clone())
+        .or_insert_with(|| load_mesh_vertices(&msg.mesh_resource));
+    match vertices {
+        Some(vertices) if !vertices.is_empty() => {
+            let projected: Vec<(f64, f64)> = vertices
+                .iter()
+                .map(|v| {
+                    let p = iso.transform_point(&Point3::new(
+                        v.x * msg.scale.x,
+                        v.y * msg.scale.y,
+                        v.z * msg.scale.z,
+                    ));
+                    (p.x, p.y)
+                })
+                .collect();
+            let hull: Vec<Point3<f64>> = convex_hull(projected)
+                .iter()
+                .map(|p| Point3::new(p.0, p.1, 0.0))
+                .collect();
+            from_point_strips(&vec![hull], color)
+        }
+        _ => parse_cube(
+            &msg.scale,
+            &rosrust_msg::geometry_msgs::Point {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            color,
+            iso,
+        ),
+    }
+}
+
 fn parse_marker_msg(
     msg: &rosrust_msg::visualization_msgs::Marker,
+    topic: &str,
     tf: &rosrust_msg::geometry_msgs::Transform,
+    meshes: &mut HashMap<String, Option<Vec<Point3<f64>>>>,
+    background: &ConfigColor,
 ) -> TermvizMarker {
     let trans_marker_to_static_frame = isometry_from_transform(tf);
     let trans_to_marker = isometry_from_pose(&msg.pose);
 
     let iso = trans_marker_to_static_frame.inverse() * trans_to_marker;
 
-    let color = Color::Rgb(
-        (msg.color.r * 255.0) as u8,
-        (msg.color.g * 255.0) as u8,
-        (msg.color.b * 255.0) as u8,
-    );
+    let color = to_tui_color(&msg.color, background);
+    let z = iso.translation.vector.z;
+    // Markers other than lists are kept or filtered out as a whole
+    let single = |lines: Vec<Line>| vec![MarkerPart { z, lines }];
 
-    let res = match msg.type_ as u8 {
-        rosrust_msg::visualization_msgs::Marker::ARROW => parse_arrow_msg(msg, &color, &iso),
-        rosrust_msg::visualization_msgs::Marker::CUBE => parse_cube_msg(msg, &color, &iso),
+    let parts = match msg.type_ as u8 {
+        rosrust_msg::visualization_msgs::Marker::ARROW => {
+            single(parse_arrow_msg(msg, &color, &iso))
+        }
+        rosrust_msg::visualization_msgs::Marker::CUBE => single(parse_cube_msg(msg, &color, &iso)),
         rosrust_msg::visualization_msgs::Marker::CUBE_LIST => {
             parse_cube_list_msg(msg, &color, &iso)
         }
         rosrust_msg::visualization_msgs::Marker::POINTS => parse_points_msg(msg, &color, &iso),
         rosrust_msg::visualization_msgs::Marker::LINE_STRIP => {
-            parse_line_strip_msg(msg, &color, &iso)
+            parse_line_strip_msg(msg, &color, &iso, background)
         }
         rosrust_msg::visualization_msgs::Marker::LINE_LIST => {
-            parse_line_list_msg(msg, &color, &iso)
+            parse_line_list_msg(msg, &color, &iso, background)
+        }
+        rosrust_msg::visualization_msgs::Marker::SPHERE => {
+            single(parse_sphere_msg(msg, &color, &iso))
+        }
+        rosrust_msg::visualization_msgs::Marker::CYLINDER => {
+            single(parse_cylinder_msg(msg, &color, &iso))
+        }
+        rosrust_msg::visualization_msgs::Marker::SPHERE_LIST => {
+            parse_sphere_list_msg(msg, &color, &iso, background)
+        }
+        rosrust_msg::visualization_msgs::Marker::MESH_RESOURCE => {
+            single(parse_mesh_resource_msg(msg, &color, &iso, meshes))
         }
-        rosrust_msg::visualization_msgs::Marker::SPHERE => parse_sphere_msg(msg, &color, &iso),
         _ => Vec::new(),
     };
 
+    let mut texts = Vec::new();
+    if msg.type_ as u8 == rosrust_msg::visualization_msgs::Marker::TEXT_VIEW_FACING {
+        let position = iso.transform_point(&Point3::new(0.0, 0.0, 0.0));
+        texts.push(MarkerText {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            text: msg.text.clone(),
+            color,
+        });
+    }
+
     TermvizMarker {
-        lines: res,
+        parts,
+        texts,
+        z,
         id: msg.id,
+        topic: topic.to_string(),
+        expiry: None,
     }
 }
 
@@ -523,45 +806,61 @@ fn parse_marker_msg(
 /// publishers such that they can be managed globally.
 struct TermvizMarkerContainer {
     markers: HashMap<String, HashMap<i32, TermvizMarker>>,
+    meshes: HashMap<String, Option<Vec<Point3<f64>>>>,
+    hidden_namespaces: HashSet<String>,
+    /// Heights between which the markers of each topic are drawn.
+    z_ranges: HashMap<String, ZRange>,
+    background_color: ConfigColor,
     static_frame: String,
-    tf_listener: Arc<rustros_tf::TfListener>,
+    tf_listener: Arc<source::TfListener>,
 }
 
 impl TermvizMarkerContainer {
     pub fn new(
-        tf_listener: Arc<rustros_tf::TfListener>,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
+        background_color: ConfigColor,
     ) -> TermvizMarkerContainer {
         Self {
             markers: HashMap::<String, HashMap<i32, TermvizMarker>>::new(),
+            meshes: HashMap::new(),
+            hidden_namespaces: HashSet::new(),
+            z_ranges: HashMap::new(),
+            background_color,
             static_frame: static_frame,
             tf_listener: tf_listener,
         }
     }
 
-    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker) {
+    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker, topic: &str) {
         let transform = &self.tf_listener.clone().lookup_transform(
             &marker.header.frame_id,
             &self.static_frame.clone(),
             marker.header.stamp,
         );
-        match &transform {
+        let transform = match &transform {
             Ok(transform) => transform,
             Err(_e) => return,
         };
 
+        let mut res = parse_marker_msg(
+            &marker,
+            topic,
+            &transform.transform,
+            &mut self.meshes,
+            &self.background_color,
+        );
+        // A lifetime of 0 means that the marker never expires
+        if marker.lifetime.sec > 0 || (marker.lifetime.sec == 0 && marker.lifetime.nsec > 0) {
+            res.expiry = Some(
+                Instant::now()
+                    + Duration::new(marker.lifetime.sec as u64, marker.lifetime.nsec as u32),
+            );
+        }
         self.markers
             .entry(marker.ns.clone())
-            .and_modify(|namespace| {
-                let res = parse_marker_msg(&marker, &transform.as_ref().unwrap().transform);
-                namespace.insert(res.id, res);
-            })
-            .or_insert_with(|| {
-                let res = parse_marker_msg(&marker, &transform.as_ref().unwrap().transform);
-                let mut namespace = HashMap::<i32, TermvizMarker>::new();
-                namespace.insert(res.id, res);
-                namespace
-            });
+            .or_insert_with(HashMap::<i32, TermvizMarker>::new)
+            .insert(res.id, res);
     }
 
     fn delete_marker(&mut self, marker_ns: String, marker_id: i32) {
@@ -574,170 +873,160 @@ impl TermvizMarkerContainer {
         self.markers.clear();
     }
 
-    fn clear_namespace(&mut self, marker_ns: String) -> Vec<i32> {
-        let mut res = Vec::new();
+    fn clear_namespace(&mut self, marker_ns: String) {
         self.markers.entry(marker_ns).and_modify(|namespace| {
-            for marker in namespace.values() {
-                res.push(marker.id);
-            }
             namespace.clear();
         });
+    }
+
+    /// Deletes the markers whose lifetime is over.
+    fn remove_expired(&mut self, now: Instant) {
+        for namespace in self.markers.values_mut() {
+            namespace.retain(|_, marker| marker.expiry.map_or(true, |expiry| expiry > now));
+        }
+    }
+
+    /// Returns the namespaces that are not hidden.
+    fn visible_namespaces(&self) -> impl Iterator<Item = &HashMap<i32, TermvizMarker>> {
+        self.markers
+            .iter()
+            .filter(move |(ns, _)| !self.hidden_namespaces.contains(*ns))
+            .map(|(_, namespace)| namespace)
+    }
+
+    fn z_range(&self, topic: &str) -> ZRange {
+        self.z_ranges.get(topic).copied().unwrap_or_default()
+    }
+
+    fn get_lines(&self) -> Vec<(f64, Line)> {
+        let mut res = Vec::<(f64, Line)>::new();
+        for namespace in self.visible_namespaces() {
+            for marker in namespace.values() {
+                let z_range = self.z_range(&marker.topic);
+                for part in marker.parts.iter().filter(|part| z_range.contains(part.z)) {
+                    res.extend(part.lines.iter().map(|line| (part.z, line.clone())));
+                }
+            }
+        }
         res
     }
 
-    fn get_lines(&self) -> Vec<Line> {
-        let mut res = Vec::<Line>::new();
-        for namespace in self.markers.values() {
+    fn get_texts(&self) -> Vec<MarkerText> {
+        let mut res = Vec::<MarkerText>::new();
+        for namespace in self.visible_namespaces() {
             for marker in namespace.values() {
-                res.extend(marker.lines.to_vec());
+                if self.z_range(&marker.topic).contains(marker.z) {
+                    res.extend(marker.texts.to_vec());
+                }
             }
         }
         res
     }
+
+    fn get_namespaces(&self) -> Vec<(String, usize, bool)> {
+        let mut res: Vec<(String, usize, bool)> = self
+            .markers
+            .iter()
+            .map(|(ns, namespace)| {
+                (
+                    ns.clone(),
+                    namespace.len(),
+                    !self.hidden_namespaces.contains(ns),
+                )
+            })
+            .collect();
+        res.sort();
+        res
+    }
+
+    fn toggle_namespace(&mut self, marker_ns: &str) {
+        if !self.hidden_namespaces.remove(marker_ns) {
+            self.hidden_namespaces.insert(marker_ns.to_string());
+        }
+    }
 }
 
-/// Class that handles the lifecycle of the markers.
-///
-/// Markers that provide a lifecycle (i.e. not 0) need to be deleted from the container
-/// once the lifecycle is reached. To do this we add another container which has a timer.
-/// The timer collects jobs after a delay to delete markers from the markers container.
-///
-/// Because the timer uses guards, we also need to store them which is done with a dict
-/// and a list. A repeated task is given to the timer to consume the list of deleted
-/// marker and free the guards.
-struct MarkersLifecycle {
+pub struct MarkersListener {
     markers_container: Arc<RwLock<TermvizMarkerContainer>>,
-    deleted_markers: Arc<Mutex<Vec<(String, i32)>>>,
-    guards: Arc<Mutex<HashMap<(String, i32), timer::Guard>>>,
-    timer: Arc<Mutex<timer::Timer>>,
-    #[allow(dead_code)] // because the guard is never used but must be kept
-    cleaner_guard: timer::Guard,
+    subscribers: Vec<Arc<Mutex<source::Subscriber>>>,
 }
 
-impl MarkersLifecycle {
-    pub fn new(marker_container: TermvizMarkerContainer) -> MarkersLifecycle {
-        let timer = Arc::new(Mutex::new(timer::Timer::new()));
-        let deleted_markers = Arc::new(Mutex::new(Vec::<(String, i32)>::new()));
-        let guards = Arc::new(Mutex::new(HashMap::<(String, i32), timer::Guard>::new()));
-
-        let clean_job_delete_markers_ref = deleted_markers.clone();
-        let clean_job_guards_ref = guards.clone();
-
-        let guard =
-            timer
-                .lock()
-                .unwrap()
-                .schedule_repeating(chrono::Duration::seconds(5), move || {
-                    // every 5s clear the guards that outlived their markers
-                    let mut clean_job_delete_markers = clean_job_delete_markers_ref.lock().unwrap();
-                    let mut clean_job_guards = clean_job_guards_ref.lock().unwrap();
-                    for key in clean_job_delete_markers.iter() {
-                        clean_job_guards.remove(key);
-                    }
-                    clean_job_delete_markers.clear();
-                });
-
+impl MarkersListener {
+    pub fn new(
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+        background_color: ConfigColor,
+    ) -> MarkersListener {
+        let marker_container =
+            TermvizMarkerContainer::new(tf_listener, static_frame, background_color);
         Self {
             markers_container: Arc::new(RwLock::new(marker_container)),
-            deleted_markers: deleted_markers,
-            guards: guards,
-            timer: timer,
-            cleaner_guard: guard,
+            subscribers: Vec::new(),
         }
     }
 
-    fn add_marker(&mut self, marker: &rosrust_msg::visualization_msgs::Marker) {
-        self.markers_container.write().unwrap().add_marker(marker);
-
-        // Handle marker lifecycle
-        if marker.lifetime.seconds() == 0.0 {
-            return;
-        }
-
-        let markers_container_ref = self.markers_container.clone();
-
-        let chrono_delay = chrono::Duration::seconds(marker.lifetime.sec as i64)
-            + chrono::Duration::nanoseconds(marker.lifetime.nsec as i64);
-
-        let marker_info = (marker.ns.clone(), marker.id);
-
-        let guard_ = self
-            .timer
-            .lock()
-            .unwrap()
-            .schedule_with_delay(chrono_delay, move || {
-                let mut mc = markers_container_ref.write().unwrap();
-                mc.delete_marker(marker_info.0.clone(), marker_info.1);
-            });
-
-        let mut guards_ref = self.guards.lock().unwrap();
-        guards_ref.insert((marker.ns.clone(), marker.id), guard_);
+    /// Gets all the lines currently active, to render, with the height at which they lie.
+    pub fn get_lines(&self) -> Vec<(f64, Line)> {
+        let mut markers_container_ref = self.markers_container.write().unwrap();
+        markers_container_ref.remove_expired(Instant::now());
+        markers_container_ref.get_lines()
     }
 
-    fn delete_marker(&mut self, marker_ns: String, marker_id: i32) {
-        self.markers_container
-            .write()
-            .unwrap()
-            .delete_marker(marker_ns.clone(), marker_id);
-        self.deleted_markers
-            .lock()
-            .unwrap()
-            .push((marker_ns, marker_id));
+    /// Gets all the text labels currently active, to render.
+    pub fn get_texts(&self) -> Vec<MarkerText> {
+        let mut markers_container_ref = self.markers_container.write().unwrap();
+        markers_container_ref.remove_expired(Instant::now());
+        markers_container_ref.get_texts()
     }
 
-    fn clear(&mut self) {
-        // loose the guards if any, to cancel the timer tasks
-        self.deleted_markers.lock().unwrap().clear();
-        self.guards.lock().unwrap().clear();
-
-        self.markers_container.write().unwrap().clear();
+    /// Gets the namespaces of the markers currently held, sorted by name,
+    /// with the number of markers and whether they are visible.
+    pub fn get_namespaces(&self) -> Vec<(String, usize, bool)> {
+        self.markers_container.read().unwrap().get_namespaces()
     }
 
-    fn clear_namespace(&mut self, marker_ns: String) {
-        let removed_ids = self
-            .markers_container
+    /// Hides a visible namespace or shows a hidden one. The markers are still received.
+    pub fn toggle_namespace(&self, marker_ns: &str) {
+        self.markers_container
             .write()
             .unwrap()
-            .clear_namespace(marker_ns.clone());
-
-        // schedule deletion of the cleared markers
-        let mut deleted_markers = self.deleted_markers.lock().unwrap();
-        deleted_markers.extend(removed_ids.iter().map(|&id| (marker_ns.clone(), id)));
+            .toggle_namespace(marker_ns);
     }
 
-    fn get_lines(&self) -> Vec<Line> {
-        self.markers_container.write().unwrap().get_lines()
+    /// Returns the heights between which the markers are drawn. As they are adjusted together
+    /// at runtime, this is the range of the first topic.
+    pub fn get_z_range(&self) -> ZRange {
+        let container = self.markers_container.read().unwrap();
+        container
+            .z_ranges
+            .values()
+            .next()
+            .copied()
+            .unwrap_or_default()
     }
-}
 
-pub struct MarkersListener {
-    markers_lifecycle: Arc<RwLock<MarkersLifecycle>>,
-    subscribers: Vec<Arc<Mutex<rosrust::Subscriber>>>,
-}
-
-impl MarkersListener {
-    pub fn new(tf_listener: Arc<rustros_tf::TfListener>, static_frame: String) -> MarkersListener {
-        let marker_container = TermvizMarkerContainer::new(tf_listener, static_frame);
-        Self {
-            markers_lifecycle: Arc::new(RwLock::new(MarkersLifecycle::new(marker_container))),
-            subscribers: Vec::new(),
+    /// Sets the heights between which the markers of all the topics are drawn.
+    pub fn set_z_range(&self, z_range: ZRange) {
+        let mut container = self.markers_container.write().unwrap();
+        for topic_range in container.z_ranges.values_mut() {
+            *topic_range = z_range;
         }
     }
 
-    /// Gets all the lines currently active, to render.
-    pub fn get_lines(&self) -> Vec<Line> {
-        let markers_container_ref = self.markers_lifecycle.read().unwrap();
-        markers_container_ref.get_lines()
-    }
-
     /// Adds a subscriber for a marker topic.
     ///
     /// # Arguments
     /// - `config`: Configuration containing the topic name.
     pub fn add_marker_listener(&mut self, config: &ListenerConfig) {
-        let markers_container_ref = self.markers_lifecycle.clone();
+        let markers_container_ref = self.markers_container.clone();
+        let topic = config.topic.clone();
+        self.markers_container
+            .write()
+            .unwrap()
+            .z_ranges
+            .insert(topic.clone(), config.z_range);
 
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             2,
             move |msg: rosrust_msg::visualization_msgs::Marker| {
@@ -745,7 +1034,7 @@ impl MarkersListener {
 
                 match msg.action as u8 {
                     rosrust_msg::visualization_msgs::Marker::ADD => {
-                        markers_container.add_marker(&msg)
+                        markers_container.add_marker(&msg, &topic)
                     }
                     rosrust_msg::visualization_msgs::Marker::DELETE => {
                         markers_container.delete_marker(msg.ns.clone(), msg.id)
@@ -764,9 +1053,15 @@ impl MarkersListener {
     /// # Arguments
     /// * `config` - Configuration containing the topic.
     pub fn add_marker_array_listener(&mut self, config: &ListenerConfig) {
-        let markers_container_ref = self.markers_lifecycle.clone();
+        let markers_container_ref = self.markers_container.clone();
+        let topic = config.topic.clone();
+        self.markers_container
+            .write()
+            .unwrap()
+            .z_ranges
+            .insert(topic.clone(), config.z_range);
 
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             2,
             move |msg: rosrust_msg::visualization_msgs::MarkerArray| {
@@ -775,7 +1070,7 @@ impl MarkersListener {
                 for marker in msg.markers {
                     match marker.action as u8 {
                         rosrust_msg::visualization_msgs::Marker::ADD => {
-                            markers_container.add_marker(&marker)
+                            markers_container.add_marker(&marker, &topic)
                         }
                         rosrust_msg::visualization_msgs::Marker::DELETE => {
                             markers_container.delete_marker(marker.ns.clone(), marker.id)
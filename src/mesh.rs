@@ -0,0 +1,100 @@
+//! Module dealing with the loading of meshes referenced by MESH_RESOURCE markers.
+//!
+//! Only STL files (binary or ASCII) referenced by a `file://` URI are supported.
+use byteorder::{ByteOrder, LittleEndian};
+use nalgebra::geometry::Point3;
+use std::fs;
+
+const STL_HEADER_SIZE: usize = 84;
+const STL_TRIANGLE_SIZE: usize = 50;
+
+fn parse_binary_stl(data: &[u8]) -> Option<Vec<Point3<f64>>> {
+    if data.len() < STL_HEADER_SIZE {
+        return None;
+    }
+    let triangle_count = LittleEndian::read_u32(&data[80..84]) as usize;
+    if data.len() != STL_HEADER_SIZE + triangle_count * STL_TRIANGLE_SIZE {
+        return None;
+    }
+    let mut vertices = Vec::with_capacity(triangle_count * 3);
+    for triangle in data[STL_HEADER_SIZE..].chunks(STL_TRIANGLE_SIZE) {
+        // Skip the normal, then read the three vertices
+        for vertex in triangle[12..48].chunks(12) {
+            vertices.push(Point3::new(
+                LittleEndian::read_f32(&vertex[0..4]) as f64,
+                LittleEndian::read_f32(&vertex[4..8]) as f64,
+                LittleEndian::read_f32(&vertex[8..12]) as f64,
+            ));
+        }
+    }
+    Some(vertices)
+}
+
+fn parse_ascii_stl(data: &[u8]) -> Option<Vec<Point3<f64>>> {
+    let text = std::str::from_utf8(data).ok()?;
+    let mut vertices = Vec::new();
+    for line in text.lines() {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("vertex") {
+            continue;
+        }
+        let coords: Vec<f64> = tokens.filter_map(|t| t.parse().ok()).collect();
+        if coords.len() != 3 {
+            return None;
+        }
+        vertices.push(Point3::new(coords[0], coords[1], coords[2]));
+    }
+    Some(vertices)
+}
+
+/// Loads the vertices of the mesh referenced by the given resource URI.
+/// Returns None if the URI or the file format is not supported.
+pub fn load_mesh_vertices(uri: &str) -> Option<Vec<Point3<f64>>> {
+    let path = uri.strip_prefix("file://")?;
+    if !path.to_lowercase().ends_with(".stl") {
+        return None;
+    }
+    let data = fs::read(path).ok()?;
+    // Binary files may also start with "solid", so the size is checked first
+    parse_binary_stl(&data).or_else(|| {
+        if data.starts_with(b"solid") {
+            parse_ascii_stl(&data)
+        } else {
+            None
+        }
+    })
+}
+
+fn cross(o: &(f64, f64), a: &(f64, f64), b: &(f64, f64)) -> f64 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+/// Computes the convex hull of a set of 2D points with the monotone chain algorithm.
+/// The hull is returned counter-clockwise, as a closed polygon.
+pub fn convex_hull(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut lower: Vec<(f64, f64)> = Vec::new();
+    for p in points.iter() {
+        while lower.len() >= 2 && cross(&lower[lower.len() - 2], &lower[lower.len() - 1], p) <= 0.0
+        {
+            lower.pop();
+        }
+        lower.push(*p);
+    }
+    let mut upper: Vec<(f64, f64)> = Vec::new();
+    for p in points.iter().rev() {
+        while upper.len() >= 2 && cross(&upper[upper.len() - 2], &upper[upper.len() - 1], p) <= 0.0
+        {
+            upper.pop();
+        }
+        upper.push(*p);
+    }
+    // The last point of each chain is the first point of the other one
+    lower.pop();
+    lower.extend(upper);
+    lower
+}
@@ -0,0 +1,241 @@
+//! Module dealing with the move_base action interface, without the need of an actionlib client.
+//!
+//! Goals are published on `<ns>/goal`, and their state is tracked from `<ns>/status`
+//! and `<ns>/feedback`.
+use crate::config::MoveBaseStatusListenerConfig;
+use crate::source;
+use rosrust;
+use rosrust_msg::actionlib_msgs::{GoalID, GoalStatus, GoalStatusArray};
+use rosrust_msg::geometry_msgs::PoseStamped;
+use rosrust_msg::move_base_msgs::{MoveBaseActionFeedback, MoveBaseActionGoal};
+use rosrust_msg::nav_msgs::Path;
+use std::sync::{Arc, RwLock};
+use tui::style::{Color, Style};
+use tui::text::Span;
+
+/// Returns the name of a goal status.
+pub fn status_name(status: u8) -> &'static str {
+    match status {
+        GoalStatus::PENDING => "PENDING",
+        GoalStatus::ACTIVE => "ACTIVE",
+        GoalStatus::PREEMPTED => "PREEMPTED",
+        GoalStatus::SUCCEEDED => "SUCCEEDED",
+        GoalStatus::ABORTED => "ABORTED",
+        GoalStatus::REJECTED => "REJECTED",
+        GoalStatus::PREEMPTING => "PREEMPTING",
+        GoalStatus::RECALLING => "RECALLING",
+        GoalStatus::RECALLED => "RECALLED",
+        GoalStatus::LOST => "LOST",
+        _ => "UNKNOWN",
+    }
+}
+
+/// Returns true if the goal status is terminal, i.e. the goal is not pursued anymore.
+pub fn is_terminal(status: u8) -> bool {
+    !matches!(
+        status,
+        GoalStatus::PENDING | GoalStatus::ACTIVE | GoalStatus::PREEMPTING | GoalStatus::RECALLING
+    )
+}
+
+/// State of the last goal sent.
+#[derive(Clone, Default)]
+pub struct GoalState {
+    pub id: String,
+    pub target: (f64, f64),
+    pub status: Option<u8>,
+    pub text: String,
+    /// Position of the robot, as received in the last feedback.
+    pub base_position: Option<(f64, f64)>,
+}
+
+impl GoalState {
+    /// Returns the distance between the last feedback position and the target.
+    pub fn distance_to_goal(&self) -> Option<f64> {
+        let (x, y) = self.base_position?;
+        Some(((self.target.0 - x).powi(2) + (self.target.1 - y).powi(2)).sqrt())
+    }
+}
+
+pub struct MoveBaseClient {
+    pub goal: Arc<RwLock<Option<GoalState>>>,
+    goal_pub: rosrust::Publisher<MoveBaseActionGoal>,
+    cancel_pub: rosrust::Publisher<GoalID>,
+    _status_sub: source::Subscriber,
+    _feedback_sub: source::Subscriber,
+}
+
+impl MoveBaseClient {
+    pub fn new(namespace: &str) -> MoveBaseClient {
+        let goal: Arc<RwLock<Option<GoalState>>> = Arc::new(RwLock::new(None));
+
+        let cb_goal = goal.clone();
+        let status_sub = source::subscribe(
+            &format!("{}/status", namespace),
+            1,
+            move |msg: GoalStatusArray| {
+                let mut goal = cb_goal.write().unwrap();
+                if let Some(goal) = goal.as_mut() {
+                    for status in msg.status_list {
+                        if status.goal_id.id == goal.id {
+                            goal.status = Some(status.status);
+                            goal.text = status.text;
+                        }
+                    }
+                }
+            },
+        )
+        .unwrap();
+
+        let cb_goal = goal.clone();
+        let feedback_sub = source::subscribe(
+            &format!("{}/feedback", namespace),
+            1,
+            move |msg: MoveBaseActionFeedback| {
+                let mut goal = cb_goal.write().unwrap();
+                if let Some(goal) = goal.as_mut() {
+                    if msg.status.goal_id.id == goal.id {
+                        let position = msg.feedback.base_position.pose.position;
+                        goal.base_position = Some((position.x, position.y));
+                    }
+                }
+            },
+        )
+        .unwrap();
+
+        MoveBaseClient {
+            goal,
+            goal_pub: rosrust::publish(&format!("{}/goal", namespace), 1).unwrap(),
+            cancel_pub: rosrust::publish(&format!("{}/cancel", namespace), 1).unwrap(),
+            _status_sub: status_sub,
+            _feedback_sub: feedback_sub,
+        }
+    }
+
+    /// Sends a new goal, which replaces the tracked one.
+    pub fn send_goal(&self, target_pose: PoseStamped) {
+        let now = rosrust::now();
+        let id = format!("termviz-{}.{:09}", now.sec, now.nsec);
+        let mut msg = MoveBaseActionGoal::default();
+        msg.header.stamp = now;
+        msg.goal_id.stamp = now;
+        msg.goal_id.id = id.clone();
+        let target = (target_pose.pose.position.x, target_pose.pose.position.y);
+        msg.goal.target_pose = target_pose;
+        *self.goal.write().unwrap() = Some(GoalState {
+            id,
+            target,
+            ..Default::default()
+        });
+        self.goal_pub.send(msg).unwrap();
+    }
+
+    /// Cancels the tracked goal, if any.
+    pub fn cancel_goal(&self) {
+        if let Some(goal) = self.goal.read().unwrap().as_ref() {
+            let mut msg = GoalID::default();
+            msg.id = goal.id.clone();
+            self.cancel_pub.send(msg).unwrap();
+        }
+    }
+
+    /// Returns a description of the state of the tracked goal.
+    pub fn describe(&self) -> Option<String> {
+        let goal = self.goal.read().unwrap();
+        let goal = goal.as_ref()?;
+        let mut description = format!("Goal: {}", goal.status.map(status_name).unwrap_or("SENT"));
+        if let Some(distance) = goal.distance_to_goal() {
+            description += &format!(" ({:.2}m to go)", distance);
+        }
+        if !goal.text.is_empty() {
+            description += &format!(" {}", goal.text);
+        }
+        Some(description)
+    }
+}
+
+/// Returns the length of the path.
+fn path_length(path: &Path) -> f64 {
+    path.poses
+        .windows(2)
+        .map(|w| {
+            let p1 = &w[0].pose.position;
+            let p2 = &w[1].pose.position;
+            ((p2.x - p1.x).powi(2) + (p2.y - p1.y).powi(2)).sqrt()
+        })
+        .sum()
+}
+
+/// Status of the goals of move_base, whoever sent them.
+struct NavigationData {
+    /// Status of the current goal, i.e. the last active one or else the last received one.
+    status: Option<GoalStatus>,
+    /// Length of the last global plan.
+    plan_length: Option<f64>,
+}
+
+pub struct MoveBaseStatusListener {
+    pub config: MoveBaseStatusListenerConfig,
+    data: Arc<RwLock<NavigationData>>,
+    _status_sub: source::Subscriber,
+    _plan_sub: source::Subscriber,
+}
+
+impl MoveBaseStatusListener {
+    pub fn new(config: MoveBaseStatusListenerConfig) -> MoveBaseStatusListener {
+        let data = Arc::new(RwLock::new(NavigationData {
+            status: None,
+            plan_length: None,
+        }));
+
+        let cb_data = data.clone();
+        let status_sub = source::subscribe(&config.status_topic, 1, move |msg: GoalStatusArray| {
+            let current = msg
+                .status_list
+                .iter()
+                .rev()
+                .find(|status| !is_terminal(status.status))
+                .or_else(|| msg.status_list.last())
+                .cloned();
+            // The status list is empty a while after the last goal is done,
+            // the last status is kept until a new goal is sent
+            if current.is_some() {
+                cb_data.write().unwrap().status = current;
+            }
+        })
+        .unwrap();
+
+        let cb_data = data.clone();
+        let plan_sub = source::subscribe(&config.plan_topic, 1, move |msg: Path| {
+            cb_data.write().unwrap().plan_length = Some(path_length(&msg));
+        })
+        .unwrap();
+
+        MoveBaseStatusListener {
+            config,
+            data,
+            _status_sub: status_sub,
+            _plan_sub: plan_sub,
+        }
+    }
+
+    /// Returns the status of the current goal and, while it is pursued,
+    /// the length of the global plan as distance to the goal.
+    /// Nothing is shown until the first goal is received.
+    pub fn status(&self) -> Option<Span<'static>> {
+        let data = self.data.read().unwrap();
+        let status = data.status.as_ref()?;
+        let mut text = format!("Goal: {}", status_name(status.status));
+        if !is_terminal(status.status) {
+            if let Some(length) = data.plan_length {
+                text += &format!(" {:.2}m", length);
+            }
+        }
+        let color = match status.status {
+            GoalStatus::ACTIVE | GoalStatus::SUCCEEDED => Color::Green,
+            GoalStatus::ABORTED | GoalStatus::REJECTED | GoalStatus::LOST => Color::Red,
+            _ => Color::Yellow,
+        };
+        Some(Span::styled(text, Style::default().fg(color)))
+    }
+}
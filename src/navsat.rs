@@ -0,0 +1,110 @@
+//! Module dealing with GPS fixes received as sensor_msgs::NavSatFix.
+//!
+//! Fixes are projected on a local East-North-Up plane whose origin is either
+//! given in the configuration or set by the first valid fix. The plane is
+//! assumed to be aligned with the static frame, with its origin at the origin
+//! of the static frame.
+use crate::config::NavSatFixListenerConfig;
+use crate::source;
+use std::collections::VecDeque;
+use std::sync::{Arc, RwLock};
+use tui::style::Color;
+
+use rosrust_msg::sensor_msgs::{NavSatFix, NavSatStatus};
+
+/// Equatorial radius of the WGS84 ellipsoid, in meters.
+const EARTH_RADIUS: f64 = 6378137.0;
+
+/// Projects a latitude/longitude on the plane tangent to the origin (in degrees),
+/// using an equirectangular approximation that is accurate for local areas.
+fn to_local_enu(latitude: f64, longitude: f64, origin: (f64, f64)) -> (f64, f64) {
+    let east = (longitude - origin.1).to_radians() * origin.0.to_radians().cos() * EARTH_RADIUS;
+    let north = (latitude - origin.0).to_radians() * EARTH_RADIUS;
+    (east, north)
+}
+
+/// Returns the color of a fix: by horizontal standard deviation if the covariance
+/// is known, by status otherwise.
+fn fix_color(fix: &NavSatFix) -> Color {
+    if fix.position_covariance_type != NavSatFix::COVARIANCE_TYPE_UNKNOWN {
+        let std_dev = (fix.position_covariance[0] + fix.position_covariance[4]).sqrt();
+        if std_dev < 0.5 {
+            Color::Green
+        } else if std_dev < 2.0 {
+            Color::Yellow
+        } else {
+            Color::Red
+        }
+    } else if fix.status.status == NavSatStatus::STATUS_GBAS_FIX {
+        Color::Green
+    } else if fix.status.status == NavSatStatus::STATUS_SBAS_FIX {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+struct NavSatFixData {
+    origin: Option<(f64, f64)>,
+    history: VecDeque<((f64, f64), Color)>,
+    last_fix: Option<(f64, f64)>,
+}
+
+pub struct NavSatFixListener {
+    pub config: NavSatFixListenerConfig,
+    data: Arc<RwLock<NavSatFixData>>,
+    _subscriber: source::Subscriber,
+}
+
+impl NavSatFixListener {
+    pub fn new(config: NavSatFixListenerConfig) -> NavSatFixListener {
+        let origin = match (config.origin_latitude, config.origin_longitude) {
+            (Some(latitude), Some(longitude)) => Some((latitude, longitude)),
+            _ => None,
+        };
+        let data = Arc::new(RwLock::new(NavSatFixData {
+            origin,
+            history: VecDeque::new(),
+            last_fix: None,
+        }));
+        let cb_data = data.clone();
+        let history_length = config.history_length;
+
+        let fix_sub = source::subscribe(&config.topic, 2, move |fix: NavSatFix| {
+            if fix.status.status == NavSatStatus::STATUS_NO_FIX {
+                return;
+            }
+            let mut data = cb_data.write().unwrap();
+            let origin = *data.origin.get_or_insert((fix.latitude, fix.longitude));
+            let point = to_local_enu(fix.latitude, fix.longitude, origin);
+            data.history.push_back((point, fix_color(&fix)));
+            while data.history.len() > history_length {
+                data.history.pop_front();
+            }
+            data.last_fix = Some((fix.latitude, fix.longitude));
+        })
+        .unwrap();
+
+        NavSatFixListener {
+            config,
+            data,
+            _subscriber: fix_sub,
+        }
+    }
+
+    /// Returns the history of fixes in the static frame, with their color.
+    pub fn get_points(&self) -> Vec<((f64, f64), Color)> {
+        self.data.read().unwrap().history.iter().cloned().collect()
+    }
+
+    /// Returns the last received fix, if any.
+    pub fn info(&self) -> Option<String> {
+        match self.data.read().unwrap().last_fix {
+            Some((latitude, longitude)) => Some(format!(
+                "/{} lat: {:.6} lon: {:.6}",
+                self.config.topic, latitude, longitude
+            )),
+            None => None,
+        }
+    }
+}
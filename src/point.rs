@@ -0,0 +1,86 @@
+use crate::config::PointListenerConfig;
+use crate::source;
+use crate::transformation;
+use std::option::Option;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+/// Creates a cross of the given size centered on the point.
+fn point_to_cross(pt: (f64, f64), size: f64, color: tui::style::Color) -> Vec<Line> {
+    let half_size = size / 2.0;
+    vec![
+        Line {
+            x1: pt.0 - half_size,
+            y1: pt.1 - half_size,
+            x2: pt.0 + half_size,
+            y2: pt.1 + half_size,
+            color,
+        },
+        Line {
+            x1: pt.0 - half_size,
+            y1: pt.1 + half_size,
+            x2: pt.0 + half_size,
+            y2: pt.1 - half_size,
+            color,
+        },
+    ]
+}
+
+pub struct PointStampedListener {
+    pub config: PointListenerConfig,
+    pub point: Arc<RwLock<Option<(f64, f64)>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl PointStampedListener {
+    pub fn new(
+        config: PointListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> PointStampedListener {
+        let point = Arc::new(RwLock::new(None));
+        let cb_point = point.clone();
+        let str_ = static_frame.clone();
+
+        let local_listener = tf_listener.clone();
+        let point_sub = source::subscribe(
+            &config.topic,
+            2,
+            move |point_msg: rosrust_msg::geometry_msgs::PointStamped| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &point_msg.header.frame_id,
+                    point_msg.header.stamp,
+                );
+                match &res {
+                    Ok(res) => {
+                        let pt = transformation::transform_relative_pt(
+                            &res.transform,
+                            (point_msg.point.x, point_msg.point.y),
+                        );
+                        *cb_point.write().unwrap() = Some(pt);
+                    }
+                    Err(_e) => return,
+                };
+            },
+        )
+        .unwrap();
+
+        PointStampedListener {
+            config,
+            point,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: point_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match *self.point.read().unwrap() {
+            Some(pt) => point_to_cross(pt, self.config.size, self.config.color.to_tui()),
+            None => Vec::new(),
+        }
+    }
+}
@@ -1,21 +1,29 @@
 use crate::config::PointCloud2ListenerConfig;
-use byteorder::{ByteOrder, LittleEndian};
+use crate::source;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
 use colorgrad;
+use std::collections::{HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 use nalgebra::geometry::Point3;
 use tui::style::Color;
 
 use crate::transformation::ros_transform_to_isometry;
-use rosrust;
-use rustros_tf;
+
+/// Points of a cloud in the static frame, with the time at which it was received.
+pub struct CloudPoints {
+    pub received: Instant,
+    pub points: Vec<ColoredPoint>,
+}
 
 pub struct PointCloud2Listener {
     pub config: PointCloud2ListenerConfig,
-    pub points: Arc<RwLock<Vec<ColoredPoint>>>,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    /// Clouds received within the accumulation time, from the oldest to the latest.
+    pub clouds: Arc<RwLock<VecDeque<CloudPoints>>>,
+    _tf_listener: Arc<source::TfListener>,
     _static_frame: String,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 #[derive(Clone)]
@@ -33,45 +41,83 @@ impl ColoredPoint {
     }
 }
 
-pub fn get_channel_offset(name: &str, fields: &Vec<rosrust_msg::sensor_msgs::PointField>) -> u32 {
-    for field in fields {
-        if field.name == name {
-            return field.offset;
-        }
+/// Returns the field of the given name, if the cloud has it.
+pub fn find_channel<'a>(
+    name: &str,
+    fields: &'a Vec<rosrust_msg::sensor_msgs::PointField>,
+) -> Option<&'a rosrust_msg::sensor_msgs::PointField> {
+    fields.iter().find(|field| field.name == name)
+}
+
+/// Returns the field of the given name, panicking if the cloud does not have it.
+pub fn get_channel<'a>(
+    name: &str,
+    fields: &'a Vec<rosrust_msg::sensor_msgs::PointField>,
+) -> &'a rosrust_msg::sensor_msgs::PointField {
+    match find_channel(name, fields) {
+        Some(field) => field,
+        None => panic!("Could not find field {:}", name),
     }
-    panic!("Could not find field {:}", name);
 }
 
-pub fn read_f32(bytes: &Vec<u8>, idx: u32) -> f32 {
-    LittleEndian::read_f32(&bytes[idx as usize..(idx + 4) as usize])
+/// Reads a value of the given datatype at the given index of the data.
+fn read_value<E: ByteOrder>(bytes: &[u8], idx: usize, datatype: u8) -> f64 {
+    use rosrust_msg::sensor_msgs::PointField;
+    match datatype {
+        PointField::INT8 => bytes[idx] as i8 as f64,
+        PointField::UINT8 => bytes[idx] as f64,
+        PointField::INT16 => E::read_i16(&bytes[idx..idx + 2]) as f64,
+        PointField::UINT16 => E::read_u16(&bytes[idx..idx + 2]) as f64,
+        PointField::INT32 => E::read_i32(&bytes[idx..idx + 4]) as f64,
+        PointField::UINT32 => E::read_u32(&bytes[idx..idx + 4]) as f64,
+        PointField::FLOAT64 => E::read_f64(&bytes[idx..idx + 8]),
+        _ => E::read_f32(&bytes[idx..idx + 4]) as f64,
+    }
+}
+
+/// Reads the value of the field of the point starting at the given index of the data,
+/// according to the datatype of the field and the endianness of the cloud.
+pub fn read_field(
+    msg: &rosrust_msg::sensor_msgs::PointCloud2,
+    idx: u32,
+    field: &rosrust_msg::sensor_msgs::PointField,
+) -> f64 {
+    let idx = (idx + field.offset) as usize;
+    if msg.is_bigendian {
+        read_value::<BigEndian>(&msg.data, idx, field.datatype)
+    } else {
+        read_value::<LittleEndian>(&msg.data, idx, field.datatype)
+    }
 }
 
 pub fn read_xyz(msg: &rosrust_msg::sensor_msgs::PointCloud2) -> Vec<Point3<f64>> {
     let n_pts = msg.width * msg.height;
     let mut points: Vec<Point3<f64>> = Vec::with_capacity(n_pts as usize);
-    let x_offset = get_channel_offset("x", &msg.fields);
-    let y_offset = get_channel_offset("y", &msg.fields);
-    let z_offset = get_channel_offset("z", &msg.fields);
+    let x_field = get_channel("x", &msg.fields);
+    let y_field = get_channel("y", &msg.fields);
+    let z_field = get_channel("z", &msg.fields);
     for i in 0..n_pts {
         let idx = i * msg.point_step;
         points.push(Point3::new(
-            read_f32(&msg.data, idx + x_offset) as f64,
-            read_f32(&msg.data, idx + y_offset) as f64,
-            read_f32(&msg.data, idx + z_offset) as f64,
+            read_field(msg, idx, x_field),
+            read_field(msg, idx, y_field),
+            read_field(msg, idx, z_field),
         ));
     }
     points
 }
 
+/// Colorizes the points according to their RGB values, given the indices of the kept points in
+/// the cloud.
 pub fn colorize_from_rgb(
     mut points: Vec<ColoredPoint>,
+    indices: &[u32],
     msg: &rosrust_msg::sensor_msgs::PointCloud2,
 ) -> Vec<ColoredPoint> {
-    let n_pts = msg.width * msg.height;
-    let rgb_offset = get_channel_offset("rgb", &msg.fields);
-    for i in 0..n_pts {
+    let rgb_offset = get_channel("rgb", &msg.fields).offset;
+    for (pt, i) in points.iter_mut().zip(indices) {
         let idx = i * msg.point_step + rgb_offset;
-        points[i as usize].color = Color::Rgb(
+        pt.color = Color::Rgb(
             msg.data[(idx + 2) as usize],
             msg.data[(idx + 1) as usize],
             msg.data[idx as usize],
@@ -80,10 +126,48 @@ pub fn colorize_from_rgb(
     points
 }
 
-pub fn colorize_points(mut points: Vec<ColoredPoint>, min_z: f64, max_z: f64) -> Vec<ColoredPoint> {
-    let grad = colorgrad::turbo();
-    for pt in points.iter_mut() {
-        let c = grad.at((pt.point.z - min_z) / (max_z - min_z)).to_rgba8();
+/// Returns the gradient of the given name, or turbo if it is unknown.
+pub fn gradient(name: &str) -> colorgrad::Gradient {
+    match name {
+        "viridis" => colorgrad::viridis(),
+        "plasma" => colorgrad::plasma(),
+        "inferno" => colorgrad::inferno(),
+        "magma" => colorgrad::magma(),
+        "cividis" => colorgrad::cividis(),
+        "rainbow" => colorgrad::rainbow(),
+        "sinebow" => colorgrad::sinebow(),
+        "spectral" => colorgrad::spectral(),
+        _ => colorgrad::turbo(),
+    }
+}
+
+/// Returns the value of the point mapped to the gradient, given the point in the frame of the
+/// sensor and in the static frame.
+fn axis_value(axis: &str, sensor_pt: &Point3<f64>, static_pt: &Point3<f64>) -> f64 {
+    match axis {
+        "x" => static_pt.x,
+        "y" => static_pt.y,
+        "distance" => sensor_pt.coords.norm(),
+        _ => static_pt.z,
+    }
+}
+
+/// Maps the values of the points through the gradient, from min to max. Values out of the
+/// bounds get the color of the closest one.
+pub fn colorize_points(
+    mut points: Vec<ColoredPoint>,
+    values: &[f64],
+    min: f64,
+    max: f64,
+    grad: &colorgrad::Gradient,
+) -> Vec<ColoredPoint> {
+    for (pt, value) in points.iter_mut().zip(values) {
+        let t = if max > min {
+            ((value - min) / (max - min)).max(0.0).min(1.0)
+        } else {
+            0.5
+        };
+        let c = grad.at(t).to_rgba8();
         pt.color = Color::Rgb(c[0], c[1], c[2]);
     }
     points
@@ -92,15 +176,23 @@ pub fn colorize_points(mut points: Vec<ColoredPoint>, min_z: f64, max_z: f64) ->
 impl PointCloud2Listener {
     pub fn new(
         config: PointCloud2ListenerConfig,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
     ) -> PointCloud2Listener {
-        let occ_points = Arc::new(RwLock::new(Vec::<ColoredPoint>::new()));
-        let cb_occ_points = occ_points.clone();
+        let clouds = Arc::new(RwLock::new(VecDeque::<CloudPoints>::new()));
+        let cb_clouds = clouds.clone();
+        let accumulation_time = Duration::from_secs_f64(config.accumulation_time.max(0.0));
+        let decimation = config.decimation.max(1);
+        let voxel_size = config.voxel_size;
         let str_ = static_frame.clone();
         let local_listener = tf_listener.clone();
         let use_rgb = config.use_rgb.clone();
-        let _sub = rosrust::subscribe(
+        let color_axis = config.color_axis.clone();
+        let color_field = config.color_field.clone();
+        let color_min = config.color_min;
+        let color_max = config.color_max;
+        let grad = gradient(&config.gradient);
+        let _sub = source::subscribe(
             &config.topic,
             1,
             move |cloud: rosrust_msg::sensor_msgs::PointCloud2| {
@@ -116,39 +208,96 @@ impl PointCloud2Listener {
                 };
 
                 let isometry = ros_transform_to_isometry(&res.unwrap().transform);
-                let mut max_z = f64::MIN;
-                let mut min_z = f64::MAX;
-                for pt in read_xyz(&cloud) {
+                // The color axis is used if the cloud does not have the color field
+                let field = color_field
+                    .as_ref()
+                    .and_then(|field| find_channel(field, &cloud.fields));
+                let mut values: Vec<f64> = Vec::new();
+                let mut indices: Vec<u32> = Vec::new();
+                let mut voxels: HashSet<(i64, i64, i64)> = HashSet::new();
+                let mut max = f64::MIN;
+                let mut min = f64::MAX;
+                for (i, pt) in read_xyz(&cloud).into_iter().enumerate().step_by(decimation) {
                     let trans_pt = isometry.transform_point(&pt);
-                    if trans_pt.z > max_z {
-                        max_z = trans_pt.z;
+                    if trans_pt.z.is_nan() {
+                        continue;
                     }
-                    if trans_pt.z < min_z {
-                        min_z = trans_pt.z;
+                    // Only the first point of each voxel is kept
+                    if voxel_size > 0.0
+                        && !voxels.insert((
+                            (trans_pt.x / voxel_size).floor() as i64,
+                            (trans_pt.y / voxel_size).floor() as i64,
+                            (trans_pt.z / voxel_size).floor() as i64,
+                        ))
+                    {
+                        continue;
                     }
+                    let value = match field {
+                        Some(field) => read_field(&cloud, i as u32 * cloud.point_step, field),
+                        None => axis_value(&color_axis, &pt, &trans_pt),
+                    };
+                    if value > max {
+                        max = value;
+                    }
+                    if value < min {
+                        min = value;
+                    }
+                    values.push(value);
+                    indices.push(i as u32);
                     points.push(ColoredPoint::new(Some(trans_pt), None));
                 }
                 if use_rgb {
-                    points = colorize_from_rgb(points, &cloud);
+                    points = colorize_from_rgb(points, &indices, &cloud);
                 } else {
-                    points = colorize_points(points, min_z, max_z);
+                    points = colorize_points(
+                        points,
+                        &values,
+                        color_min.unwrap_or(min),
+                        color_max.unwrap_or(max),
+                        &grad,
+                    );
+                }
+                let now = Instant::now();
+                let mut cb_clouds = cb_clouds.write().unwrap();
+                while cb_clouds.front().map_or(false, |oldest| {
+                    now.duration_since(oldest.received) >= accumulation_time
+                }) {
+                    cb_clouds.pop_front();
                 }
-                points = points
-                    .into_iter()
-                    .filter(|n| !n.point.z.is_nan())
-                    .collect::<Vec<_>>();
-                let mut cb_occ_points = cb_occ_points.write().unwrap();
-                *cb_occ_points = points;
+                cb_clouds.push_back(CloudPoints {
+                    received: now,
+                    points,
+                });
             },
         )
         .unwrap();
 
         PointCloud2Listener {
             config,
-            points: occ_points,
+            clouds,
             _tf_listener: tf_listener,
             _static_frame: static_frame.to_string(),
             _subscriber: _sub,
         }
     }
+
+    /// Returns the points of the clouds received within the accumulation time, or of the
+    /// latest cloud only if there is no accumulation time.
+    pub fn get_points(&self) -> Vec<ColoredPoint> {
+        let clouds = self.clouds.read().unwrap();
+        if self.config.accumulation_time <= 0.0 {
+            return clouds
+                .back()
+                .map(|cloud| cloud.points.clone())
+                .unwrap_or_default();
+        }
+        let now = Instant::now();
+        clouds
+            .iter()
+            .filter(|cloud| {
+                now.duration_since(cloud.received).as_secs_f64() < self.config.accumulation_time
+            })
+            .flat_map(|cloud| cloud.points.iter().cloned())
+            .collect()
+    }
 }
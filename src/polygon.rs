@@ -1,8 +1,7 @@
 use crate::{
-    config::ListenerConfigColor, transformation::ros_transform_to_isometry,
+    config::ListenerConfigColor, source, transformation::ros_transform_to_isometry,
 };
 use nalgebra::Point3;
-use rustros_tf;
 use std::sync::{Arc, RwLock};
 use tui::widgets::canvas::Line;
 use tui::style::Color;
@@ -20,13 +19,14 @@ pub struct PolygonData {
     pub polygon_stamped_msg: Option<rosrust_msg::geometry_msgs::PolygonStamped>,
     pub lines_in_static_frame: Option<Vec<Line>>,
     _color: Color,
-    _tf_listener: Arc<rustros_tf::TfListener>,
+    _tf_listener: Arc<source::TfListener>,
     _static_frame: String,
 }
 
 pub struct PolygonListener {
+    pub config: ListenerConfigColor,
     _data: Arc<RwLock<PolygonData>>,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 impl PolygonData {
@@ -75,7 +75,7 @@ impl PolygonData {
 impl PolygonListener {
     pub fn new(
         config: ListenerConfigColor,
-        tf_listener: Arc<rustros_tf::TfListener>,
+        tf_listener: Arc<source::TfListener>,
         static_frame: String,
     ) -> PolygonListener {
         let data = Arc::new(RwLock::new(PolygonData {
@@ -87,7 +87,7 @@ impl PolygonListener {
         }));
 
         let cloned_data = data.clone();
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             1,
             move |msg: rosrust_msg::geometry_msgs::PolygonStamped| {
@@ -99,6 +99,7 @@ impl PolygonListener {
         .unwrap();
 
         return PolygonListener {
+            config: config,
             _data: data,
             _subscriber: sub,
         };
@@ -1,13 +1,13 @@
-use crate::config::{Color, PoseListenerConfig};
-use crate::transformation::ros_pose_to_isometry;
+use crate::config::{Color, OdometryListenerConfig, PoseListenerConfig};
+use crate::source;
+use crate::transformation::{ros_pose_to_isometry, ros_transform_to_isometry};
 use nalgebra::geometry::{Isometry3, Point3};
+use std::f64::consts::PI;
 use std::option::Option;
 use std::sync::{Arc, RwLock};
 use tui::style;
 use tui::widgets::canvas::Line;
 
-use rosrust;
-
 fn pose_to_arrow(pose: &Isometry3<f64>, length: f64, color: &Color) -> Vec<Line> {
     let mut lines: Vec<Line> = Vec::new();
     let tui_color = style::Color::Rgb(color.r, color.g, color.b);
@@ -39,7 +39,37 @@ fn pose_to_arrow(pose: &Isometry3<f64>, length: f64, color: &Color) -> Vec<Line>
     lines
 }
 
-fn pose_to_axes(pose: &Isometry3<f64>, length: f64) -> Vec<Line> {
+/// Creates an arrow going from `start` to `end`, with a head proportional to its length.
+pub fn vector_to_arrow(start: &Point3<f64>, end: &Point3<f64>, color: style::Color) -> Vec<Line> {
+    let mut lines: Vec<Line> = Vec::new();
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+    lines.push(Line {
+        x1: start.x,
+        y1: start.y,
+        x2: end.x,
+        y2: end.y,
+        color,
+    });
+    if dx == 0.0 && dy == 0.0 {
+        return lines;
+    }
+    // Head lines go back a quarter of the length, rotated by +/- 45 degrees
+    let angle = dy.atan2(dx);
+    let head_length = (dx * dx + dy * dy).sqrt() / 4.0;
+    for head_angle in [angle + 0.75 * PI, angle - 0.75 * PI] {
+        lines.push(Line {
+            x1: end.x,
+            y1: end.y,
+            x2: end.x + head_length * head_angle.cos(),
+            y2: end.y + head_length * head_angle.sin(),
+            color,
+        });
+    }
+    lines
+}
+
+pub fn pose_to_axes(pose: &Isometry3<f64>, length: f64) -> Vec<Line> {
     let mut lines: Vec<Line> = Vec::new();
     let origin = pose.transform_point(&Point3::new(0.0, 0.0, 0.0));
     let x_axis = pose.transform_point(&Point3::new(length, 0.0, 0.0));
@@ -87,16 +117,16 @@ fn poses_to_lines(poses: &Vec<Isometry3<f64>>, color: &Color) -> Vec<Line> {
 }
 
 pub struct PoseStampedListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     pose: Arc<RwLock<Option<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 impl PoseStampedListener {
     pub fn new(config: PoseListenerConfig) -> PoseStampedListener {
         let pose = Arc::new(RwLock::new(None));
         let cb_pose = pose.clone();
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             2,
             move |pose_msg: rosrust_msg::geometry_msgs::PoseStamped| {
@@ -126,16 +156,16 @@ impl PoseStampedListener {
 }
 
 pub struct PoseArrayListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     poses: Arc<RwLock<Vec<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 impl PoseArrayListener {
     pub fn new(config: PoseListenerConfig) -> PoseArrayListener {
         let poses = Arc::new(RwLock::new(Vec::<Isometry3<f64>>::new()));
         let cb_poses = poses.clone();
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             2,
             move |pose_array: rosrust_msg::geometry_msgs::PoseArray| {
@@ -187,16 +217,16 @@ impl PoseArrayListener {
 }
 
 pub struct PathListener {
-    config: PoseListenerConfig,
+    pub config: PoseListenerConfig,
     poses: Arc<RwLock<Vec<Isometry3<f64>>>>,
-    _subscriber: rosrust::Subscriber,
+    _subscriber: source::Subscriber,
 }
 
 impl PathListener {
     pub fn new(config: PoseListenerConfig) -> PathListener {
         let poses = Arc::new(RwLock::new(Vec::<Isometry3<f64>>::new()));
         let cb_poses = poses.clone();
-        let sub = rosrust::subscribe(
+        let sub = source::subscribe(
             &config.topic,
             2,
             move |path: rosrust_msg::nav_msgs::Path| {
@@ -247,3 +277,186 @@ impl PathListener {
         }
     }
 }
+
+/// Odometry received in the static frame, with the linear velocity
+/// expressed as the end point of the twist vector.
+struct OdometryData {
+    pose: Isometry3<f64>,
+    twist_end: Point3<f64>,
+}
+
+pub struct OdometryListener {
+    pub config: OdometryListenerConfig,
+    odometry: Arc<RwLock<Option<OdometryData>>>,
+    _subscriber: source::Subscriber,
+}
+
+impl OdometryListener {
+    pub fn new(
+        config: OdometryListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> OdometryListener {
+        let odometry = Arc::new(RwLock::new(None));
+        let cb_odometry = odometry.clone();
+        let sub = source::subscribe(
+            &config.topic,
+            2,
+            move |odom_msg: rosrust_msg::nav_msgs::Odometry| {
+                let res = tf_listener.lookup_transform(
+                    &static_frame,
+                    &odom_msg.header.frame_id,
+                    odom_msg.header.stamp,
+                );
+                let transform = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                let pose = transform * ros_pose_to_isometry(&odom_msg.pose.pose);
+                // The twist is expressed in the child frame, i.e. relative to the pose
+                let twist_end = pose.transform_point(&Point3::new(
+                    odom_msg.twist.twist.linear.x,
+                    odom_msg.twist.twist.linear.y,
+                    0.0,
+                ));
+                *cb_odometry.write().unwrap() = Some(OdometryData { pose, twist_end });
+            },
+        )
+        .unwrap();
+
+        OdometryListener {
+            config: config,
+            odometry: odometry,
+            _subscriber: sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match &*self.odometry.read().unwrap() {
+            Some(odom) => {
+                let mut lines = match self.config.style.as_str() {
+                    "arrow" => pose_to_arrow(&odom.pose, self.config.length, &self.config.color),
+                    "axis" => pose_to_axes(&odom.pose, self.config.length),
+                    _ => Vec::new(),
+                };
+                if self.config.show_twist {
+                    let origin = odom.pose.transform_point(&Point3::new(0.0, 0.0, 0.0));
+                    lines.extend(vector_to_arrow(
+                        &origin,
+                        &odom.twist_end,
+                        self.config.color.to_tui(),
+                    ));
+                }
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Number of segments used to approximate the covariance ellipse.
+const ELLIPSE_SEGMENTS: usize = 24;
+
+/// Computes the outline of the ellipse representing one standard deviation
+/// of the x/y covariance, centered on the position of the pose.
+///
+/// # Arguments
+/// - `covariance`: the row-major 6x6 covariance matrix of the pose.
+fn covariance_to_ellipse(pose: &Isometry3<f64>, covariance: &[f64]) -> Vec<Point3<f64>> {
+    let (var_x, cov_xy, var_y) = (covariance[0], covariance[1], covariance[7]);
+    // Eigen decomposition of the symmetric 2x2 matrix
+    let mean = (var_x + var_y) / 2.0;
+    let delta = (((var_x - var_y) / 2.0).powi(2) + cov_xy.powi(2)).sqrt();
+    let major = (mean + delta).max(0.0).sqrt();
+    let minor = (mean - delta).max(0.0).sqrt();
+    let angle = 0.5 * (2.0 * cov_xy).atan2(var_x - var_y);
+
+    let center = pose.translation.vector;
+    (0..ELLIPSE_SEGMENTS + 1)
+        .map(|i| {
+            let t = 2.0 * PI * i as f64 / ELLIPSE_SEGMENTS as f64;
+            let (x, y) = (major * t.cos(), minor * t.sin());
+            Point3::new(
+                center.x + x * angle.cos() - y * angle.sin(),
+                center.y + x * angle.sin() + y * angle.cos(),
+                center.z,
+            )
+        })
+        .collect()
+}
+
+/// Pose with covariance received in the static frame.
+struct PoseWithCovarianceData {
+    pose: Isometry3<f64>,
+    ellipse: Vec<Point3<f64>>,
+}
+
+pub struct PoseWithCovarianceListener {
+    pub config: PoseListenerConfig,
+    data: Arc<RwLock<Option<PoseWithCovarianceData>>>,
+    _subscriber: source::Subscriber,
+}
+
+impl PoseWithCovarianceListener {
+    pub fn new(
+        config: PoseListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> PoseWithCovarianceListener {
+        let data = Arc::new(RwLock::new(None));
+        let cb_data = data.clone();
+        let sub = source::subscribe(
+            &config.topic,
+            2,
+            move |pose_msg: rosrust_msg::geometry_msgs::PoseWithCovarianceStamped| {
+                let res = tf_listener.lookup_transform(
+                    &static_frame,
+                    &pose_msg.header.frame_id,
+                    pose_msg.header.stamp,
+                );
+                let transform = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                // The covariance is expressed in the frame of the message
+                let pose = ros_pose_to_isometry(&pose_msg.pose.pose);
+                let ellipse = covariance_to_ellipse(&pose, &pose_msg.pose.covariance)
+                    .iter()
+                    .map(|pt| transform.transform_point(pt))
+                    .collect();
+                *cb_data.write().unwrap() = Some(PoseWithCovarianceData {
+                    pose: transform * pose,
+                    ellipse,
+                });
+            },
+        )
+        .unwrap();
+
+        PoseWithCovarianceListener {
+            config: config,
+            data: data,
+            _subscriber: sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match &*self.data.read().unwrap() {
+            Some(data) => {
+                let mut lines = match self.config.style.as_str() {
+                    "arrow" => pose_to_arrow(&data.pose, self.config.length, &self.config.color),
+                    "axis" => pose_to_axes(&data.pose, self.config.length),
+                    _ => Vec::new(),
+                };
+                lines.extend(data.ellipse.windows(2).map(|w| Line {
+                    x1: w[0].x,
+                    y1: w[0].y,
+                    x2: w[1].x,
+                    y2: w[1].y,
+                    color: self.config.color.to_tui(),
+                }));
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use crate::config::ListenerConfigColor;
+use crate::source;
+use crate::transformation;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+/// Number of segments used to approximate the arc at the end of the cone.
+const ARC_SEGMENTS: usize = 10;
+
+/// Creates the outline of the cone of a range sensor, in the sensor frame.
+fn get_cone_points(range: f64, field_of_view: f64) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = vec![(0.0, 0.0)];
+    let step = field_of_view / ARC_SEGMENTS as f64;
+    for i in 0..ARC_SEGMENTS + 1 {
+        let angle = -field_of_view / 2.0 + i as f64 * step;
+        points.push((range * angle.cos(), range * angle.sin()));
+    }
+    points.push((0.0, 0.0));
+    points
+}
+
+pub struct RangeListener {
+    pub config: ListenerConfigColor,
+    pub lines: Arc<RwLock<Vec<Line>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl RangeListener {
+    pub fn new(
+        config: ListenerConfigColor,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> RangeListener {
+        let cone_lines = Arc::new(RwLock::new(Vec::<Line>::new()));
+        let cb_cone_lines = cone_lines.clone();
+        let str_ = static_frame.clone();
+        let color = config.color.to_tui();
+
+        let local_listener = tf_listener.clone();
+        let range_sub = source::subscribe(
+            &config.topic,
+            2,
+            move |range_msg: rosrust_msg::sensor_msgs::Range| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &range_msg.header.frame_id,
+                    range_msg.header.stamp,
+                );
+                let transform = match &res {
+                    Ok(res) => &res.transform,
+                    Err(_e) => return,
+                };
+                // Readings above the maximum (e.g. +Inf) mean that nothing was detected
+                // in the cone, which is shown as a cone of maximum length.
+                let range = if range_msg.range.is_finite() {
+                    range_msg.range.min(range_msg.max_range)
+                } else {
+                    range_msg.max_range
+                };
+                let mut lines: Vec<Line> = Vec::new();
+                if range >= range_msg.min_range {
+                    let points: Vec<(f64, f64)> =
+                        get_cone_points(range as f64, range_msg.field_of_view as f64)
+                            .into_iter()
+                            .map(|pt| transformation::transform_relative_pt(transform, pt))
+                            .collect();
+                    for w in points.windows(2) {
+                        lines.push(Line {
+                            x1: w[0].0,
+                            y1: w[0].1,
+                            x2: w[1].0,
+                            y2: w[1].1,
+                            color,
+                        });
+                    }
+                }
+                let mut cb_cone_lines = cb_cone_lines.write().unwrap();
+                *cb_cone_lines = lines;
+            },
+        )
+        .unwrap();
+
+        RangeListener {
+            config,
+            lines: cone_lines,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: range_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        self.lines.read().unwrap().to_vec()
+    }
+}
@@ -0,0 +1,202 @@
+//! Module dealing with the log messages published by the nodes as rosgraph_msgs::Log.
+//!
+//! The messages are stored in a ring buffer of fixed size, so that the oldest ones
+//! are discarded when it is full.
+use crate::config::LogsConfig;
+use crate::source;
+use regex::Regex;
+use rosrust;
+use rosrust_msg::rosgraph_msgs::Log;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the name of a severity level.
+pub fn level_name(level: i8) -> &'static str {
+    if level == Log::DEBUG {
+        "DEBUG"
+    } else if level == Log::INFO {
+        "INFO"
+    } else if level == Log::WARN {
+        "WARN"
+    } else if level == Log::ERROR {
+        "ERROR"
+    } else if level == Log::FATAL {
+        "FATAL"
+    } else {
+        "UNKNOWN"
+    }
+}
+
+/// Returns the severity level with the given name, if any.
+pub fn level_from_name(name: &str) -> Option<i8> {
+    match name.to_uppercase().as_str() {
+        "DEBUG" => Some(Log::DEBUG),
+        "INFO" => Some(Log::INFO),
+        "WARN" => Some(Log::WARN),
+        "ERROR" => Some(Log::ERROR),
+        "FATAL" => Some(Log::FATAL),
+        _ => None,
+    }
+}
+
+/// Severity levels, in increasing order.
+pub const LEVELS: [i8; 5] = [Log::DEBUG, Log::INFO, Log::WARN, Log::ERROR, Log::FATAL];
+
+/// Filter on the displayed log messages.
+#[derive(Clone)]
+pub struct LogFilter {
+    pub min_level: i8,
+    /// Only the messages of the nodes whose name contains this string are shown.
+    pub node: String,
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        entry.level >= self.min_level && entry.node.contains(&self.node)
+    }
+}
+
+#[derive(Clone)]
+pub struct LogEntry {
+    pub stamp: rosrust::Time,
+    pub level: i8,
+    pub node: String,
+    pub msg: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{:5}] [{}.{:09}] [{}]: {}",
+            level_name(self.level),
+            self.stamp.sec,
+            self.stamp.nsec,
+            self.node,
+            self.msg
+        )
+    }
+}
+
+pub struct LogBuffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> LogBuffer {
+        LogBuffer {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Adds an entry, discarding the oldest one if the buffer is full.
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Iterates over the entries, from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    /// Iterates over the entries matching the filter, from the oldest to the newest.
+    pub fn filtered<'a>(&'a self, filter: &'a LogFilter) -> impl Iterator<Item = &'a LogEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| filter.matches(entry))
+    }
+
+    /// Iterates over the entries matching the filter, along with the byte ranges
+    /// of their message matching the pattern.
+    pub fn search<'a>(
+        &'a self,
+        filter: &'a LogFilter,
+        pattern: &'a Regex,
+    ) -> impl Iterator<Item = (&'a LogEntry, Vec<Range<usize>>)> {
+        self.filtered(filter).map(move |entry| {
+            let matches = pattern.find_iter(&entry.msg).map(|m| m.range()).collect();
+            (entry, matches)
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Writes all the entries as plain text to a timestamped file in the current directory.
+    /// Returns the name of the file.
+    pub fn export(&self) -> io::Result<String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let filename = format!("termviz_logs_{}.log", now.as_secs());
+        let mut file = BufWriter::new(File::create(&filename)?);
+        for entry in self.entries.iter() {
+            writeln!(file, "{}", entry)?;
+        }
+        file.flush()?;
+        Ok(filename)
+    }
+}
+
+pub struct RosoutListener {
+    pub config: LogsConfig,
+    pub buffer: Arc<RwLock<LogBuffer>>,
+    buffering: Arc<AtomicBool>,
+    _subscriber: source::Subscriber,
+}
+
+impl RosoutListener {
+    pub fn new(config: LogsConfig) -> RosoutListener {
+        let buffer = Arc::new(RwLock::new(LogBuffer::new(config.buffer_size)));
+        let buffering = Arc::new(AtomicBool::new(true));
+        let cb_buffer = buffer.clone();
+        let cb_buffering = buffering.clone();
+
+        // All the messages are stored, so that changing the filter also applies to the old ones
+        let sub = source::subscribe(&config.topic, 100, move |log: Log| {
+            if !cb_buffering.load(Ordering::Relaxed) {
+                return;
+            }
+            cb_buffer.write().unwrap().push(LogEntry {
+                stamp: log.header.stamp,
+                level: log.level,
+                node: log.name,
+                msg: log.msg,
+            });
+        })
+        .unwrap();
+
+        RosoutListener {
+            config,
+            buffer,
+            buffering,
+            _subscriber: sub,
+        }
+    }
+
+    /// Stops or restarts storing the received messages. Returns true if buffering.
+    pub fn toggle_buffering(&self) -> bool {
+        let buffering = !self.buffering.load(Ordering::Relaxed);
+        self.buffering.store(buffering, Ordering::Relaxed);
+        buffering
+    }
+
+    pub fn is_buffering(&self) -> bool {
+        self.buffering.load(Ordering::Relaxed)
+    }
+}
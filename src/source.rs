@@ -0,0 +1,330 @@
+//! Source of the messages received by the listeners.
+//!
+//! The messages normally come from the ROS master through rosrust. When bags are given on the
+//! command line, they are instead read directly from the files and passed to the callbacks of
+//! the subscribers, so no ROS master is needed. TF is then built from the tf and tf_static
+//! messages of the bags.
+use crate::transformation::{isometry_to_ros, ros_transform_to_isometry};
+use nalgebra::geometry::Isometry3;
+use once_cell::sync::OnceCell;
+use rosbag::{ChunkRecord, IndexRecord, MessageRecord, RosBag};
+use rosrust;
+use rosrust::RosMsg;
+use rosrust_msg::geometry_msgs::TransformStamped;
+use rosrust_msg::std_msgs::Header;
+use rosrust_msg::tf2_msgs::TFMessage;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Set when reading bags, in which case no ROS master is used.
+static BAGS: OnceCell<Bags> = OnceCell::new();
+
+struct Subscription {
+    id: usize,
+    md5sum: String,
+    callback: Box<dyn Fn(&[u8]) + Send>,
+}
+
+struct Bags {
+    /// Subscriptions of the listeners, by topic.
+    subscriptions: Mutex<HashMap<String, Vec<Subscription>>>,
+    /// Topics found in the bags, with their type.
+    topics: Vec<(String, String)>,
+    next_id: AtomicUsize,
+    /// Time of the last message read, in nanoseconds.
+    time: AtomicU64,
+}
+
+fn normalize(name: &str) -> String {
+    name.trim_start_matches('/').to_string()
+}
+
+/// Returns true if the messages are read from bags instead of a ROS master.
+pub fn reading_bags() -> bool {
+    BAGS.get().is_some()
+}
+
+/// Returns the names and types of the topics advertised on the master or found in the bags.
+pub fn topics() -> Vec<(String, String)> {
+    match BAGS.get() {
+        Some(bags) => bags
+            .topics
+            .iter()
+            .map(|(name, datatype)| (format!("/{}", name), datatype.clone()))
+            .collect(),
+        None => rosrust::topics()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|topic| (topic.name, topic.datatype))
+            .collect(),
+    }
+}
+
+/// Returns the type of the topic, as advertised on the master or found in the bags.
+pub fn topic_type(topic: &str) -> Option<String> {
+    let name = normalize(topic);
+    topics()
+        .into_iter()
+        .find(|(topic, _)| normalize(topic) == name)
+        .map(|(_, datatype)| datatype)
+}
+
+/// Subscription to a topic, which is unsubscribed when dropped.
+pub enum Subscriber {
+    Ros(rosrust::Subscriber),
+    Bag(usize),
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        if let Subscriber::Bag(id) = self {
+            if let Some(bags) = BAGS.get() {
+                for subscriptions in bags.subscriptions.lock().unwrap().values_mut() {
+                    subscriptions.retain(|subscription| subscription.id != *id);
+                }
+            }
+        }
+    }
+}
+
+/// Subscribes to a topic, like rosrust::subscribe, on the master or in the bags.
+pub fn subscribe<T, F>(
+    topic: &str,
+    queue_size: usize,
+    callback: F,
+) -> rosrust::error::Result<Subscriber>
+where
+    T: rosrust::Message,
+    F: Fn(T) + Send + 'static,
+{
+    let bags = match BAGS.get() {
+        Some(bags) => bags,
+        None => return rosrust::subscribe(topic, queue_size, callback).map(Subscriber::Ros),
+    };
+    let id = bags.next_id.fetch_add(1, Ordering::Relaxed);
+    let subscription = Subscription {
+        id,
+        md5sum: T::md5sum(),
+        callback: Box::new(move |data| {
+            if let Ok(msg) = T::decode_slice(data) {
+                callback(msg);
+            }
+        }),
+    };
+    bags.subscriptions
+        .lock()
+        .unwrap()
+        .entry(normalize(topic))
+        .or_default()
+        .push(subscription);
+    Ok(Subscriber::Bag(id))
+}
+
+/// Transforms read from the bags, by child frame.
+#[derive(Default)]
+struct TfTree {
+    transforms: HashMap<String, (String, Isometry3<f64>)>,
+}
+
+impl TfTree {
+    fn insert(&mut self, msg: TFMessage) {
+        for tf in msg.transforms {
+            self.transforms.insert(
+                normalize(&tf.child_frame_id),
+                (
+                    normalize(&tf.header.frame_id),
+                    ros_transform_to_isometry(&tf.transform),
+                ),
+            );
+        }
+    }
+
+    /// Returns the frames from the given one up to the root of its tree,
+    /// with the pose of the given frame in each of them.
+    fn chain(&self, frame: &str) -> Vec<(String, Isometry3<f64>)> {
+        let mut chain = vec![(frame.to_string(), Isometry3::identity())];
+        // The length limit protects from loops in the transforms
+        while chain.len() <= self.transforms.len() {
+            let (child, pose) = chain.last().unwrap();
+            match self.transforms.get(child) {
+                Some((parent, tf)) => {
+                    let pose = tf * pose;
+                    chain.push((parent.clone(), pose));
+                }
+                None => break,
+            }
+        }
+        chain
+    }
+
+    /// Returns the pose of the frame `to` in the frame `from`.
+    fn lookup(&self, from: &str, to: &str) -> Option<Isometry3<f64>> {
+        let from_chain = self.chain(from);
+        self.chain(to).into_iter().find_map(|(frame, to_pose)| {
+            from_chain
+                .iter()
+                .find(|(other, _)| *other == frame)
+                .map(|(_, from_pose)| from_pose.inverse() * to_pose)
+        })
+    }
+}
+
+/// TF listener on the master or in the bags.
+pub struct TfListener {
+    ros: Option<rustros_tf::TfListener>,
+    tree: Arc<RwLock<TfTree>>,
+    _subscribers: Vec<Subscriber>,
+}
+
+impl TfListener {
+    pub fn new() -> TfListener {
+        let tree = Arc::new(RwLock::new(TfTree::default()));
+        if !reading_bags() {
+            return TfListener {
+                ros: Some(rustros_tf::TfListener::new()),
+                tree,
+                _subscribers: Vec::new(),
+            };
+        }
+        let subscribers = ["tf", "tf_static"]
+            .iter()
+            .map(|topic| {
+                let cb_tree = tree.clone();
+                subscribe(topic, 100, move |msg: TFMessage| {
+                    cb_tree.write().unwrap().insert(msg);
+                })
+                .unwrap()
+            })
+            .collect();
+        TfListener {
+            ros: None,
+            tree,
+            _subscribers: subscribers,
+        }
+    }
+
+    /// Looks up the pose of the frame `to` in the frame `from`.
+    /// From the bags, the latest transforms are always used.
+    pub fn lookup_transform(
+        &self,
+        from: &str,
+        to: &str,
+        time: rosrust::Time,
+    ) -> Result<TransformStamped, String> {
+        if let Some(listener) = &self.ros {
+            return listener
+                .lookup_transform(from, to, time)
+                .map_err(|e| format!("{:?}", e));
+        }
+        let pose = self
+            .tree
+            .read()
+            .unwrap()
+            .lookup(&normalize(from), &normalize(to))
+            .ok_or(format!("No transform from {} to {}", from, to))?;
+        let nanos = BAGS
+            .get()
+            .map_or(0, |bags| bags.time.load(Ordering::Relaxed));
+        Ok(TransformStamped {
+            header: Header {
+                frame_id: from.to_string(),
+                stamp: rosrust::Time::from_nanos(nanos as i64),
+                ..Default::default()
+            },
+            child_frame_id: to.to_string(),
+            transform: isometry_to_ros(&pose),
+        })
+    }
+}
+
+/// Bags opened as the source of the messages, to be played once the listeners are subscribed.
+pub struct BagReader {
+    bags: Vec<RosBag>,
+}
+
+/// Opens the bags and makes them the source of the messages instead of the ROS master.
+pub fn open_bags(paths: &[String]) -> Result<BagReader, Box<dyn Error>> {
+    let mut bags = Vec::new();
+    let mut topics = Vec::new();
+    for path in paths {
+        let bag = RosBag::new(path).map_err(|e| format!("Could not open {}: {}", path, e))?;
+        for record in bag.index_records() {
+            if let IndexRecord::Connection(connection) = record? {
+                let topic = (normalize(connection.topic), connection.tp.to_string());
+                // The connections of a topic are repeated across the bags
+                if !topics.contains(&topic) {
+                    topics.push(topic);
+                }
+            }
+        }
+        bags.push(bag);
+    }
+    let _ = BAGS.set(Bags {
+        subscriptions: Mutex::new(HashMap::new()),
+        topics,
+        next_id: AtomicUsize::new(0),
+        time: AtomicU64::new(0),
+    });
+    Ok(BagReader { bags })
+}
+
+impl BagReader {
+    /// Plays the bags one after the other in a background thread,
+    /// passing the messages to the subscribers at the pace they were recorded.
+    pub fn play(self) {
+        let source = BAGS.get().unwrap();
+        std::thread::spawn(move || {
+            for bag in self.bags {
+                // A corrupted bag ends the playback
+                if play_bag(source, &bag).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+fn play_bag(source: &Bags, bag: &RosBag) -> Result<(), rosbag::Error> {
+    let mut connections = HashMap::new();
+    let mut start = None;
+    for record in bag.chunk_records() {
+        let chunk = match record? {
+            ChunkRecord::Chunk(chunk) => chunk,
+            _ => continue,
+        };
+        for message in chunk.messages() {
+            match message? {
+                MessageRecord::Connection(connection) => {
+                    connections.insert(
+                        connection.id,
+                        (normalize(connection.topic), connection.md5sum.to_string()),
+                    );
+                }
+                MessageRecord::MessageData(data) => {
+                    let (started, first_time) = *start.get_or_insert((Instant::now(), data.time));
+                    let due = started + Duration::from_nanos(data.time.saturating_sub(first_time));
+                    if let Some(delay) = due.checked_duration_since(Instant::now()) {
+                        std::thread::sleep(delay);
+                    }
+                    source.time.store(data.time, Ordering::Relaxed);
+                    let (topic, md5sum) = match connections.get(&data.conn_id) {
+                        Some(connection) => connection,
+                        None => continue,
+                    };
+                    if let Some(subscriptions) = source.subscriptions.lock().unwrap().get(topic) {
+                        for subscription in subscriptions.iter().filter(|subscription| {
+                            // Raw messages are received whatever their type
+                            subscription.md5sum == *md5sum || subscription.md5sum == "*"
+                        }) {
+                            (subscription.callback)(data.data);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
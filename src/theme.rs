@@ -0,0 +1,220 @@
+//! Colors of the user interface, taken from a built-in preset and overridden by the config.
+
+use crate::config::{Color as ConfigColor, ThemeConfig};
+use tui::style::Color;
+
+#[derive(Clone)]
+pub struct Theme {
+    /// Names of the modes and titles of the pages.
+    pub title: Color,
+    /// Regular text.
+    pub text: Color,
+    /// Selected items and table headers.
+    pub highlight: Color,
+    /// Cursors and values standing out of the text.
+    pub accent: Color,
+    pub ok: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Stale or disabled items, and construction lines in the viewport.
+    pub inactive: Color,
+    /// Colors given in turn to the topics added at runtime.
+    pub layer_colors: Vec<ConfigColor>,
+}
+
+impl Theme {
+    /// The historical colors, meant for dark terminals.
+    pub fn dark() -> Theme {
+        Theme {
+            title: Color::Red,
+            text: Color::White,
+            highlight: Color::Yellow,
+            accent: Color::Cyan,
+            ok: Color::Green,
+            warning: Color::Yellow,
+            error: Color::Red,
+            inactive: Color::DarkGray,
+            layer_colors: vec![
+                ConfigColor { r: 255, g: 0, b: 0 },
+                ConfigColor { r: 0, g: 255, b: 0 },
+                ConfigColor {
+                    r: 0,
+                    g: 128,
+                    b: 255,
+                },
+                ConfigColor {
+                    r: 255,
+                    g: 255,
+                    b: 0,
+                },
+                ConfigColor {
+                    r: 0,
+                    g: 255,
+                    b: 255,
+                },
+                ConfigColor {
+                    r: 255,
+                    g: 0,
+                    b: 255,
+                },
+            ],
+        }
+    }
+
+    pub fn light() -> Theme {
+        Theme {
+            title: Color::Red,
+            text: Color::Black,
+            highlight: Color::Blue,
+            accent: Color::Magenta,
+            ok: Color::Rgb(0, 128, 0),
+            warning: Color::Rgb(176, 112, 0),
+            error: Color::Red,
+            inactive: Color::Gray,
+            layer_colors: vec![
+                ConfigColor { r: 200, g: 0, b: 0 },
+                ConfigColor { r: 0, g: 128, b: 0 },
+                ConfigColor { r: 0, g: 0, b: 200 },
+                ConfigColor {
+                    r: 176,
+                    g: 112,
+                    b: 0,
+                },
+                ConfigColor {
+                    r: 0,
+                    g: 128,
+                    b: 128,
+                },
+                ConfigColor {
+                    r: 160,
+                    g: 0,
+                    b: 160,
+                },
+            ],
+        }
+    }
+
+    /// The accent colors of the Solarized palette, which read on both dark and light terminals.
+    pub fn solarized() -> Theme {
+        Theme {
+            title: Color::Rgb(203, 75, 22),
+            text: Color::Rgb(147, 161, 161),
+            highlight: Color::Rgb(181, 137, 0),
+            accent: Color::Rgb(42, 161, 152),
+            ok: Color::Rgb(133, 153, 0),
+            warning: Color::Rgb(181, 137, 0),
+            error: Color::Rgb(220, 50, 47),
+            inactive: Color::Rgb(88, 110, 117),
+            layer_colors: vec![
+                ConfigColor {
+                    r: 220,
+                    g: 50,
+                    b: 47,
+                },
+                ConfigColor {
+                    r: 133,
+                    g: 153,
+                    b: 0,
+                },
+                ConfigColor {
+                    r: 38,
+                    g: 139,
+                    b: 210,
+                },
+                ConfigColor {
+                    r: 181,
+                    g: 137,
+                    b: 0,
+                },
+                ConfigColor {
+                    r: 42,
+                    g: 161,
+                    b: 152,
+                },
+                ConfigColor {
+                    r: 211,
+                    g: 54,
+                    b: 130,
+                },
+            ],
+        }
+    }
+
+    /// The Okabe-Ito palette, which avoids telling states apart by red and green only.
+    pub fn colorblind() -> Theme {
+        Theme {
+            title: Color::Rgb(213, 94, 0),
+            text: Color::White,
+            highlight: Color::Rgb(240, 228, 66),
+            accent: Color::Rgb(86, 180, 233),
+            ok: Color::Rgb(0, 114, 178),
+            warning: Color::Rgb(230, 159, 0),
+            error: Color::Rgb(213, 94, 0),
+            inactive: Color::DarkGray,
+            layer_colors: vec![
+                ConfigColor {
+                    r: 230,
+                    g: 159,
+                    b: 0,
+                },
+                ConfigColor {
+                    r: 86,
+                    g: 180,
+                    b: 233,
+                },
+                ConfigColor {
+                    r: 0,
+                    g: 158,
+                    b: 115,
+                },
+                ConfigColor {
+                    r: 240,
+                    g: 228,
+                    b: 66,
+                },
+                ConfigColor {
+                    r: 0,
+                    g: 114,
+                    b: 178,
+                },
+                ConfigColor {
+                    r: 204,
+                    g: 121,
+                    b: 167,
+                },
+            ],
+        }
+    }
+
+    /// Creates the preset of the config, with the colors set in the config replacing its own.
+    pub fn from_config(config: &ThemeConfig) -> Theme {
+        let mut theme = match config.preset.as_str() {
+            "light" => Theme::light(),
+            "solarized" => Theme::solarized(),
+            "colorblind" => Theme::colorblind(),
+            _ => Theme::dark(),
+        };
+        let set = |color: &mut Color, custom: &Option<ConfigColor>| {
+            if let Some(custom) = custom {
+                *color = custom.to_tui();
+            }
+        };
+        set(&mut theme.title, &config.title);
+        set(&mut theme.text, &config.text);
+        set(&mut theme.highlight, &config.highlight);
+        set(&mut theme.accent, &config.accent);
+        set(&mut theme.ok, &config.ok);
+        set(&mut theme.warning, &config.warning);
+        set(&mut theme.error, &config.error);
+        set(&mut theme.inactive, &config.inactive);
+        if !config.layer_colors.is_empty() {
+            theme.layer_colors = config.layer_colors.clone();
+        }
+        theme
+    }
+
+    /// Returns the color of the n-th topic added at runtime.
+    pub fn layer_color(&self, n: usize) -> ConfigColor {
+        self.layer_colors[n % self.layer_colors.len()].clone()
+    }
+}
@@ -66,3 +66,19 @@ pub fn ros_transform_to_isometry(tf: &rosrust_msg::geometry_msgs::Transform) ->
     ));
     Isometry3::from_parts(tra, rot)
 }
+
+pub fn isometry_to_ros(tf: &Isometry3<f64>) -> rosrust_msg::geometry_msgs::Transform {
+    rosrust_msg::geometry_msgs::Transform {
+        translation: rosrust_msg::geometry_msgs::Vector3 {
+            x: tf.translation.x,
+            y: tf.translation.y,
+            z: tf.translation.z,
+        },
+        rotation: rosrust_msg::geometry_msgs::Quaternion {
+            x: tf.rotation.quaternion()[0],
+            y: tf.rotation.quaternion()[1],
+            z: tf.rotation.quaternion()[2],
+            w: tf.rotation.quaternion()[3],
+        },
+    }
+}
@@ -0,0 +1,124 @@
+use crate::config::VectorListenerConfig;
+use crate::pose::vector_to_arrow;
+use crate::source;
+use crate::transformation::ros_transform_to_isometry;
+use nalgebra::geometry::{Isometry3, Point3};
+use nalgebra::Vector3;
+use std::f64::consts::PI;
+use std::option::Option;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+/// Radius of the arc showing the angular velocity.
+const ANGULAR_ARC_RADIUS: f64 = 0.3;
+/// Number of segments of a full circle, used to approximate the arc.
+const ARC_SEGMENTS: usize = 32;
+
+/// Creates an arc around the origin of the frame, starting from its x axis and
+/// spanning the given angle (counter-clockwise if positive).
+fn angle_to_arc(frame: &Isometry3<f64>, angle: f64, color: tui::style::Color) -> Vec<Line> {
+    let angle = angle.max(-2.0 * PI).min(2.0 * PI);
+    let n_segments = ((angle.abs() / (2.0 * PI)) * ARC_SEGMENTS as f64).ceil() as usize;
+    let points: Vec<Point3<f64>> = (0..n_segments + 1)
+        .map(|i| {
+            let a = angle * i as f64 / n_segments.max(1) as f64;
+            frame.transform_point(&Point3::new(
+                ANGULAR_ARC_RADIUS * a.cos(),
+                ANGULAR_ARC_RADIUS * a.sin(),
+                0.0,
+            ))
+        })
+        .collect();
+    points
+        .windows(2)
+        .map(|w| Line {
+            x1: w[0].x,
+            y1: w[0].y,
+            x2: w[1].x,
+            y2: w[1].y,
+            color,
+        })
+        .collect()
+}
+
+/// Twist received, with the frame in which it is expressed.
+struct TwistData {
+    frame: Isometry3<f64>,
+    linear: Vector3<f64>,
+    angular_z: f64,
+}
+
+pub struct TwistStampedListener {
+    pub config: VectorListenerConfig,
+    data: Arc<RwLock<Option<TwistData>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl TwistStampedListener {
+    pub fn new(
+        config: VectorListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> TwistStampedListener {
+        let data = Arc::new(RwLock::new(None));
+        let cb_data = data.clone();
+        let str_ = static_frame.clone();
+
+        let local_listener = tf_listener.clone();
+        let twist_sub = source::subscribe(
+            &config.topic,
+            2,
+            move |twist_msg: rosrust_msg::geometry_msgs::TwistStamped| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &twist_msg.header.frame_id,
+                    twist_msg.header.stamp,
+                );
+                let frame = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                *cb_data.write().unwrap() = Some(TwistData {
+                    frame,
+                    linear: Vector3::new(
+                        twist_msg.twist.linear.x,
+                        twist_msg.twist.linear.y,
+                        twist_msg.twist.linear.z,
+                    ),
+                    angular_z: twist_msg.twist.angular.z,
+                });
+            },
+        )
+        .unwrap();
+
+        TwistStampedListener {
+            config,
+            data,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: twist_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match &*self.data.read().unwrap() {
+            Some(data) => {
+                let color = self.config.color.to_tui();
+                let origin = data.frame.transform_point(&Point3::new(0.0, 0.0, 0.0));
+                let end = data
+                    .frame
+                    .transform_point(&Point3::from(data.linear * self.config.scale));
+                let mut lines = vector_to_arrow(&origin, &end, color);
+                lines.extend(angle_to_arc(
+                    &data.frame,
+                    data.angular_z * self.config.scale,
+                    color,
+                ));
+                lines
+            }
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::config::VectorListenerConfig;
+use crate::pose::vector_to_arrow;
+use crate::source;
+use crate::transformation::ros_transform_to_isometry;
+use nalgebra::geometry::Point3;
+use std::option::Option;
+use std::sync::{Arc, RwLock};
+use tui::widgets::canvas::Line;
+
+pub struct WrenchStampedListener {
+    pub config: VectorListenerConfig,
+    /// Start and end of the force vector in the static frame.
+    force: Arc<RwLock<Option<(Point3<f64>, Point3<f64>)>>>,
+    _tf_listener: Arc<source::TfListener>,
+    _static_frame: String,
+    _subscriber: source::Subscriber,
+}
+
+impl WrenchStampedListener {
+    pub fn new(
+        config: VectorListenerConfig,
+        tf_listener: Arc<source::TfListener>,
+        static_frame: String,
+    ) -> WrenchStampedListener {
+        let force = Arc::new(RwLock::new(None));
+        let cb_force = force.clone();
+        let str_ = static_frame.clone();
+        let scale = config.scale;
+
+        let local_listener = tf_listener.clone();
+        let wrench_sub = source::subscribe(
+            &config.topic,
+            2,
+            move |wrench_msg: rosrust_msg::geometry_msgs::WrenchStamped| {
+                let res = local_listener.lookup_transform(
+                    &str_,
+                    &wrench_msg.header.frame_id,
+                    wrench_msg.header.stamp,
+                );
+                let frame = match &res {
+                    Ok(res) => ros_transform_to_isometry(&res.transform),
+                    Err(_e) => return,
+                };
+                let start = frame.transform_point(&Point3::new(0.0, 0.0, 0.0));
+                let end = frame.transform_point(&Point3::new(
+                    wrench_msg.wrench.force.x * scale,
+                    wrench_msg.wrench.force.y * scale,
+                    wrench_msg.wrench.force.z * scale,
+                ));
+                *cb_force.write().unwrap() = Some((start, end));
+            },
+        )
+        .unwrap();
+
+        WrenchStampedListener {
+            config,
+            force,
+            _tf_listener: tf_listener,
+            _static_frame: static_frame.to_string(),
+            _subscriber: wrench_sub,
+        }
+    }
+
+    pub fn get_lines(&self) -> Vec<Line> {
+        match &*self.force.read().unwrap() {
+            Some((start, end)) => vector_to_arrow(start, end, self.config.color.to_tui()),
+            None => Vec::new(),
+        }
+    }
+}